@@ -1,5 +1,10 @@
 fn main() {
     println!("cargo:rerun-if-changed=proto/event.proto");
-    prost_build::compile_protos(&["proto/event.proto"], &["proto/"]).unwrap();
+    println!("cargo:rerun-if-changed=proto/prometheus.proto");
+    prost_build::compile_protos(
+        &["proto/event.proto", "proto/prometheus.proto"],
+        &["proto/"],
+    )
+    .unwrap();
     built::write_built_file().unwrap();
 }
@@ -11,6 +11,7 @@ pub enum Metric {
         val: f64,
         timestamp: Option<DateTime<Utc>>,
         tags: Option<HashMap<String, String>>,
+        kind: MetricKind,
     },
     Histogram {
         name: String,
@@ -18,35 +19,80 @@ pub enum Metric {
         sample_rate: u32,
         timestamp: Option<DateTime<Utc>>,
         tags: Option<HashMap<String, String>>,
+        kind: MetricKind,
     },
     Gauge {
         name: String,
         val: f64,
-        direction: Option<Direction>,
         timestamp: Option<DateTime<Utc>>,
         tags: Option<HashMap<String, String>>,
+        kind: MetricKind,
     },
     Set {
         name: String,
         val: String,
         timestamp: Option<DateTime<Utc>>,
         tags: Option<HashMap<String, String>>,
+        kind: MetricKind,
+    },
+    /// A histogram aggregated at the source. `counts` holds the number of
+    /// samples in each bucket, that is no greater than its upper bound in
+    /// `buckets` and greater than the previous one. `count` includes the
+    /// samples above the largest bound.
+    AggregatedHistogram {
+        name: String,
+        buckets: Vec<f64>,
+        counts: Vec<u64>,
+        count: u64,
+        sum: f64,
+        timestamp: Option<DateTime<Utc>>,
+        tags: Option<HashMap<String, String>>,
+        kind: MetricKind,
+    },
+    /// Quantiles calculated at the source, with the number and sum of the
+    /// samples they were calculated over.
+    AggregatedSummary {
+        name: String,
+        quantiles: Vec<f64>,
+        values: Vec<f64>,
+        count: u64,
+        sum: f64,
+        timestamp: Option<DateTime<Utc>>,
+        tags: Option<HashMap<String, String>>,
+        kind: MetricKind,
     },
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-pub enum Direction {
-    Plus,
-    Minus,
+/// Whether a metric value is a change since the last observation
+/// (`Incremental`) or the current total (`Absolute`). An incremental
+/// gauge may be negative.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, is_enum_variant)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    Incremental,
+    Absolute,
 }
 
 impl Metric {
+    pub fn name(&self) -> &str {
+        match self {
+            Metric::Counter { name, .. } => name,
+            Metric::Gauge { name, .. } => name,
+            Metric::Histogram { name, .. } => name,
+            Metric::Set { name, .. } => name,
+            Metric::AggregatedHistogram { name, .. } => name,
+            Metric::AggregatedSummary { name, .. } => name,
+        }
+    }
+
     pub fn tags(&self) -> &Option<HashMap<String, String>> {
         match self {
             Metric::Counter { tags, .. } => tags,
             Metric::Gauge { tags, .. } => tags,
             Metric::Histogram { tags, .. } => tags,
             Metric::Set { tags, .. } => tags,
+            Metric::AggregatedHistogram { tags, .. } => tags,
+            Metric::AggregatedSummary { tags, .. } => tags,
         }
     }
 
@@ -56,9 +102,97 @@ impl Metric {
             Metric::Gauge { tags, .. } => tags,
             Metric::Histogram { tags, .. } => tags,
             Metric::Set { tags, .. } => tags,
+            Metric::AggregatedHistogram { tags, .. } => tags,
+            Metric::AggregatedSummary { tags, .. } => tags,
+        }
+    }
+
+    pub fn kind(&self) -> MetricKind {
+        match self {
+            Metric::Counter { kind, .. } => *kind,
+            Metric::Gauge { kind, .. } => *kind,
+            Metric::Histogram { kind, .. } => *kind,
+            Metric::Set { kind, .. } => *kind,
+            Metric::AggregatedHistogram { kind, .. } => *kind,
+            Metric::AggregatedSummary { kind, .. } => *kind,
+        }
+    }
+
+    /// Returns the change since `previous`, an earlier absolute value of
+    /// the same counter or aggregated histogram, as an incremental metric.
+    /// A value lower than the previous one means the series was reset, so
+    /// all of it is new. Other metrics can't be turned into increments.
+    pub fn to_incremental(&self, previous: &Metric) -> Option<Metric> {
+        match (self, previous) {
+            (
+                Metric::Counter {
+                    name,
+                    val,
+                    timestamp,
+                    tags,
+                    ..
+                },
+                Metric::Counter { val: last, .. },
+            ) => Some(Metric::Counter {
+                name: name.clone(),
+                val: if val >= last { val - last } else { *val },
+                timestamp: *timestamp,
+                tags: tags.clone(),
+                kind: MetricKind::Incremental,
+            }),
+            (
+                Metric::AggregatedHistogram {
+                    name,
+                    buckets,
+                    counts,
+                    count,
+                    sum,
+                    timestamp,
+                    tags,
+                    ..
+                },
+                Metric::AggregatedHistogram {
+                    buckets: previous_buckets,
+                    counts: previous_counts,
+                    count: previous_count,
+                    sum: previous_sum,
+                    ..
+                },
+            ) => {
+                let reset = buckets != previous_buckets
+                    || count < previous_count
+                    || counts.iter().zip(previous_counts).any(|(c, p)| c < p);
+                let (counts, count, sum) = if reset {
+                    (counts.clone(), *count, *sum)
+                } else {
+                    (
+                        counts
+                            .iter()
+                            .zip(previous_counts)
+                            .map(|(c, p)| c - p)
+                            .collect(),
+                        count - previous_count,
+                        sum - previous_sum,
+                    )
+                };
+                Some(Metric::AggregatedHistogram {
+                    name: name.clone(),
+                    buckets: buckets.clone(),
+                    counts,
+                    count,
+                    sum,
+                    timestamp: *timestamp,
+                    tags: tags.clone(),
+                    kind: MetricKind::Incremental,
+                })
+            }
+            _ => None,
         }
     }
 
+    /// Folds a later observation of the same metric into this one. An
+    /// incremental value is added to the current one, keeping its kind,
+    /// while an absolute value replaces it.
     pub fn merge(&mut self, other: &Metric) {
         match (self, other) {
             (
@@ -67,16 +201,24 @@ impl Metric {
                     ref mut val,
                     ref mut timestamp,
                     ref mut tags,
+                    ref mut kind,
                 },
                 Metric::Counter {
                     name: new_name,
                     val: new_val,
                     timestamp: new_timestamp,
                     tags: new_tags,
+                    kind: new_kind,
                 },
             ) => {
                 if name == new_name {
-                    *val += *new_val;
+                    match new_kind {
+                        MetricKind::Incremental => *val += *new_val,
+                        MetricKind::Absolute => {
+                            *val = *new_val;
+                            *kind = MetricKind::Absolute;
+                        }
+                    }
                     *timestamp = *new_timestamp;
                     *tags = new_tags.clone();
                 }
@@ -85,29 +227,26 @@ impl Metric {
                 Metric::Gauge {
                     ref mut name,
                     ref mut val,
-                    direction: None,
                     ref mut timestamp,
                     ref mut tags,
+                    ref mut kind,
                 },
                 Metric::Gauge {
                     name: new_name,
                     val: new_val,
                     timestamp: new_timestamp,
-                    direction: new_direction,
                     tags: new_tags,
+                    kind: new_kind,
                 },
             ) => {
                 if name == new_name {
-                    if new_direction.is_none() {
-                        *val = *new_val;
-                    } else {
-                        let delta = match new_direction {
-                            None => 0.0,
-                            Some(Direction::Plus) => *val,
-                            Some(Direction::Minus) => -*val,
-                        };
-                        *val += delta;
-                    };
+                    match new_kind {
+                        MetricKind::Incremental => *val += *new_val,
+                        MetricKind::Absolute => {
+                            *val = *new_val;
+                            *kind = MetricKind::Absolute;
+                        }
+                    }
                     *timestamp = *new_timestamp;
                     *tags = new_tags.clone();
                 }
@@ -118,18 +257,21 @@ impl Metric {
                     ref mut val,
                     ref mut timestamp,
                     ref mut tags,
+                    ref mut kind,
                 },
                 Metric::Set {
                     name: new_name,
                     val: new_val,
                     timestamp: new_timestamp,
                     tags: new_tags,
+                    kind: new_kind,
                 },
             ) => {
                 if name == new_name {
                     *val = new_val.clone();
                     *timestamp = *new_timestamp;
                     *tags = new_tags.clone();
+                    *kind = *new_kind;
                 }
             }
             (
@@ -139,6 +281,7 @@ impl Metric {
                     ref mut sample_rate,
                     ref mut timestamp,
                     ref mut tags,
+                    ref mut kind,
                 },
                 Metric::Histogram {
                     name: new_name,
@@ -146,19 +289,154 @@ impl Metric {
                     sample_rate: new_sample_rate,
                     timestamp: new_timestamp,
                     tags: new_tags,
+                    kind: new_kind,
                 },
             ) => {
                 if name == new_name && val == new_val {
-                    *sample_rate += *new_sample_rate;
+                    match new_kind {
+                        MetricKind::Incremental => *sample_rate += *new_sample_rate,
+                        MetricKind::Absolute => {
+                            *sample_rate = *new_sample_rate;
+                            *kind = MetricKind::Absolute;
+                        }
+                    }
                     *timestamp = *new_timestamp;
                     *tags = new_tags.clone();
                 };
             }
+            (
+                Metric::AggregatedHistogram {
+                    ref mut name,
+                    ref mut buckets,
+                    ref mut counts,
+                    ref mut count,
+                    ref mut sum,
+                    ref mut timestamp,
+                    ref mut tags,
+                    ref mut kind,
+                },
+                Metric::AggregatedHistogram {
+                    name: new_name,
+                    buckets: new_buckets,
+                    counts: new_counts,
+                    count: new_count,
+                    sum: new_sum,
+                    timestamp: new_timestamp,
+                    tags: new_tags,
+                    kind: new_kind,
+                },
+            ) => {
+                // increments can only be added up bucket by bucket
+                if name == new_name && (buckets == new_buckets || new_kind.is_absolute()) {
+                    match new_kind {
+                        MetricKind::Incremental => {
+                            for (c, new_c) in counts.iter_mut().zip(new_counts) {
+                                *c += new_c;
+                            }
+                            *count += new_count;
+                            *sum += new_sum;
+                        }
+                        MetricKind::Absolute => {
+                            *buckets = new_buckets.clone();
+                            *counts = new_counts.clone();
+                            *count = *new_count;
+                            *sum = *new_sum;
+                            *kind = MetricKind::Absolute;
+                        }
+                    }
+                    *timestamp = *new_timestamp;
+                    *tags = new_tags.clone();
+                }
+            }
+            // quantiles can't be combined, so the latest summary wins
+            (
+                Metric::AggregatedSummary {
+                    ref mut name,
+                    ref mut quantiles,
+                    ref mut values,
+                    ref mut count,
+                    ref mut sum,
+                    ref mut timestamp,
+                    ref mut tags,
+                    ref mut kind,
+                },
+                Metric::AggregatedSummary {
+                    name: new_name,
+                    quantiles: new_quantiles,
+                    values: new_values,
+                    count: new_count,
+                    sum: new_sum,
+                    timestamp: new_timestamp,
+                    tags: new_tags,
+                    kind: new_kind,
+                },
+            ) => {
+                if name == new_name {
+                    *quantiles = new_quantiles.clone();
+                    *values = new_values.clone();
+                    *count = *new_count;
+                    *sum = *new_sum;
+                    *timestamp = *new_timestamp;
+                    *tags = new_tags.clone();
+                    *kind = *new_kind;
+                }
+            }
             _ => {}
         }
     }
 }
 
+/// The last value of each absolute counter and aggregated histogram series,
+/// to turn the running totals into increments.
+#[derive(Default)]
+pub struct CounterState {
+    /// The last value of each series, and whether it was seen since the
+    /// last eviction.
+    last: HashMap<(String, Vec<(String, String)>), (Metric, bool)>,
+}
+
+impl CounterState {
+    /// Replaces an absolute counter or aggregated histogram with its increase
+    /// since the last value of the series. The first value only serves as the
+    /// baseline, and a value lower than the last one means the series was
+    /// reset. Other metrics are returned unchanged.
+    pub fn incremental(&mut self, metric: Metric) -> Option<Metric> {
+        match &metric {
+            Metric::Counter {
+                name,
+                tags,
+                kind: MetricKind::Absolute,
+                ..
+            }
+            | Metric::AggregatedHistogram {
+                name,
+                tags,
+                kind: MetricKind::Absolute,
+                ..
+            } => {
+                let mut labels = tags
+                    .iter()
+                    .flatten()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect::<Vec<_>>();
+                labels.sort();
+
+                self.last
+                    .insert((name.clone(), labels), (metric.clone(), true))
+                    .and_then(|(last, _)| metric.to_incremental(&last))
+            }
+            _ => Some(metric),
+        }
+    }
+
+    /// Forgets the series that weren't seen since the last eviction. A
+    /// series that comes back starts over from a new baseline.
+    pub fn evict_unseen(&mut self) {
+        self.last
+            .retain(|_, (_, seen)| std::mem::replace(seen, false));
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -185,6 +463,7 @@ mod test {
             val: 1.0,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Incremental,
         };
 
         let counter2 = Metric::Counter {
@@ -192,6 +471,7 @@ mod test {
             val: 2.0,
             timestamp: Some(ts()),
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         };
 
         counter1.merge(&counter2);
@@ -202,6 +482,47 @@ mod test {
                 val: 3.0,
                 timestamp: Some(ts()),
                 tags: Some(tags()),
+                kind: MetricKind::Incremental,
+            }
+        )
+    }
+
+    #[test]
+    fn merge_absolute_counters() {
+        let mut counter1 = Metric::Counter {
+            name: "counter".into(),
+            val: 1.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        };
+
+        let absolute = Metric::Counter {
+            name: "counter".into(),
+            val: 10.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        };
+
+        let increment = Metric::Counter {
+            name: "counter".into(),
+            val: 2.0,
+            timestamp: Some(ts()),
+            tags: Some(tags()),
+            kind: MetricKind::Incremental,
+        };
+
+        counter1.merge(&absolute);
+        counter1.merge(&increment);
+        assert_eq!(
+            counter1,
+            Metric::Counter {
+                name: "counter".into(),
+                val: 12.0,
+                timestamp: Some(ts()),
+                tags: Some(tags()),
+                kind: MetricKind::Absolute,
             }
         )
     }
@@ -213,6 +534,7 @@ mod test {
             val: 1.0,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Incremental,
         };
 
         let counter2 = Metric::Counter {
@@ -220,6 +542,7 @@ mod test {
             val: 2.0,
             timestamp: Some(ts()),
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         };
 
         counter1.merge(&counter2);
@@ -230,6 +553,7 @@ mod test {
                 val: 1.0,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         )
     }
@@ -239,17 +563,17 @@ mod test {
         let mut gauge1 = Metric::Gauge {
             name: "gauge".into(),
             val: 1.0,
-            direction: None,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Absolute,
         };
 
         let gauge2 = Metric::Gauge {
             name: "gauge".into(),
             val: 2.0,
-            direction: None,
             timestamp: Some(ts()),
             tags: Some(tags()),
+            kind: MetricKind::Absolute,
         };
 
         gauge1.merge(&gauge2);
@@ -258,13 +582,87 @@ mod test {
             Metric::Gauge {
                 name: "gauge".into(),
                 val: 2.0,
-                direction: None,
                 timestamp: Some(ts()),
                 tags: Some(tags()),
+                kind: MetricKind::Absolute,
             }
         )
     }
 
+    #[test]
+    fn merge_gauge_deltas() {
+        let mut gauge1 = Metric::Gauge {
+            name: "gauge".into(),
+            val: 10.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        };
+
+        let plus = Metric::Gauge {
+            name: "gauge".into(),
+            val: 3.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        };
+
+        let minus = Metric::Gauge {
+            name: "gauge".into(),
+            val: -5.0,
+            timestamp: Some(ts()),
+            tags: Some(tags()),
+            kind: MetricKind::Incremental,
+        };
+
+        gauge1.merge(&plus);
+        gauge1.merge(&minus);
+        assert_eq!(
+            gauge1,
+            Metric::Gauge {
+                name: "gauge".into(),
+                val: 8.0,
+                timestamp: Some(ts()),
+                tags: Some(tags()),
+                kind: MetricKind::Absolute,
+            }
+        );
+    }
+
+    #[test]
+    fn merge_incremental_gauges() {
+        let delta = |val| Metric::Gauge {
+            name: "gauge".into(),
+            val,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        };
+
+        let mut gauge = delta(3.0);
+        gauge.merge(&delta(-5.0));
+        assert_eq!(gauge, delta(-2.0));
+
+        gauge.merge(&Metric::Gauge {
+            name: "gauge".into(),
+            val: 7.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        });
+        gauge.merge(&delta(1.0));
+        assert_eq!(
+            gauge,
+            Metric::Gauge {
+                name: "gauge".into(),
+                val: 8.0,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Absolute,
+            }
+        );
+    }
+
     #[test]
     fn merge_sets() {
         let mut set1 = Metric::Set {
@@ -272,6 +670,7 @@ mod test {
             val: "old".into(),
             timestamp: None,
             tags: None,
+            kind: MetricKind::Incremental,
         };
 
         let set2 = Metric::Set {
@@ -279,6 +678,7 @@ mod test {
             val: "new".into(),
             timestamp: Some(ts()),
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         };
 
         set1.merge(&set2);
@@ -289,6 +689,7 @@ mod test {
                 val: "new".into(),
                 timestamp: Some(ts()),
                 tags: Some(tags()),
+                kind: MetricKind::Incremental,
             }
         )
     }
@@ -301,6 +702,7 @@ mod test {
             sample_rate: 10,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Incremental,
         };
 
         let hist2 = Metric::Histogram {
@@ -309,6 +711,7 @@ mod test {
             sample_rate: 20,
             timestamp: Some(ts()),
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         };
 
         hist1.merge(&hist2);
@@ -320,7 +723,189 @@ mod test {
                 sample_rate: 30,
                 timestamp: Some(ts()),
                 tags: Some(tags()),
+                kind: MetricKind::Incremental,
+            }
+        )
+    }
+
+    #[test]
+    fn merge_aggregated_histograms() {
+        let mut hist1 = Metric::AggregatedHistogram {
+            name: "hist".into(),
+            buckets: vec![1.0, 2.0],
+            counts: vec![3, 4],
+            count: 8,
+            sum: 10.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        };
+
+        let hist2 = Metric::AggregatedHistogram {
+            name: "hist".into(),
+            buckets: vec![1.0, 2.0],
+            counts: vec![1, 0],
+            count: 2,
+            sum: 3.5,
+            timestamp: Some(ts()),
+            tags: Some(tags()),
+            kind: MetricKind::Incremental,
+        };
+
+        let hist3 = Metric::AggregatedHistogram {
+            name: "hist".into(),
+            buckets: vec![1.0, 5.0],
+            counts: vec![1, 1],
+            count: 2,
+            sum: 4.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        };
+
+        hist1.merge(&hist2);
+        // different buckets can't be added up
+        hist1.merge(&hist3);
+        assert_eq!(
+            hist1,
+            Metric::AggregatedHistogram {
+                name: "hist".into(),
+                buckets: vec![1.0, 2.0],
+                counts: vec![4, 4],
+                count: 10,
+                sum: 13.5,
+                timestamp: Some(ts()),
+                tags: Some(tags()),
+                kind: MetricKind::Incremental,
             }
         )
     }
+
+    #[test]
+    fn aggregated_histograms_to_incremental() {
+        let hist = |counts: Vec<u64>, count, sum| Metric::AggregatedHistogram {
+            name: "hist".into(),
+            buckets: vec![1.0, 2.0],
+            counts,
+            count,
+            sum,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        };
+        let incremental = |counts: Vec<u64>, count, sum| Metric::AggregatedHistogram {
+            name: "hist".into(),
+            buckets: vec![1.0, 2.0],
+            counts,
+            count,
+            sum,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        };
+
+        assert_eq!(
+            hist(vec![3, 5], 9, 12.0).to_incremental(&hist(vec![1, 2], 3, 4.0)),
+            Some(incremental(vec![2, 3], 6, 8.0))
+        );
+        // a reset starts counting from zero
+        assert_eq!(
+            hist(vec![1, 0], 1, 0.5).to_incremental(&hist(vec![3, 5], 9, 12.0)),
+            Some(incremental(vec![1, 0], 1, 0.5))
+        );
+    }
+
+    fn tagged_counter(val: f64, tag: &str, kind: MetricKind) -> Metric {
+        Metric::Counter {
+            name: "requests_total".into(),
+            val,
+            timestamp: None,
+            tags: Some(
+                vec![("code".to_owned(), tag.to_owned())]
+                    .into_iter()
+                    .collect(),
+            ),
+            kind,
+        }
+    }
+
+    #[test]
+    fn absolute_counters_to_incremental() {
+        let mut state = CounterState::default();
+
+        assert_eq!(
+            state.incremental(tagged_counter(10.0, "200", MetricKind::Absolute)),
+            None
+        );
+        assert_eq!(
+            state.incremental(tagged_counter(3.0, "500", MetricKind::Absolute)),
+            None
+        );
+        assert_eq!(
+            state.incremental(tagged_counter(15.0, "200", MetricKind::Absolute)),
+            Some(tagged_counter(5.0, "200", MetricKind::Incremental))
+        );
+        assert_eq!(
+            state.incremental(tagged_counter(3.0, "200", MetricKind::Incremental)),
+            Some(tagged_counter(3.0, "200", MetricKind::Incremental))
+        );
+        // The counter was reset.
+        assert_eq!(
+            state.incremental(tagged_counter(2.0, "200", MetricKind::Absolute)),
+            Some(tagged_counter(2.0, "200", MetricKind::Incremental))
+        );
+        assert_eq!(
+            state.incremental(tagged_counter(4.0, "500", MetricKind::Absolute)),
+            Some(tagged_counter(1.0, "500", MetricKind::Incremental))
+        );
+    }
+
+    #[test]
+    fn absolute_histograms_to_incremental() {
+        let mut state = CounterState::default();
+        let histogram = |counts: Vec<u64>, count, sum, kind| Metric::AggregatedHistogram {
+            name: "latency".into(),
+            buckets: vec![0.1, 1.0],
+            counts,
+            count,
+            sum,
+            timestamp: None,
+            tags: None,
+            kind,
+        };
+
+        assert_eq!(
+            state.incremental(histogram(vec![2, 1], 3, 1.5, MetricKind::Absolute)),
+            None
+        );
+        assert_eq!(
+            state.incremental(histogram(vec![4, 1], 6, 3.0, MetricKind::Absolute)),
+            Some(histogram(vec![2, 0], 3, 1.5, MetricKind::Incremental))
+        );
+    }
+
+    #[test]
+    fn counter_state_evicts_unseen_series() {
+        let mut state = CounterState::default();
+
+        state.incremental(tagged_counter(10.0, "200", MetricKind::Absolute));
+        state.incremental(tagged_counter(3.0, "500", MetricKind::Absolute));
+        state.evict_unseen();
+
+        assert_eq!(
+            state.incremental(tagged_counter(15.0, "200", MetricKind::Absolute)),
+            Some(tagged_counter(5.0, "200", MetricKind::Incremental))
+        );
+        state.evict_unseen();
+
+        // The 500 series wasn't seen since, so it starts over
+        assert_eq!(
+            state.incremental(tagged_counter(4.0, "500", MetricKind::Absolute)),
+            None
+        );
+        assert_eq!(state.last.len(), 2);
+        state.evict_unseen();
+        state.evict_unseen();
+        assert!(state.last.is_empty());
+    }
 }
@@ -10,7 +10,7 @@ use string_cache::DefaultAtom as Atom;
 pub mod metric;
 mod unflatten;
 
-pub use metric::Metric;
+pub use metric::{CounterState, Metric, MetricKind};
 
 pub mod proto {
     include!(concat!(env!("OUT_DIR"), "/event.proto.rs"));
@@ -90,6 +90,11 @@ impl LogEvent {
         self.fields.get(key).map(|v| &v.value)
     }
 
+    /// Modifying the value in place keeps whether it was explicitly set.
+    pub fn get_mut(&mut self, key: &Atom) -> Option<&mut ValueKind> {
+        self.fields.get_mut(key).map(|v| &mut v.value)
+    }
+
     pub fn contains(&self, key: &Atom) -> bool {
         self.fields.contains_key(key)
     }
@@ -122,6 +127,14 @@ impl LogEvent {
         self.fields.remove(key).map(|v| v.value)
     }
 
+    /// Move the value at `from_key` to `to_key`, keeping whether it was
+    /// explicitly set. Any existing value at `to_key` is replaced.
+    pub fn rename_key(&mut self, from_key: &Atom, to_key: Atom) {
+        if let Some(value) = self.fields.remove(from_key) {
+            self.fields.insert(to_key, value);
+        }
+    }
+
     pub fn keys(&self) -> impl Iterator<Item = &Atom> {
         self.fields.keys()
     }
@@ -373,6 +386,7 @@ impl From<proto::EventWrapper> for Event {
                             val: counter.val,
                             timestamp,
                             tags,
+                            kind: counter.kind().into(),
                         })
                     }
                     MetricProto::Histogram(hist) => {
@@ -392,13 +406,21 @@ impl From<proto::EventWrapper> for Event {
                             sample_rate: hist.sample_rate,
                             timestamp,
                             tags,
+                            kind: hist.kind().into(),
                         })
                     }
                     MetricProto::Gauge(gauge) => {
-                        let direction = match gauge.direction() {
-                            proto::gauge::Direction::None => None,
-                            proto::gauge::Direction::Plus => Some(metric::Direction::Plus),
-                            proto::gauge::Direction::Minus => Some(metric::Direction::Minus),
+                        let (val, kind) = match (gauge.kind(), gauge.direction()) {
+                            (proto::MetricKind::Absolute, _)
+                            | (_, proto::gauge::Direction::None) => {
+                                (gauge.val, MetricKind::Absolute)
+                            }
+                            (_, proto::gauge::Direction::Plus) => {
+                                (gauge.val, MetricKind::Incremental)
+                            }
+                            (_, proto::gauge::Direction::Minus) => {
+                                (-gauge.val, MetricKind::Incremental)
+                            }
                         };
 
                         let tags = if !gauge.tags.is_empty() {
@@ -413,10 +435,10 @@ impl From<proto::EventWrapper> for Event {
 
                         Event::Metric(Metric::Gauge {
                             name: gauge.name,
-                            val: gauge.val,
-                            direction,
+                            val,
                             timestamp,
                             tags,
+                            kind,
                         })
                     }
                     MetricProto::Set(set) => {
@@ -435,6 +457,51 @@ impl From<proto::EventWrapper> for Event {
                             val: set.val,
                             timestamp,
                             tags,
+                            kind: set.kind().into(),
+                        })
+                    }
+                    MetricProto::AggregatedHistogram(hist) => {
+                        let timestamp = hist
+                            .timestamp
+                            .map(|ts| chrono::Utc.timestamp(ts.seconds, ts.nanos as u32));
+
+                        let tags = if !hist.tags.is_empty() {
+                            Some(hist.tags)
+                        } else {
+                            None
+                        };
+
+                        Event::Metric(Metric::AggregatedHistogram {
+                            name: hist.name,
+                            buckets: hist.buckets,
+                            counts: hist.counts,
+                            count: hist.count,
+                            sum: hist.sum,
+                            timestamp,
+                            tags,
+                            kind: hist.kind().into(),
+                        })
+                    }
+                    MetricProto::AggregatedSummary(summary) => {
+                        let timestamp = summary
+                            .timestamp
+                            .map(|ts| chrono::Utc.timestamp(ts.seconds, ts.nanos as u32));
+
+                        let tags = if !summary.tags.is_empty() {
+                            Some(summary.tags)
+                        } else {
+                            None
+                        };
+
+                        Event::Metric(Metric::AggregatedSummary {
+                            name: summary.name,
+                            quantiles: summary.quantiles,
+                            values: summary.values,
+                            count: summary.count,
+                            sum: summary.sum,
+                            timestamp,
+                            tags,
+                            kind: summary.kind().into(),
                         })
                     }
                 }
@@ -484,6 +551,7 @@ impl From<Event> for proto::EventWrapper {
                 val,
                 timestamp,
                 tags,
+                kind,
             }) => {
                 let timestamp = timestamp.map(|ts| prost_types::Timestamp {
                     seconds: ts.timestamp(),
@@ -497,6 +565,7 @@ impl From<Event> for proto::EventWrapper {
                     val,
                     timestamp,
                     tags,
+                    kind: proto::MetricKind::from(kind).into(),
                 };
                 let event = EventProto::Metric(proto::Metric {
                     metric: Some(MetricProto::Counter(counter)),
@@ -509,6 +578,7 @@ impl From<Event> for proto::EventWrapper {
                 sample_rate,
                 timestamp,
                 tags,
+                kind,
             }) => {
                 let timestamp = timestamp.map(|ts| prost_types::Timestamp {
                     seconds: ts.timestamp(),
@@ -523,6 +593,7 @@ impl From<Event> for proto::EventWrapper {
                     sample_rate,
                     timestamp,
                     tags,
+                    kind: proto::MetricKind::from(kind).into(),
                 };
                 let event = EventProto::Metric(proto::Metric {
                     metric: Some(MetricProto::Histogram(hist)),
@@ -532,21 +603,21 @@ impl From<Event> for proto::EventWrapper {
             Event::Metric(Metric::Gauge {
                 name,
                 val,
-                direction,
                 timestamp,
                 tags,
+                kind,
             }) => {
                 let timestamp = timestamp.map(|ts| prost_types::Timestamp {
                     seconds: ts.timestamp(),
                     nanos: ts.timestamp_subsec_nanos() as i32,
                 });
 
-                let direction = match direction {
-                    None => proto::gauge::Direction::None,
-                    Some(metric::Direction::Plus) => proto::gauge::Direction::Plus,
-                    Some(metric::Direction::Minus) => proto::gauge::Direction::Minus,
-                }
-                .into();
+                let (val, direction) = match kind {
+                    MetricKind::Absolute => (val, proto::gauge::Direction::None),
+                    MetricKind::Incremental if val < 0.0 => (-val, proto::gauge::Direction::Minus),
+                    MetricKind::Incremental => (val, proto::gauge::Direction::Plus),
+                };
+                let direction = direction.into();
 
                 let tags = tags.unwrap_or_default();
 
@@ -556,6 +627,7 @@ impl From<Event> for proto::EventWrapper {
                     direction,
                     timestamp,
                     tags,
+                    kind: proto::MetricKind::from(kind).into(),
                 };
                 let event = EventProto::Metric(proto::Metric {
                     metric: Some(MetricProto::Gauge(gauge)),
@@ -567,6 +639,7 @@ impl From<Event> for proto::EventWrapper {
                 val,
                 timestamp,
                 tags,
+                kind,
             }) => {
                 let timestamp = timestamp.map(|ts| prost_types::Timestamp {
                     seconds: ts.timestamp(),
@@ -580,12 +653,95 @@ impl From<Event> for proto::EventWrapper {
                     val,
                     timestamp,
                     tags,
+                    kind: proto::MetricKind::from(kind).into(),
                 };
                 let event = EventProto::Metric(proto::Metric {
                     metric: Some(MetricProto::Set(set)),
                 });
                 proto::EventWrapper { event: Some(event) }
             }
+            Event::Metric(Metric::AggregatedHistogram {
+                name,
+                buckets,
+                counts,
+                count,
+                sum,
+                timestamp,
+                tags,
+                kind,
+            }) => {
+                let timestamp = timestamp.map(|ts| prost_types::Timestamp {
+                    seconds: ts.timestamp(),
+                    nanos: ts.timestamp_subsec_nanos() as i32,
+                });
+
+                let tags = tags.unwrap_or_default();
+
+                let hist = proto::AggregatedHistogram {
+                    name,
+                    buckets,
+                    counts,
+                    count,
+                    sum,
+                    timestamp,
+                    tags,
+                    kind: proto::MetricKind::from(kind).into(),
+                };
+                let event = EventProto::Metric(proto::Metric {
+                    metric: Some(MetricProto::AggregatedHistogram(hist)),
+                });
+                proto::EventWrapper { event: Some(event) }
+            }
+            Event::Metric(Metric::AggregatedSummary {
+                name,
+                quantiles,
+                values,
+                count,
+                sum,
+                timestamp,
+                tags,
+                kind,
+            }) => {
+                let timestamp = timestamp.map(|ts| prost_types::Timestamp {
+                    seconds: ts.timestamp(),
+                    nanos: ts.timestamp_subsec_nanos() as i32,
+                });
+
+                let tags = tags.unwrap_or_default();
+
+                let summary = proto::AggregatedSummary {
+                    name,
+                    quantiles,
+                    values,
+                    count,
+                    sum,
+                    timestamp,
+                    tags,
+                    kind: proto::MetricKind::from(kind).into(),
+                };
+                let event = EventProto::Metric(proto::Metric {
+                    metric: Some(MetricProto::AggregatedSummary(summary)),
+                });
+                proto::EventWrapper { event: Some(event) }
+            }
+        }
+    }
+}
+
+impl From<proto::MetricKind> for MetricKind {
+    fn from(kind: proto::MetricKind) -> Self {
+        match kind {
+            proto::MetricKind::Incremental => MetricKind::Incremental,
+            proto::MetricKind::Absolute => MetricKind::Absolute,
+        }
+    }
+}
+
+impl From<MetricKind> for proto::MetricKind {
+    fn from(kind: MetricKind) -> Self {
+        match kind {
+            MetricKind::Incremental => proto::MetricKind::Incremental,
+            MetricKind::Absolute => proto::MetricKind::Absolute,
         }
     }
 }
@@ -679,7 +835,7 @@ impl<'a> Serialize for FieldsIter<'a> {
 
 #[cfg(test)]
 mod test {
-    use super::Event;
+    use super::{proto, Event, Metric, MetricKind};
     use regex::Regex;
     use std::collections::HashSet;
 
@@ -715,6 +871,95 @@ mod test {
         assert!(rfc3339_re.is_match(actual_all.pointer("/timestamp").unwrap().as_str().unwrap()));
     }
 
+    #[test]
+    fn metric_kind_proto_roundtrip() {
+        let gauge = |val, kind| {
+            Event::Metric(Metric::Gauge {
+                name: "gauge".into(),
+                val,
+                timestamp: None,
+                tags: None,
+                kind,
+            })
+        };
+        let counter = Event::Metric(Metric::Counter {
+            name: "counter".into(),
+            val: 10.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        });
+
+        for event in vec![
+            gauge(2.5, MetricKind::Absolute),
+            gauge(-2.5, MetricKind::Absolute),
+            gauge(2.5, MetricKind::Incremental),
+            gauge(-2.5, MetricKind::Incremental),
+            counter,
+            Event::Metric(Metric::AggregatedHistogram {
+                name: "hist".into(),
+                buckets: vec![1.0, 2.0],
+                counts: vec![3, 4],
+                count: 8,
+                sum: 10.5,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Absolute,
+            }),
+            Event::Metric(Metric::AggregatedSummary {
+                name: "summary".into(),
+                quantiles: vec![0.5, 0.99],
+                values: vec![1.5, 4.0],
+                count: 8,
+                sum: 10.5,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Absolute,
+            }),
+        ] {
+            let decoded = Event::from(proto::EventWrapper::from(event.clone()));
+            assert_eq!(decoded, event);
+        }
+    }
+
+    #[test]
+    fn gauge_proto_kind() {
+        let encode = |kind| {
+            let event = Event::Metric(Metric::Gauge {
+                name: "gauge".into(),
+                val: 2.5,
+                timestamp: None,
+                tags: None,
+                kind,
+            });
+            match proto::EventWrapper::from(event).event {
+                Some(proto::event_wrapper::Event::Metric(proto::Metric {
+                    metric: Some(proto::metric::Metric::Gauge(gauge)),
+                })) => gauge,
+                other => panic!("not a gauge: {:?}", other),
+            }
+        };
+
+        assert_eq!(
+            encode(MetricKind::Absolute).kind(),
+            proto::MetricKind::Absolute
+        );
+        assert_eq!(
+            encode(MetricKind::Incremental).kind(),
+            proto::MetricKind::Incremental
+        );
+
+        // Absolute gauges encoded before the kind was added
+        let mut gauge = encode(MetricKind::Absolute);
+        gauge.kind = proto::MetricKind::Incremental.into();
+        let event = Event::from(proto::EventWrapper {
+            event: Some(proto::event_wrapper::Event::Metric(proto::Metric {
+                metric: Some(proto::metric::Metric::Gauge(gauge)),
+            })),
+        });
+        assert_eq!(event.as_metric().kind(), MetricKind::Absolute);
+    }
+
     #[test]
     fn type_serialization() {
         use serde_json::json;
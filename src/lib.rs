@@ -20,6 +20,7 @@ pub mod region;
 pub mod runtime;
 pub mod sinks;
 pub mod sources;
+pub mod syslog;
 pub mod template;
 pub mod test_util;
 pub mod topology;
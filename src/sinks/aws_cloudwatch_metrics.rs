@@ -1,6 +1,6 @@
 use crate::{
     buffers::Acker,
-    event::Metric,
+    event::{Metric, MetricKind},
     region::RegionOrEndpoint,
     sinks::util::{
         retries::{FixedRetryPolicy, RetryLogic},
@@ -148,6 +148,7 @@ impl CloudWatchMetricsSvc {
                     val,
                     timestamp,
                     tags,
+                    kind: MetricKind::Incremental,
                 } => Some(MetricDatum {
                     metric_name: name.to_string(),
                     value: Some(val),
@@ -158,9 +159,9 @@ impl CloudWatchMetricsSvc {
                 Metric::Gauge {
                     name,
                     val,
-                    direction: None,
                     timestamp,
                     tags,
+                    kind: MetricKind::Absolute,
                 } => Some(MetricDatum {
                     metric_name: name.to_string(),
                     value: Some(val),
@@ -174,6 +175,7 @@ impl CloudWatchMetricsSvc {
                     sample_rate,
                     timestamp,
                     tags,
+                    ..
                 } => Some(MetricDatum {
                     metric_name: name.to_string(),
                     values: Some(vec![val]),
@@ -182,6 +184,32 @@ impl CloudWatchMetricsSvc {
                     dimensions: tags.map(tags_to_dimensions),
                     ..Default::default()
                 }),
+                // each bucket is reported as its upper bound, so samples
+                // above the largest bound can't be represented
+                Metric::AggregatedHistogram {
+                    name,
+                    buckets,
+                    counts,
+                    timestamp,
+                    tags,
+                    kind: MetricKind::Incremental,
+                    ..
+                } => {
+                    let (values, counts) = buckets
+                        .into_iter()
+                        .zip(counts)
+                        .filter(|(_, count)| *count > 0)
+                        .map(|(bucket, count)| (bucket, count as f64))
+                        .unzip();
+                    Some(MetricDatum {
+                        metric_name: name.to_string(),
+                        values: Some(values),
+                        counts: Some(counts),
+                        timestamp: timestamp.map(timestamp_to_string),
+                        dimensions: tags.map(tags_to_dimensions),
+                        ..Default::default()
+                    })
+                }
                 _ => None,
             })
             .collect();
@@ -285,12 +313,14 @@ mod tests {
                 val: 1.0,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             },
             Metric::Counter {
                 name: "bytes_out".into(),
                 val: 2.5,
                 timestamp: Some(Utc.ymd(2018, 11, 14).and_hms_nano(8, 9, 10, 123456789)),
                 tags: None,
+                kind: MetricKind::Incremental,
             },
             Metric::Counter {
                 name: "healthcheck".into(),
@@ -301,6 +331,7 @@ mod tests {
                         .into_iter()
                         .collect(),
                 ),
+                kind: MetricKind::Incremental,
             },
         ];
 
@@ -340,9 +371,9 @@ mod tests {
         let events = vec![Metric::Gauge {
             name: "temperature".into(),
             val: 10.0,
-            direction: None,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Absolute,
         }];
 
         assert_eq!(
@@ -366,6 +397,7 @@ mod tests {
             sample_rate: 100,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Incremental,
         }];
 
         assert_eq!(
@@ -381,6 +413,33 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn encode_events_aggregated_histogram() {
+        let events = vec![Metric::AggregatedHistogram {
+            name: "latency".into(),
+            buckets: vec![1.0, 2.0, 4.0],
+            counts: vec![5, 0, 2],
+            count: 8,
+            sum: 14.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        }];
+
+        assert_eq!(
+            svc().encode_events(events),
+            PutMetricDataInput {
+                namespace: "vector".into(),
+                metric_data: vec![MetricDatum {
+                    metric_name: "latency".into(),
+                    values: Some(vec![1.0, 4.0]),
+                    counts: Some(vec![5.0, 2.0]),
+                    ..Default::default()
+                }],
+            }
+        );
+    }
 }
 
 #[cfg(feature = "cloudwatch-metrics-integration-tests")]
@@ -430,6 +489,7 @@ mod integration_tests {
                     .into_iter()
                     .collect(),
                 ),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -439,9 +499,9 @@ mod integration_tests {
             let event = Event::Metric(Metric::Gauge {
                 name: format!("gauge-{}", gauge_name),
                 val: i as f64,
-                direction: None,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Absolute,
             });
             events.push(event);
         }
@@ -454,6 +514,7 @@ mod integration_tests {
                 sample_rate: 100,
                 timestamp: Some(Utc.ymd(2018, 11, 14).and_hms_nano(8, 9, 10, 123456789)),
                 tags: None,
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
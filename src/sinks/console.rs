@@ -91,7 +91,10 @@ fn encode_event(event: Event, encoding: &Encoding) -> Result<String, ()> {
 #[cfg(test)]
 mod test {
     use super::{encode_event, Encoding};
-    use crate::{event::Metric, Event};
+    use crate::{
+        event::{Metric, MetricKind},
+        Event,
+    };
     use chrono::{offset::TimeZone, Utc};
 
     #[test]
@@ -111,9 +114,10 @@ mod test {
                     .into_iter()
                     .collect(),
             ),
+            kind: MetricKind::Incremental,
         });
         assert_eq!(
-            Ok(r#"{"type":"counter","name":"foos","val":100.0,"timestamp":"2018-11-14T08:09:10.000000011Z","tags":{"key":"value"}}"#.to_string()),
+            Ok(r#"{"type":"counter","name":"foos","val":100.0,"timestamp":"2018-11-14T08:09:10.000000011Z","tags":{"key":"value"},"kind":"incremental"}"#.to_string()),
             encode_event(event, &Encoding::Text)
         );
     }
@@ -126,9 +130,10 @@ mod test {
             sample_rate: 1,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Incremental,
         });
         assert_eq!(
-            Ok(r#"{"type":"histogram","name":"glork","val":10.0,"sample_rate":1,"timestamp":null,"tags":null}"#.to_string()),
+            Ok(r#"{"type":"histogram","name":"glork","val":10.0,"sample_rate":1,"timestamp":null,"tags":null,"kind":"incremental"}"#.to_string()),
             encode_event(event, &Encoding::Text)
         );
     }
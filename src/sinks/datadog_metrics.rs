@@ -1,6 +1,6 @@
 use crate::{
     buffers::Acker,
-    event::Metric,
+    event::{Metric, MetricKind},
     sinks::util::{
         http::{Error as HttpError, HttpRetryLogic, HttpService, Response as HttpResponse},
         retries::FixedRetryPolicy,
@@ -228,53 +228,116 @@ fn encode_namespace(namespace: &str, name: String) -> String {
 fn encode_events(events: Vec<Metric>, interval: i64, namespace: &str) -> DatadogRequest {
     let series: Vec<_> = events
         .into_iter()
-        .filter_map(|event| match event {
+        .flat_map(|event| match event {
             Metric::Counter {
                 name,
                 val,
                 timestamp,
                 tags,
-            } => Some(DatadogMetric {
+                kind: MetricKind::Incremental,
+            } => vec![DatadogMetric {
                 metric: encode_namespace(namespace, name),
                 r#type: DatadogMetricType::Count,
                 interval: Some(interval),
                 points: vec![DatadogPoint(encode_timestamp(timestamp), val)],
                 tags: tags.map(encode_tags),
-            }),
+            }],
             Metric::Gauge {
                 name,
                 val,
-                direction: None,
                 timestamp,
                 tags,
-            } => Some(DatadogMetric {
+                kind: MetricKind::Absolute,
+            } => vec![DatadogMetric {
                 metric: encode_namespace(namespace, name),
                 r#type: DatadogMetricType::Gauge,
                 interval: None,
                 points: vec![DatadogPoint(encode_timestamp(timestamp), val)],
                 tags: tags.map(encode_tags),
-            }),
+            }],
             Metric::Histogram {
                 name,
                 val,
                 sample_rate,
                 timestamp,
                 tags,
+                ..
             } => {
                 let mut points = Vec::new();
                 for _ in 0..sample_rate {
                     let point = DatadogPoint(encode_timestamp(timestamp), val);
                     points.push(point);
                 }
-                Some(DatadogMetric {
+                vec![DatadogMetric {
                     metric: encode_namespace(namespace, name),
                     r#type: DatadogMetricType::Count,
                     interval: Some(interval),
                     points,
                     tags: tags.map(encode_tags),
-                })
+                }]
             }
-            _ => None,
+            // Datadog has no bucketed histograms, so only the number and
+            // sum of the samples are sent
+            Metric::AggregatedHistogram {
+                name,
+                count,
+                sum,
+                timestamp,
+                tags,
+                kind: MetricKind::Incremental,
+                ..
+            } => {
+                let tags = tags.map(encode_tags);
+                let point = |val| vec![DatadogPoint(encode_timestamp(timestamp), val)];
+                vec![
+                    DatadogMetric {
+                        metric: encode_namespace(namespace, format!("{}.count", name)),
+                        r#type: DatadogMetricType::Count,
+                        interval: Some(interval),
+                        points: point(count as f64),
+                        tags: tags.clone(),
+                    },
+                    DatadogMetric {
+                        metric: encode_namespace(namespace, format!("{}.sum", name)),
+                        r#type: DatadogMetricType::Count,
+                        interval: Some(interval),
+                        points: point(sum),
+                        tags,
+                    },
+                ]
+            }
+            // summaries are snapshots, sent as gauges named the way
+            // DogStatsD names the percentiles it calculates
+            Metric::AggregatedSummary {
+                name,
+                quantiles,
+                values,
+                count,
+                sum,
+                timestamp,
+                tags,
+                ..
+            } => {
+                let tags = tags.map(encode_tags);
+                let gauge = |suffix: String, val| DatadogMetric {
+                    metric: encode_namespace(namespace, format!("{}.{}", name, suffix)),
+                    r#type: DatadogMetricType::Gauge,
+                    interval: None,
+                    points: vec![DatadogPoint(encode_timestamp(timestamp), val)],
+                    tags: tags.clone(),
+                };
+                let mut series = quantiles
+                    .iter()
+                    .zip(values)
+                    .map(|(quantile, value)| {
+                        gauge(format!("{}percentile", quantile * 100.0), value)
+                    })
+                    .collect::<Vec<_>>();
+                series.push(gauge("count".into(), count as f64));
+                series.push(gauge("sum".into(), sum));
+                series
+            }
+            _ => vec![],
         })
         .collect();
 
@@ -326,12 +389,14 @@ mod tests {
                 val: 1.5,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             },
             Metric::Counter {
                 name: "check".into(),
                 val: 1.0,
                 timestamp: Some(ts()),
                 tags: Some(tags()),
+                kind: MetricKind::Incremental,
             },
         ];
         let input = encode_events(events, interval, "ns");
@@ -348,9 +413,9 @@ mod tests {
         let events = vec![Metric::Gauge {
             name: "volume".into(),
             val: -1.1,
-            direction: None,
             timestamp: Some(ts()),
             tags: None,
+            kind: MetricKind::Absolute,
         }];
         let input = encode_events(events, 60, "");
         let json = serde_json::to_string(&input).unwrap();
@@ -369,6 +434,7 @@ mod tests {
             sample_rate: 2,
             timestamp: Some(ts()),
             tags: None,
+            kind: MetricKind::Incremental,
         }];
         let input = encode_events(events, 60, "");
         let json = serde_json::to_string(&input).unwrap();
@@ -378,4 +444,46 @@ mod tests {
             r#"{"series":[{"metric":"login","type":"count","interval":60,"points":[[1542182950,1.0],[1542182950,1.0]],"tags":null}]}"#
         );
     }
+
+    #[test]
+    fn encode_aggregated_histogram() {
+        let events = vec![Metric::AggregatedHistogram {
+            name: "requests".into(),
+            buckets: vec![1.0, 2.0],
+            counts: vec![1, 2],
+            count: 4,
+            sum: 8.5,
+            timestamp: Some(ts()),
+            tags: None,
+            kind: MetricKind::Incremental,
+        }];
+        let input = encode_events(events, 60, "");
+        let json = serde_json::to_string(&input).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"series":[{"metric":"requests.count","type":"count","interval":60,"points":[[1542182950,4.0]],"tags":null},{"metric":"requests.sum","type":"count","interval":60,"points":[[1542182950,8.5]],"tags":null}]}"#
+        );
+    }
+
+    #[test]
+    fn encode_aggregated_summary() {
+        let events = vec![Metric::AggregatedSummary {
+            name: "requests".into(),
+            quantiles: vec![0.5, 0.99],
+            values: vec![1.0, 3.0],
+            count: 4,
+            sum: 8.5,
+            timestamp: Some(ts()),
+            tags: None,
+            kind: MetricKind::Absolute,
+        }];
+        let input = encode_events(events, 60, "");
+        let json = serde_json::to_string(&input).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"series":[{"metric":"requests.50percentile","type":"gauge","interval":null,"points":[[1542182950,1.0]],"tags":null},{"metric":"requests.99percentile","type":"gauge","interval":null,"points":[[1542182950,3.0]],"tags":null},{"metric":"requests.count","type":"gauge","interval":null,"points":[[1542182950,4.0]],"tags":null},{"metric":"requests.sum","type":"gauge","interval":null,"points":[[1542182950,8.5]],"tags":null}]}"#
+        );
+    }
 }
@@ -0,0 +1,294 @@
+use super::{
+    encode_line, healthcheck, Field, InfluxDB1Settings, InfluxDB2Settings, ProtocolVersion,
+};
+use crate::{
+    buffers::Acker,
+    event::{self, Event, ValueKind},
+    sinks::util::{
+        http::{HttpRetryLogic, HttpService},
+        retries::FixedRetryPolicy,
+        tls::{TlsOptions, TlsSettings},
+        BatchConfig, BatchServiceSink, Buffer, SinkExt,
+    },
+    topology::config::{DataType, SinkConfig, SinkDescription},
+};
+use chrono::Utc;
+use futures::{stream::iter_ok, Sink};
+use http::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use string_cache::DefaultAtom as Atom;
+use tower::ServiceBuilder;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct InfluxDBLogsConfig {
+    pub measurement: String,
+    pub endpoint: String,
+    #[serde(default)]
+    pub tags: Vec<Atom>,
+    #[serde(flatten)]
+    pub influxdb1_settings: Option<InfluxDB1Settings>,
+    #[serde(flatten)]
+    pub influxdb2_settings: Option<InfluxDB2Settings>,
+    #[serde(default, flatten)]
+    pub batch: BatchConfig,
+
+    // Tower Request based configuration
+    pub request_in_flight_limit: Option<usize>,
+    pub request_timeout_secs: Option<u64>,
+    pub request_rate_limit_duration_secs: Option<u64>,
+    pub request_rate_limit_num: Option<u64>,
+    pub request_retry_attempts: Option<usize>,
+    pub request_retry_backoff_secs: Option<u64>,
+
+    pub tls: Option<TlsOptions>,
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<InfluxDBLogsConfig>("influxdb_logs")
+}
+
+#[typetag::serde(name = "influxdb_logs")]
+impl SinkConfig for InfluxDBLogsConfig {
+    fn build(
+        &self,
+        acker: Acker,
+    ) -> crate::Result<(crate::sinks::RouterSink, crate::sinks::Healthcheck)> {
+        let protocol = ProtocolVersion::new(&self.influxdb1_settings, &self.influxdb2_settings)?;
+        let tls = TlsSettings::from_options(&self.tls)?;
+        let sink = influxdb_logs(self.clone(), protocol.clone(), tls.clone(), acker)?;
+        let healthcheck = healthcheck(&self.endpoint, &protocol, tls)?;
+        Ok((sink, healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "influxdb_logs"
+    }
+}
+
+fn influxdb_logs(
+    config: InfluxDBLogsConfig,
+    protocol: ProtocolVersion,
+    tls_settings: TlsSettings,
+    acker: Acker,
+) -> crate::Result<crate::sinks::RouterSink> {
+    let batch = config.batch.unwrap_or(bytesize::mib(1u64), 1);
+
+    let timeout = config.request_timeout_secs.unwrap_or(60);
+    let in_flight_limit = config.request_in_flight_limit.unwrap_or(5);
+    let rate_limit_duration = config.request_rate_limit_duration_secs.unwrap_or(1);
+    let rate_limit_num = config.request_rate_limit_num.unwrap_or(5);
+    let retry_attempts = config.request_retry_attempts.unwrap_or(5);
+    let retry_backoff_secs = config.request_retry_backoff_secs.unwrap_or(1);
+
+    let policy = FixedRetryPolicy::new(
+        retry_attempts,
+        Duration::from_secs(retry_backoff_secs),
+        HttpRetryLogic,
+    );
+
+    let uri = protocol.write_uri(&config.endpoint)?;
+
+    let http_service =
+        HttpService::builder()
+            .tls_settings(tls_settings)
+            .build(move |body: Vec<u8>| {
+                let mut builder = hyper::Request::builder();
+                builder.method(Method::POST);
+                builder.uri(uri.clone());
+
+                builder.header("Content-Type", "text/plain; charset=utf-8");
+
+                let mut request = builder.body(body).unwrap();
+                protocol.authorize(request.headers_mut());
+
+                request
+            });
+
+    let service = ServiceBuilder::new()
+        .concurrency_limit(in_flight_limit)
+        .rate_limit(rate_limit_num, Duration::from_secs(rate_limit_duration))
+        .retry(policy)
+        .timeout(Duration::from_secs(timeout))
+        .service(http_service);
+
+    let measurement = config.measurement.clone();
+    let tags = config.tags.iter().cloned().collect::<HashSet<_>>();
+    let sink = BatchServiceSink::new(service, acker)
+        .batched_with_min(Buffer::new(false), &batch)
+        .with_flat_map(move |event| iter_ok(encode_event(event, &measurement, &tags)));
+
+    Ok(Box::new(sink))
+}
+
+fn encode_field(value: &ValueKind) -> Field {
+    match value {
+        ValueKind::Integer(i) => Field::Integer(*i),
+        ValueKind::Float(f) => Field::Float(*f),
+        ValueKind::Boolean(b) => Field::Boolean(*b),
+        ValueKind::Bytes(_) | ValueKind::Timestamp(_) => Field::String(value.to_string_lossy()),
+    }
+}
+
+/// Converts a log event into a point. The configured `tags` become tags,
+/// `timestamp` the point time and every other field a typed field.
+fn encode_event(event: Event, measurement: &str, tags: &HashSet<Atom>) -> Option<Vec<u8>> {
+    let event = event.into_log();
+
+    let timestamp = match event.get(&event::TIMESTAMP).and_then(|v| v.as_timestamp()) {
+        Some(ts) => ts.timestamp_nanos(),
+        None => Utc::now().timestamp_nanos(),
+    };
+
+    let mut point_tags = BTreeMap::new();
+    let mut fields = BTreeMap::new();
+    for (key, value) in event.all_fields() {
+        if tags.contains(key) {
+            point_tags.insert(key.to_string(), value.to_string_lossy());
+        } else if *key != *event::TIMESTAMP {
+            fields.insert(key.to_string(), encode_field(value));
+        }
+    }
+
+    let mut line = String::new();
+    encode_line(measurement, &point_tags, &fields, timestamp, &mut line);
+
+    if line.is_empty() {
+        warn!(
+            message = "Event has no fields to write; Dropping event.",
+            rate_limit_secs = 30,
+        );
+        return None;
+    }
+
+    Some(line.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        runtime::Runtime,
+        test_util::{next_addr, shutdown_on_idle},
+    };
+    use chrono::offset::TimeZone;
+    use futures::{stream, sync::mpsc, Future, Stream};
+    use hyper::service::service_fn_ok;
+    use hyper::{Body, Request, Response, Server};
+
+    fn event() -> Event {
+        let mut event = Event::from("hello world");
+        let log = event.as_mut_log();
+        log.insert_implicit(
+            event::TIMESTAMP.clone(),
+            Utc.ymd(2018, 11, 14).and_hms_nano(8, 9, 10, 11).into(),
+        );
+        log.insert_explicit("host".into(), "web-1".into());
+        log.insert_explicit("status".into(), 200.into());
+        log.insert_explicit("duration".into(), 1.5.into());
+        log.insert_explicit("cached".into(), false.into());
+        event
+    }
+
+    #[test]
+    fn influxdb_logs_encode_event() {
+        let tags = vec![Atom::from("host"), Atom::from("missing")]
+            .into_iter()
+            .collect();
+
+        let line = encode_event(event(), "vector-logs", &tags).unwrap();
+
+        assert_eq!(
+            String::from_utf8(line).unwrap(),
+            "vector-logs,host=web-1 cached=false,duration=1.5,message=\"hello world\",\
+             status=200i 1542182950000000011\n"
+        );
+    }
+
+    #[test]
+    fn influxdb_logs_drops_events_without_fields() {
+        let mut event = Event::new_empty_log();
+        event
+            .as_mut_log()
+            .insert_explicit("host".into(), "web-1".into());
+        let tags = vec![Atom::from("host")].into_iter().collect();
+
+        assert!(encode_event(event, "vector-logs", &tags).is_none());
+    }
+
+    #[test]
+    fn influxdb_logs_v1_happy_path() {
+        let in_addr = next_addr();
+
+        let config = r#"
+        measurement = "vector-logs"
+        endpoint = "http://$IN_ADDR"
+        tags = ["host"]
+        database = "my-database"
+        username = "writer"
+        password = "secret"
+    "#
+        .replace("$IN_ADDR", &format!("{}", in_addr));
+        let config: InfluxDBLogsConfig = toml::from_str(&config).unwrap();
+        assert!(config.influxdb2_settings.is_none());
+
+        let (tx, rx) = mpsc::channel(100);
+        let service = move || {
+            let tx = tx.clone();
+            service_fn_ok(move |req: Request<Body>| {
+                let (parts, body) = req.into_parts();
+
+                let tx = tx.clone();
+                tokio::spawn(
+                    body.concat2()
+                        .map_err(|e| panic!(e))
+                        .and_then(|body| tx.send((parts, body)))
+                        .map(|_| ())
+                        .map_err(|e| panic!(e)),
+                );
+
+                Response::new(Body::empty())
+            })
+        };
+
+        let (trigger, tripwire) = stream_cancel::Tripwire::new();
+        let server = Server::bind(&in_addr)
+            .serve(service)
+            .with_graceful_shutdown(tripwire)
+            .map_err(|e| panic!("server error: {}", e));
+
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let pump = sink.send_all(stream::iter_ok(vec![event(), event()]));
+
+        let mut rt = Runtime::new().unwrap();
+        rt.spawn(server);
+
+        let _ = rt.block_on(pump).unwrap();
+        drop(trigger);
+
+        let requests = rx.wait().map(Result::unwrap).collect::<Vec<_>>();
+
+        shutdown_on_idle(rt);
+
+        assert_eq!(requests.len(), 1);
+        let (parts, body) = &requests[0];
+        assert_eq!(parts.uri.path(), "/write");
+        assert_eq!(
+            parts.uri.query(),
+            Some("db=my-database&u=writer&p=secret&precision=ns")
+        );
+        assert!(parts.headers.get("Authorization").is_none());
+
+        let lines = String::from_utf8_lossy(&body[..]);
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("vector-logs,host=web-1 cached=false,"));
+    }
+}
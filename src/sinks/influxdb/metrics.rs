@@ -0,0 +1,637 @@
+use super::{
+    encode_line, healthcheck, Field, InfluxDB1Settings, InfluxDB2Settings, ProtocolVersion,
+};
+use crate::{
+    buffers::Acker,
+    event::{Metric, MetricKind},
+    sinks::util::{
+        http::{Error as HttpError, HttpRetryLogic, HttpService, Response as HttpResponse},
+        retries::FixedRetryPolicy,
+        tls::{TlsOptions, TlsSettings},
+        BatchConfig, BatchServiceSink, MetricBuffer, SinkExt,
+    },
+    topology::config::{DataType, SinkConfig, SinkDescription},
+};
+use chrono::{DateTime, Utc};
+use futures::Poll;
+use http::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tower::{Service, ServiceBuilder};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct InfluxDBMetricsConfig {
+    pub namespace: String,
+    pub endpoint: String,
+    #[serde(flatten)]
+    pub influxdb1_settings: Option<InfluxDB1Settings>,
+    #[serde(flatten)]
+    pub influxdb2_settings: Option<InfluxDB2Settings>,
+    #[serde(default, flatten)]
+    pub batch: BatchConfig,
+
+    // Tower Request based configuration
+    pub request_in_flight_limit: Option<usize>,
+    pub request_timeout_secs: Option<u64>,
+    pub request_rate_limit_duration_secs: Option<u64>,
+    pub request_rate_limit_num: Option<u64>,
+    pub request_retry_attempts: Option<usize>,
+    pub request_retry_backoff_secs: Option<u64>,
+
+    pub tls: Option<TlsOptions>,
+}
+
+#[derive(Clone)]
+struct InfluxDBSvc {
+    namespace: String,
+    inner: HttpService,
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<InfluxDBMetricsConfig>("influxdb_metrics")
+}
+
+#[typetag::serde(name = "influxdb_metrics")]
+impl SinkConfig for InfluxDBMetricsConfig {
+    fn build(
+        &self,
+        acker: Acker,
+    ) -> crate::Result<(crate::sinks::RouterSink, crate::sinks::Healthcheck)> {
+        let protocol = ProtocolVersion::new(&self.influxdb1_settings, &self.influxdb2_settings)?;
+        let tls = TlsSettings::from_options(&self.tls)?;
+        let sink = InfluxDBSvc::new(self.clone(), protocol.clone(), tls.clone(), acker)?;
+        let healthcheck = healthcheck(&self.endpoint, &protocol, tls)?;
+        Ok((sink, healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "influxdb_metrics"
+    }
+}
+
+impl InfluxDBSvc {
+    pub fn new(
+        config: InfluxDBMetricsConfig,
+        protocol: ProtocolVersion,
+        tls_settings: TlsSettings,
+        acker: Acker,
+    ) -> crate::Result<crate::sinks::RouterSink> {
+        let batch = config.batch.unwrap_or(20, 1);
+
+        let timeout = config.request_timeout_secs.unwrap_or(60);
+        let in_flight_limit = config.request_in_flight_limit.unwrap_or(5);
+        let rate_limit_duration = config.request_rate_limit_duration_secs.unwrap_or(1);
+        let rate_limit_num = config.request_rate_limit_num.unwrap_or(5);
+        let retry_attempts = config.request_retry_attempts.unwrap_or(5);
+        let retry_backoff_secs = config.request_retry_backoff_secs.unwrap_or(1);
+
+        let policy = FixedRetryPolicy::new(
+            retry_attempts,
+            Duration::from_secs(retry_backoff_secs),
+            HttpRetryLogic,
+        );
+
+        let uri = protocol.write_uri(&config.endpoint)?;
+
+        let http_service =
+            HttpService::builder()
+                .tls_settings(tls_settings)
+                .build(move |body: Vec<u8>| {
+                    let mut builder = hyper::Request::builder();
+                    builder.method(Method::POST);
+                    builder.uri(uri.clone());
+
+                    builder.header("Content-Type", "text/plain; charset=utf-8");
+
+                    let mut request = builder.body(body).unwrap();
+                    protocol.authorize(request.headers_mut());
+
+                    request
+                });
+
+        let influxdb_http_service = InfluxDBSvc {
+            namespace: config.namespace,
+            inner: http_service,
+        };
+
+        let service = ServiceBuilder::new()
+            .concurrency_limit(in_flight_limit)
+            .rate_limit(rate_limit_num, Duration::from_secs(rate_limit_duration))
+            .retry(policy)
+            .timeout(Duration::from_secs(timeout))
+            .service(influxdb_http_service);
+
+        let sink =
+            BatchServiceSink::new(service, acker).batched_with_min(MetricBuffer::new(), &batch);
+
+        Ok(Box::new(sink))
+    }
+}
+
+impl Service<Vec<Metric>> for InfluxDBSvc {
+    type Response = HttpResponse;
+    type Error = HttpError;
+    type Future = <HttpService as Service<Vec<u8>>>::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, items: Vec<Metric>) -> Self::Future {
+        let body = encode_events(items, &self.namespace);
+
+        self.inner.call(body.into_bytes())
+    }
+}
+
+/// The observed values of a histogram or set with a given name and tags,
+/// which are written as a single point.
+#[derive(Default)]
+struct Aggregate {
+    count: i64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+impl Aggregate {
+    fn observe(&mut self, val: f64, count: u32, timestamp: Option<DateTime<Utc>>) {
+        if self.count == 0 || val < self.min {
+            self.min = val;
+        }
+        if self.count == 0 || val > self.max {
+            self.max = val;
+        }
+        self.count += i64::from(count);
+        self.sum += val * f64::from(count);
+        self.timestamp = self.timestamp.max(timestamp);
+    }
+}
+
+fn encode_tags(tags: Option<HashMap<String, String>>) -> BTreeMap<String, String> {
+    tags.map(|tags| tags.into_iter().collect())
+        .unwrap_or_default()
+}
+
+fn encode_timestamp(timestamp: Option<DateTime<Utc>>) -> i64 {
+    if let Some(ts) = timestamp {
+        ts.timestamp_nanos()
+    } else {
+        Utc::now().timestamp_nanos()
+    }
+}
+
+fn encode_namespace(namespace: &str, name: String) -> String {
+    if !namespace.is_empty() {
+        format!("{}.{}", namespace, name)
+    } else {
+        name
+    }
+}
+
+fn single_field(name: &str, value: Field) -> BTreeMap<String, Field> {
+    let mut fields = BTreeMap::new();
+    fields.insert(name.to_string(), value);
+    fields
+}
+
+/// Converts metrics to line protocol. Counters and gauges are written as
+/// a `value` field. Histograms are summarized into `count`, `sum`, `min`,
+/// `max` and `mean` fields, and sets into the `count` of unique values.
+/// Aggregated histograms and summaries keep their `count` and `sum` next to
+/// a `bucket_<bound>` or `quantile_<quantile>` field for each of their values.
+/// Counters and aggregated histograms must be incremental and gauges
+/// absolute, as `MetricBuffer` makes them.
+fn encode_events(events: Vec<Metric>, namespace: &str) -> String {
+    let mut output = String::new();
+    let mut histograms = BTreeMap::new();
+    let mut sets = BTreeMap::new();
+
+    for event in events {
+        match event {
+            Metric::Counter {
+                name,
+                val,
+                timestamp,
+                tags,
+                kind: MetricKind::Incremental,
+            } => encode_line(
+                &encode_namespace(namespace, name),
+                &encode_tags(tags),
+                &single_field("value", Field::Float(val)),
+                encode_timestamp(timestamp),
+                &mut output,
+            ),
+            Metric::Gauge {
+                name,
+                val,
+                timestamp,
+                tags,
+                kind: MetricKind::Absolute,
+            } => encode_line(
+                &encode_namespace(namespace, name),
+                &encode_tags(tags),
+                &single_field("value", Field::Float(val)),
+                encode_timestamp(timestamp),
+                &mut output,
+            ),
+            Metric::Histogram {
+                name,
+                val,
+                sample_rate,
+                timestamp,
+                tags,
+                ..
+            } => histograms
+                .entry((name, encode_tags(tags)))
+                .or_insert_with(Aggregate::default)
+                .observe(val, sample_rate, timestamp),
+            Metric::Set {
+                name,
+                timestamp,
+                tags,
+                ..
+            } => sets
+                .entry((name, encode_tags(tags)))
+                .or_insert_with(Aggregate::default)
+                .observe(0.0, 1, timestamp),
+            Metric::AggregatedHistogram {
+                name,
+                buckets,
+                counts,
+                count,
+                sum,
+                timestamp,
+                tags,
+                kind: MetricKind::Incremental,
+            } => {
+                let mut fields = BTreeMap::new();
+                for (bucket, bucket_count) in buckets.iter().zip(counts) {
+                    fields.insert(
+                        format!("bucket_{}", bucket),
+                        Field::Integer(bucket_count as i64),
+                    );
+                }
+                fields.insert("count".to_string(), Field::Integer(count as i64));
+                fields.insert("sum".to_string(), Field::Float(sum));
+                encode_line(
+                    &encode_namespace(namespace, name),
+                    &encode_tags(tags),
+                    &fields,
+                    encode_timestamp(timestamp),
+                    &mut output,
+                )
+            }
+            Metric::AggregatedSummary {
+                name,
+                quantiles,
+                values,
+                count,
+                sum,
+                timestamp,
+                tags,
+                ..
+            } => {
+                let mut fields = BTreeMap::new();
+                for (quantile, value) in quantiles.iter().zip(values) {
+                    fields.insert(format!("quantile_{}", quantile), Field::Float(value));
+                }
+                fields.insert("count".to_string(), Field::Integer(count as i64));
+                fields.insert("sum".to_string(), Field::Float(sum));
+                encode_line(
+                    &encode_namespace(namespace, name),
+                    &encode_tags(tags),
+                    &fields,
+                    encode_timestamp(timestamp),
+                    &mut output,
+                )
+            }
+            // The batch buffer turns these into the kinds handled above,
+            // without its state there is nothing meaningful to write.
+            metric => warn!(
+                message = "Unsupported metric kind; Dropping metric.",
+                name = metric.name(),
+                kind = ?metric.kind(),
+                rate_limit_secs = 30,
+            ),
+        }
+    }
+
+    for ((name, tags), histogram) in histograms {
+        let mut fields = BTreeMap::new();
+        fields.insert("count".to_string(), Field::Integer(histogram.count));
+        fields.insert("sum".to_string(), Field::Float(histogram.sum));
+        fields.insert("min".to_string(), Field::Float(histogram.min));
+        fields.insert("max".to_string(), Field::Float(histogram.max));
+        if histogram.count > 0 {
+            let mean = histogram.sum / histogram.count as f64;
+            fields.insert("mean".to_string(), Field::Float(mean));
+        }
+
+        encode_line(
+            &encode_namespace(namespace, name),
+            &tags,
+            &fields,
+            encode_timestamp(histogram.timestamp),
+            &mut output,
+        );
+    }
+
+    for ((name, tags), set) in sets {
+        encode_line(
+            &encode_namespace(namespace, name),
+            &tags,
+            &single_field("count", Field::Integer(set.count)),
+            encode_timestamp(set.timestamp),
+            &mut output,
+        );
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::Event,
+        runtime::Runtime,
+        sinks::util::Batch,
+        test_util::{next_addr, shutdown_on_idle},
+    };
+    use chrono::offset::TimeZone;
+    use futures::{stream, sync::mpsc, Future, Sink, Stream};
+    use hyper::service::service_fn_ok;
+    use hyper::{Body, Request, Response, Server};
+    use pretty_assertions::assert_eq;
+
+    fn ts() -> DateTime<Utc> {
+        Utc.ymd(2018, 11, 14).and_hms_nano(8, 9, 10, 11)
+    }
+
+    fn tags() -> Option<HashMap<String, String>> {
+        Some(
+            vec![
+                ("normal_tag".to_owned(), "value".to_owned()),
+                ("true_tag".to_owned(), "true".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn encode_counter_and_gauge() {
+        let events = vec![
+            Metric::Counter {
+                name: "total".into(),
+                val: 1.5,
+                timestamp: Some(ts()),
+                tags: tags(),
+                kind: MetricKind::Incremental,
+            },
+            Metric::Gauge {
+                name: "temperature".into(),
+                val: -4.0,
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Absolute,
+            },
+            Metric::Gauge {
+                name: "relative".into(),
+                val: 1.0,
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Incremental,
+            },
+        ];
+
+        assert_eq!(
+            encode_events(events, "ns"),
+            "ns.total,normal_tag=value,true_tag=true value=1.5 1542182950000000011\n\
+             ns.temperature value=-4 1542182950000000011\n"
+        );
+    }
+
+    fn encode_buffered(events: Vec<Metric>) -> String {
+        let mut buffer = MetricBuffer::new();
+        for event in events {
+            buffer.push(Event::Metric(event));
+        }
+        encode_events(buffer.finish(), "ns")
+    }
+
+    #[test]
+    fn encode_absolute_counter() {
+        let events = vec![5.0, 8.0]
+            .into_iter()
+            .map(|val| Metric::Counter {
+                name: "total".into(),
+                val,
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Absolute,
+            })
+            .collect::<Vec<_>>();
+
+        // Written as the increase since the first observation
+        assert_eq!(
+            encode_buffered(events.clone()),
+            "ns.total value=3 1542182950000000011\n"
+        );
+        assert_eq!(encode_events(events, "ns"), "");
+    }
+
+    #[test]
+    fn encode_incremental_gauge() {
+        let events = vec![2.0, 3.0]
+            .into_iter()
+            .map(|val| Metric::Gauge {
+                name: "temperature".into(),
+                val,
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Incremental,
+            })
+            .collect::<Vec<_>>();
+
+        // Written as the sum of the changes
+        assert_eq!(
+            encode_buffered(events.clone()),
+            "ns.temperature value=5 1542182950000000011\n"
+        );
+        assert_eq!(encode_events(events, "ns"), "");
+    }
+
+    #[test]
+    fn encode_histogram() {
+        let events = vec![
+            Metric::Histogram {
+                name: "latency".into(),
+                val: 1.0,
+                sample_rate: 3,
+                timestamp: Some(ts()),
+                tags: tags(),
+                kind: MetricKind::Incremental,
+            },
+            Metric::Histogram {
+                name: "latency".into(),
+                val: 5.0,
+                sample_rate: 1,
+                timestamp: Some(ts()),
+                tags: tags(),
+                kind: MetricKind::Incremental,
+            },
+            Metric::Histogram {
+                name: "latency".into(),
+                val: 2.0,
+                sample_rate: 1,
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Incremental,
+            },
+        ];
+
+        assert_eq!(
+            encode_events(events, ""),
+            "latency count=1i,max=2,mean=2,min=2,sum=2 1542182950000000011\n\
+             latency,normal_tag=value,true_tag=true \
+             count=4i,max=5,mean=2,min=1,sum=8 1542182950000000011\n"
+        );
+    }
+
+    #[test]
+    fn encode_aggregated_histogram_and_summary() {
+        let events = vec![
+            Metric::AggregatedHistogram {
+                name: "latency".into(),
+                buckets: vec![0.5, 1.0],
+                counts: vec![3, 1],
+                count: 5,
+                sum: 4.5,
+                timestamp: Some(ts()),
+                tags: tags(),
+                kind: MetricKind::Incremental,
+            },
+            Metric::AggregatedSummary {
+                name: "duration".into(),
+                quantiles: vec![0.5, 0.99],
+                values: vec![0.25, 2.0],
+                count: 5,
+                sum: 4.5,
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Absolute,
+            },
+        ];
+
+        assert_eq!(
+            encode_events(events, ""),
+            "latency,normal_tag=value,true_tag=true \
+             bucket_0.5=3i,bucket_1=1i,count=5i,sum=4.5 1542182950000000011\n\
+             duration count=5i,quantile_0.5=0.25,quantile_0.99=2,sum=4.5 1542182950000000011\n"
+        );
+    }
+
+    #[test]
+    fn encode_set() {
+        let events = vec!["a", "b", "c"]
+            .into_iter()
+            .map(|val| Metric::Set {
+                name: "users".into(),
+                val: val.into(),
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Incremental,
+            })
+            .collect();
+
+        assert_eq!(
+            encode_events(events, "ns"),
+            "ns.users count=3i 1542182950000000011\n"
+        );
+    }
+
+    #[test]
+    fn influxdb_metrics_v2_happy_path() {
+        let in_addr = next_addr();
+
+        let config = r#"
+        namespace = "vector"
+        endpoint = "http://$IN_ADDR"
+        org = "my-org"
+        bucket = "my-bucket"
+        token = "my-token"
+    "#
+        .replace("$IN_ADDR", &format!("{}", in_addr));
+        let config: InfluxDBMetricsConfig = toml::from_str(&config).unwrap();
+        assert!(config.influxdb1_settings.is_none());
+
+        let (tx, rx) = mpsc::channel(100);
+        let service = move || {
+            let tx = tx.clone();
+            service_fn_ok(move |req: Request<Body>| {
+                let (parts, body) = req.into_parts();
+
+                let tx = tx.clone();
+                tokio::spawn(
+                    body.concat2()
+                        .map_err(|e| panic!(e))
+                        .and_then(|body| tx.send((parts, body)))
+                        .map(|_| ())
+                        .map_err(|e| panic!(e)),
+                );
+
+                Response::new(Body::empty())
+            })
+        };
+
+        let (trigger, tripwire) = stream_cancel::Tripwire::new();
+        let server = Server::bind(&in_addr)
+            .serve(service)
+            .with_graceful_shutdown(tripwire)
+            .map_err(|e| panic!("server error: {}", e));
+
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let events = vec![Event::Metric(Metric::Counter {
+            name: "requests".into(),
+            val: 2.0,
+            timestamp: Some(ts()),
+            tags: None,
+            kind: MetricKind::Incremental,
+        })];
+        let pump = sink.send_all(stream::iter_ok(events));
+
+        let mut rt = Runtime::new().unwrap();
+        rt.spawn(server);
+
+        let _ = rt.block_on(pump).unwrap();
+        drop(trigger);
+
+        let requests = rx.wait().map(Result::unwrap).collect::<Vec<_>>();
+
+        shutdown_on_idle(rt);
+
+        assert_eq!(requests.len(), 1);
+        let (parts, body) = &requests[0];
+        assert_eq!(parts.method, hyper::Method::POST);
+        assert_eq!(parts.uri.path(), "/api/v2/write");
+        assert_eq!(
+            parts.uri.query(),
+            Some("org=my-org&bucket=my-bucket&precision=ns")
+        );
+        assert_eq!(parts.headers["Authorization"], "Token my-token");
+        assert_eq!(
+            String::from_utf8_lossy(&body[..]),
+            "vector.requests value=2 1542182950000000011\n"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+pub mod logs;
+pub mod metrics;
+
+use crate::sinks::util::{http::https_client, tls::TlsSettings};
+use futures::Future;
+use http::{header::HeaderValue, HeaderMap, Uri};
+use hyper::{Body, Request};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Snafu)]
+enum ConfigError {
+    #[snafu(display("Either the InfluxDB 1 (`database`) or the InfluxDB 2 (`org`, `bucket`, `token`) settings must be given"))]
+    MissingSettings,
+    #[snafu(display("Only one of the InfluxDB 1 (`database`) or InfluxDB 2 (`org`, `bucket`, `token`) settings may be given"))]
+    BothSettings,
+}
+
+/// Settings for the `/write` endpoint of InfluxDB 1.x.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct InfluxDB1Settings {
+    pub database: String,
+    pub consistency: Option<String>,
+    pub retention_policy_name: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Settings for the `/api/v2/write` endpoint of InfluxDB 2.x.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct InfluxDB2Settings {
+    pub org: String,
+    pub bucket: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum ProtocolVersion {
+    V1(InfluxDB1Settings),
+    V2(InfluxDB2Settings),
+}
+
+impl ProtocolVersion {
+    pub fn new(
+        v1: &Option<InfluxDB1Settings>,
+        v2: &Option<InfluxDB2Settings>,
+    ) -> crate::Result<Self> {
+        match (v1, v2) {
+            (Some(v1), None) => Ok(ProtocolVersion::V1(v1.clone())),
+            (None, Some(v2)) => Ok(ProtocolVersion::V2(v2.clone())),
+            (Some(_), Some(_)) => Err(Box::new(ConfigError::BothSettings)),
+            (None, None) => Err(Box::new(ConfigError::MissingSettings)),
+        }
+    }
+
+    pub fn write_uri(&self, endpoint: &str) -> crate::Result<Uri> {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        let path = match self {
+            ProtocolVersion::V1(settings) => {
+                query.append_pair("db", &settings.database);
+                if let Some(rp) = &settings.retention_policy_name {
+                    query.append_pair("rp", rp);
+                }
+                if let Some(consistency) = &settings.consistency {
+                    query.append_pair("consistency", consistency);
+                }
+                if let Some(username) = &settings.username {
+                    query.append_pair("u", username);
+                }
+                if let Some(password) = &settings.password {
+                    query.append_pair("p", password);
+                }
+                "write"
+            }
+            ProtocolVersion::V2(settings) => {
+                query.append_pair("org", &settings.org);
+                query.append_pair("bucket", &settings.bucket);
+                "api/v2/write"
+            }
+        };
+        query.append_pair("precision", "ns");
+
+        build_uri(endpoint, &format!("{}?{}", path, query.finish()))
+    }
+
+    pub fn healthcheck_uri(&self, endpoint: &str) -> crate::Result<Uri> {
+        match self {
+            ProtocolVersion::V1(_) => build_uri(endpoint, "ping"),
+            ProtocolVersion::V2(_) => build_uri(endpoint, "health"),
+        }
+    }
+
+    pub fn authorize(&self, headers: &mut HeaderMap) {
+        if let ProtocolVersion::V2(settings) = self {
+            if let Ok(value) = HeaderValue::from_str(&format!("Token {}", settings.token)) {
+                headers.insert("Authorization", value);
+            }
+        }
+    }
+}
+
+fn build_uri(endpoint: &str, path: &str) -> crate::Result<Uri> {
+    let uri = format!("{}/{}", endpoint.trim_end_matches('/'), path);
+    Ok(uri.parse::<Uri>().context(super::UriParseError)?)
+}
+
+pub fn healthcheck(
+    endpoint: &str,
+    protocol: &ProtocolVersion,
+    tls_settings: TlsSettings,
+) -> crate::Result<super::Healthcheck> {
+    let uri = protocol.healthcheck_uri(endpoint)?;
+    let mut request = Request::get(&uri).body(Body::empty()).unwrap();
+    protocol.authorize(request.headers_mut());
+
+    let client = https_client(tls_settings)?;
+
+    let healthcheck = client
+        .request(request)
+        .map_err(|err| err.into())
+        .and_then(|response| match response.status() {
+            hyper::StatusCode::OK | hyper::StatusCode::NO_CONTENT => Ok(()),
+            status => Err(super::HealthcheckError::UnexpectedStatus { status }.into()),
+        });
+
+    Ok(Box::new(healthcheck))
+}
+
+/// A typed field value of a line protocol point.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    String(String),
+    Float(f64),
+    Integer(i64),
+    Boolean(bool),
+}
+
+/// Appends a point in line protocol to `output`. Empty tag values and
+/// non-finite floats can't be represented and are left out; a point
+/// without any fields is skipped entirely.
+pub fn encode_line(
+    measurement: &str,
+    tags: &BTreeMap<String, String>,
+    fields: &BTreeMap<String, Field>,
+    timestamp: i64,
+    output: &mut String,
+) {
+    let fields = fields
+        .iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Field::String(s) => format!("\"{}\"", escape(s, &['"', '\\'])),
+                Field::Float(f) if f.is_finite() => f.to_string(),
+                Field::Float(_) => return None,
+                Field::Integer(i) => format!("{}i", i),
+                Field::Boolean(b) => b.to_string(),
+            };
+            Some(format!("{}={}", escape(key, &[',', '=', ' ']), value))
+        })
+        .collect::<Vec<_>>();
+
+    if fields.is_empty() {
+        return;
+    }
+
+    output.push_str(&escape(measurement, &[',', ' ']));
+    for (key, value) in tags.iter().filter(|(_, value)| !value.is_empty()) {
+        output.push(',');
+        output.push_str(&escape(key, &[',', '=', ' ']));
+        output.push('=');
+        output.push_str(&escape(value, &[',', '=', ' ']));
+    }
+    output.push(' ');
+    output.push_str(&fields.join(","));
+    output.push(' ');
+    output.push_str(&timestamp.to_string());
+    output.push('\n');
+}
+
+fn escape(s: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            // Line breaks would end the point early.
+            '\n' => escaped.push_str("\\n"),
+            c if special.contains(&c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1() -> InfluxDB1Settings {
+        InfluxDB1Settings {
+            database: "vector".into(),
+            retention_policy_name: Some("autogen".into()),
+            username: Some("writer".into()),
+            password: Some("s3cr&t".into()),
+            ..Default::default()
+        }
+    }
+
+    fn v2() -> InfluxDB2Settings {
+        InfluxDB2Settings {
+            org: "my-org".into(),
+            bucket: "my-bucket".into(),
+            token: "my-token".into(),
+        }
+    }
+
+    #[test]
+    fn influxdb_protocol_version_requires_one_setting() {
+        assert!(ProtocolVersion::new(&None, &None).is_err());
+        assert!(ProtocolVersion::new(&Some(v1()), &Some(v2())).is_err());
+        assert!(ProtocolVersion::new(&Some(v1()), &None).is_ok());
+        assert!(ProtocolVersion::new(&None, &Some(v2())).is_ok());
+    }
+
+    #[test]
+    fn influxdb_write_uri() {
+        let uri = ProtocolVersion::V1(v1())
+            .write_uri("http://localhost:8086/")
+            .unwrap();
+        assert_eq!(
+            uri.to_string(),
+            "http://localhost:8086/write?db=vector&rp=autogen&u=writer&p=s3cr%26t&precision=ns"
+        );
+
+        let uri = ProtocolVersion::V2(v2())
+            .write_uri("https://localhost:9999")
+            .unwrap();
+        assert_eq!(
+            uri.to_string(),
+            "https://localhost:9999/api/v2/write?org=my-org&bucket=my-bucket&precision=ns"
+        );
+    }
+
+    #[test]
+    fn influxdb_authorize() {
+        let mut headers = HeaderMap::new();
+        ProtocolVersion::V1(v1()).authorize(&mut headers);
+        assert!(headers.is_empty());
+
+        ProtocolVersion::V2(v2()).authorize(&mut headers);
+        assert_eq!(headers["Authorization"], "Token my-token");
+    }
+
+    #[test]
+    fn influxdb_encode_line() {
+        let mut tags = BTreeMap::new();
+        tags.insert("host name".to_string(), "a,b=c".to_string());
+        tags.insert("empty".to_string(), "".to_string());
+
+        let mut fields = BTreeMap::new();
+        fields.insert("message".to_string(), Field::String("say \"hi\"\\".into()));
+        fields.insert("count".to_string(), Field::Integer(3));
+        fields.insert("ratio".to_string(), Field::Float(0.5));
+        fields.insert("nan".to_string(), Field::Float(std::f64::NAN));
+        fields.insert("ok".to_string(), Field::Boolean(true));
+
+        let mut output = String::new();
+        encode_line("my measurement,1", &tags, &fields, 42, &mut output);
+
+        assert_eq!(
+            output,
+            "my\\ measurement\\,1,host\\ name=a\\,b\\=c \
+             count=3i,message=\"say \\\"hi\\\"\\\\\",ok=true,ratio=0.5 42\n"
+        );
+    }
+
+    #[test]
+    fn influxdb_encode_line_without_fields() {
+        let mut output = String::new();
+        encode_line("m", &BTreeMap::new(), &BTreeMap::new(), 42, &mut output);
+        assert_eq!(output, "");
+    }
+}
@@ -0,0 +1,490 @@
+use crate::{
+    buffers::Acker,
+    event::{self, Event},
+    sinks::util::{
+        http::{https_client, HttpRetryLogic, HttpService},
+        retries::FixedRetryPolicy,
+        tls::{TlsOptions, TlsSettings},
+        BatchConfig, BatchServiceSink, PartitionBuffer, PartitionInnerBuffer, SinkExt,
+    },
+    template::Template,
+    topology::config::{DataType, SinkConfig, SinkDescription},
+};
+use futures::{stream::iter_ok, Future, Poll, Sink};
+use headers::HeaderMapExt;
+use http::{Method, Uri};
+use hyper::{Body, Request};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tower::{Service, ServiceBuilder};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("At least one label must be configured"))]
+    NoLabels,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LokiConfig {
+    pub endpoint: String,
+    pub labels: IndexMap<String, Template>,
+    #[serde(default)]
+    pub encoding: Encoding,
+    pub tenant_id: Option<String>,
+    pub basic_auth: Option<LokiBasicAuthConfig>,
+    #[serde(default, flatten)]
+    pub batch: BatchConfig,
+
+    // Tower Request based configuration
+    pub request_in_flight_limit: Option<usize>,
+    pub request_timeout_secs: Option<u64>,
+    pub request_rate_limit_duration_secs: Option<u64>,
+    pub request_rate_limit_num: Option<u64>,
+    pub request_retry_attempts: Option<usize>,
+    pub request_retry_backoff_secs: Option<u64>,
+
+    pub tls: Option<TlsOptions>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone, Derivative)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum Encoding {
+    #[derivative(Default)]
+    Text,
+    Json,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct LokiBasicAuthConfig {
+    pub password: String,
+    pub user: String,
+}
+
+impl LokiBasicAuthConfig {
+    fn apply(&self, header_map: &mut http::header::HeaderMap) {
+        let auth = headers::Authorization::basic(&self.user, &self.password);
+        header_map.typed_insert(auth)
+    }
+}
+
+/// The rendered labels identifying a stream.
+type Labels = BTreeMap<String, String>;
+
+/// A single log line with its timestamp in nanoseconds.
+type Entry = (i64, String);
+
+inventory::submit! {
+    SinkDescription::new_without_default::<LokiConfig>("loki")
+}
+
+#[typetag::serde(name = "loki")]
+impl SinkConfig for LokiConfig {
+    fn build(&self, acker: Acker) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        if self.labels.is_empty() {
+            return Err(Box::new(BuildError::NoLabels));
+        }
+
+        let tls = TlsSettings::from_options(&self.tls)?;
+        let sink = loki(self.clone(), acker, tls.clone())?;
+        let healthcheck = healthcheck(&self.endpoint, self.basic_auth.clone(), tls)?;
+
+        Ok((sink, healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "loki"
+    }
+}
+
+fn loki(
+    config: LokiConfig,
+    acker: Acker,
+    tls_settings: TlsSettings,
+) -> crate::Result<super::RouterSink> {
+    let uri = build_uri(&config.endpoint, "loki/api/v1/push")?;
+
+    let batch = config.batch.unwrap_or(100_000, 1);
+
+    let timeout = config.request_timeout_secs.unwrap_or(30);
+    let in_flight_limit = config.request_in_flight_limit.unwrap_or(5);
+    let rate_limit_duration = config.request_rate_limit_duration_secs.unwrap_or(1);
+    let rate_limit_num = config.request_rate_limit_num.unwrap_or(5);
+    let retry_attempts = config.request_retry_attempts.unwrap_or(usize::max_value());
+    let retry_backoff_secs = config.request_retry_backoff_secs.unwrap_or(1);
+
+    let tenant_id = config.tenant_id.clone();
+    let basic_auth = config.basic_auth.clone();
+
+    let policy = FixedRetryPolicy::new(
+        retry_attempts,
+        Duration::from_secs(retry_backoff_secs),
+        HttpRetryLogic,
+    );
+
+    let http_service =
+        HttpService::builder()
+            .tls_settings(tls_settings)
+            .build(move |body: Vec<u8>| {
+                let mut builder = hyper::Request::builder();
+                builder.method(Method::POST);
+                builder.uri(uri.clone());
+
+                builder.header("Content-Type", "application/json");
+
+                if let Some(tenant_id) = &tenant_id {
+                    builder.header("X-Scope-OrgID", tenant_id.as_str());
+                }
+
+                let mut request = builder.body(body).unwrap();
+
+                if let Some(auth) = &basic_auth {
+                    auth.apply(request.headers_mut());
+                }
+
+                request
+            });
+
+    let service = ServiceBuilder::new()
+        .concurrency_limit(in_flight_limit)
+        .rate_limit(rate_limit_num, Duration::from_secs(rate_limit_duration))
+        .retry(policy)
+        .timeout(Duration::from_secs(timeout))
+        .service(http_service);
+
+    let labels = config.labels.clone();
+    let encoding = config.encoding.clone();
+    let sink = BatchServiceSink::new(LokiService { inner: service }, acker)
+        .partitioned_batched_with_min(PartitionBuffer::new(Vec::new()), &batch)
+        .with_flat_map(move |event| iter_ok(encode_event(event, &labels, &encoding)));
+
+    Ok(Box::new(sink))
+}
+
+/// Encodes the batched entries of one stream into a push request body
+/// before handing it to the wrapped HTTP service.
+struct LokiService<S> {
+    inner: S,
+}
+
+impl<S> Service<PartitionInnerBuffer<Vec<Entry>, Labels>> for LokiService<S>
+where
+    S: Service<Vec<u8>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, batch: PartitionInnerBuffer<Vec<Entry>, Labels>) -> Self::Future {
+        let (entries, labels) = batch.into_parts();
+        self.inner.call(encode_body(entries, labels))
+    }
+}
+
+fn healthcheck(
+    endpoint: &str,
+    auth: Option<LokiBasicAuthConfig>,
+    tls_settings: TlsSettings,
+) -> crate::Result<super::Healthcheck> {
+    let uri = build_uri(endpoint, "ready")?;
+    let mut request = Request::get(&uri).body(Body::empty()).unwrap();
+
+    if let Some(auth) = auth {
+        auth.apply(request.headers_mut());
+    }
+
+    let client = https_client(tls_settings)?;
+
+    let healthcheck = client
+        .request(request)
+        .map_err(|err| err.into())
+        .and_then(|response| match response.status() {
+            hyper::StatusCode::OK => Ok(()),
+            status => Err(super::HealthcheckError::UnexpectedStatus { status }.into()),
+        });
+
+    Ok(Box::new(healthcheck))
+}
+
+fn build_uri(endpoint: &str, path: &str) -> crate::Result<Uri> {
+    let uri = format!("{}/{}", endpoint.trim_end_matches('/'), path);
+    Ok(uri.parse::<Uri>().context(super::UriParseError)?)
+}
+
+fn encode_event(
+    event: Event,
+    labels: &IndexMap<String, Template>,
+    encoding: &Encoding,
+) -> Option<PartitionInnerBuffer<Entry, Labels>> {
+    let mut key = Labels::new();
+    for (name, template) in labels {
+        match template.render_string(&event) {
+            Ok(value) => {
+                key.insert(name.clone(), value);
+            }
+            Err(missing_keys) => {
+                warn!(
+                    message = "Keys do not exist on the event; Dropping label.",
+                    label = &name[..],
+                    ?missing_keys,
+                    rate_limit_secs = 30,
+                );
+            }
+        }
+    }
+
+    let event = event.into_log();
+
+    let timestamp = match event.get(&event::TIMESTAMP).and_then(|v| v.as_timestamp()) {
+        Some(ts) => ts.timestamp_nanos(),
+        None => chrono::Utc::now().timestamp_nanos(),
+    };
+
+    let line = match encoding {
+        Encoding::Text => match event.get(&event::MESSAGE) {
+            Some(v) => v.to_string_lossy(),
+            None => {
+                warn!(
+                    message = "Event missing the message key; Dropping event.",
+                    rate_limit_secs = 30,
+                );
+                return None;
+            }
+        },
+        Encoding::Json => serde_json::to_string(&event.unflatten())
+            .map_err(|e| panic!("Unable to encode into JSON: {}", e))
+            .ok()?,
+    };
+
+    Some(PartitionInnerBuffer::new((timestamp, line), key))
+}
+
+/// Builds a push API request for a single stream. Loki rejects entries
+/// older than the newest one already accepted for a stream, so they are
+/// sent in timestamp order.
+fn encode_body(mut entries: Vec<Entry>, labels: Labels) -> Vec<u8> {
+    entries.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let values = entries
+        .into_iter()
+        .map(|(timestamp, line)| (timestamp.to_string(), line))
+        .collect::<Vec<_>>();
+
+    let body = serde_json::json!({
+        "streams": [{
+            "stream": labels,
+            "values": values,
+        }]
+    });
+
+    serde_json::to_vec(&body).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        runtime::Runtime,
+        test_util::{next_addr, shutdown_on_idle},
+        topology::config::SinkConfig,
+    };
+    use chrono::{TimeZone, Utc};
+    use futures::{stream, sync::mpsc, Stream};
+    use hyper::service::service_fn_ok;
+    use hyper::{Response, Server};
+
+    fn event(message: &str, app: &str, nanos: u32) -> Event {
+        let mut event = Event::from(message);
+        event.as_mut_log().insert_explicit("app".into(), app.into());
+        event.as_mut_log().insert_implicit(
+            event::TIMESTAMP.clone(),
+            Utc.ymd(2019, 11, 1).and_hms_nano(0, 0, 0, nanos).into(),
+        );
+        event
+    }
+
+    fn labels(config: &str) -> IndexMap<String, Template> {
+        toml::from_str::<LokiConfig>(config).unwrap().labels
+    }
+
+    #[test]
+    fn loki_encode_event_renders_labels() {
+        let labels = labels(
+            r#"
+            endpoint = "http://localhost:3100"
+            [labels]
+            job = "vector"
+            app = "{{ app }}"
+            missing = "{{ nope }}"
+            "#,
+        );
+
+        let (entry, key) = encode_event(event("hello", "web", 5), &labels, &Encoding::Text)
+            .unwrap()
+            .into_parts();
+
+        assert_eq!(entry, (1_572_566_400_000_000_005, "hello".to_string()));
+        assert_eq!(key.len(), 2);
+        assert_eq!(key["job"], "vector");
+        assert_eq!(key["app"], "web");
+    }
+
+    #[test]
+    fn loki_encode_event_json() {
+        let labels = labels(
+            r#"
+            endpoint = "http://localhost:3100"
+            labels = { job = "vector" }
+            "#,
+        );
+
+        let (entry, _) = encode_event(event("hello", "web", 0), &labels, &Encoding::Json)
+            .unwrap()
+            .into_parts();
+
+        let line: serde_json::Value = serde_json::from_str(&entry.1).unwrap();
+        assert_eq!(line["message"], "hello");
+        assert_eq!(line["app"], "web");
+    }
+
+    #[test]
+    fn loki_encode_body_sorts_entries() {
+        let mut labels = Labels::new();
+        labels.insert("job".into(), "vector".into());
+
+        let body = encode_body(
+            vec![(3, "c".into()), (1, "a".into()), (2, "b".into())],
+            labels,
+        );
+
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "streams": [{
+                    "stream": { "job": "vector" },
+                    "values": [["1", "a"], ["2", "b"], ["3", "c"]],
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn loki_requires_labels() {
+        let config = toml::from_str::<LokiConfig>(
+            r#"
+            endpoint = "http://localhost:3100"
+            labels = {}
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.build(Acker::Null).is_err());
+    }
+
+    #[test]
+    fn loki_happy_path() {
+        let in_addr = next_addr();
+
+        let config = r#"
+        endpoint = "http://$IN_ADDR/"
+        tenant_id = "tenant1"
+        batch_timeout = 1
+        [labels]
+        app = "{{ app }}"
+    "#
+        .replace("$IN_ADDR", &format!("{}", in_addr));
+        let config: LokiConfig = toml::from_str(&config).unwrap();
+
+        let (tx, rx) = mpsc::channel(100);
+        let service = move || {
+            let tx = tx.clone();
+            service_fn_ok(move |req: Request<Body>| {
+                let (parts, body) = req.into_parts();
+
+                let tx = tx.clone();
+                tokio::spawn(
+                    body.concat2()
+                        .map_err(|e| panic!(e))
+                        .and_then(|body| tx.send((parts, body)))
+                        .map(|_| ())
+                        .map_err(|e| panic!(e)),
+                );
+
+                Response::new(Body::empty())
+            })
+        };
+
+        let (trigger, tripwire) = stream_cancel::Tripwire::new();
+        let server = Server::bind(&in_addr)
+            .serve(service)
+            .with_graceful_shutdown(tripwire)
+            .map_err(|e| panic!("server error: {}", e));
+
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let events = vec![
+            event("second", "web", 2),
+            event("first", "web", 1),
+            event("other", "db", 3),
+        ];
+        let pump = sink.send_all(stream::iter_ok(events));
+
+        let mut rt = Runtime::new().unwrap();
+        rt.spawn(server);
+
+        let _ = rt.block_on(pump).unwrap();
+        drop(trigger);
+
+        let mut streams = rx
+            .wait()
+            .map(Result::unwrap)
+            .map(|(parts, body)| {
+                assert_eq!(hyper::Method::POST, parts.method);
+                assert_eq!("/loki/api/v1/push", parts.uri.path());
+                assert_eq!(
+                    Some("tenant1"),
+                    parts
+                        .headers
+                        .get("X-Scope-OrgID")
+                        .map(|v| v.to_str().unwrap())
+                );
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                body["streams"][0].clone()
+            })
+            .collect::<Vec<_>>();
+
+        shutdown_on_idle(rt);
+
+        streams.sort_by_key(|stream| stream["stream"]["app"].as_str().unwrap().to_string());
+        assert_eq!(
+            streams,
+            vec![
+                serde_json::json!({
+                    "stream": { "app": "db" },
+                    "values": [["1572566400000000003", "other"]],
+                }),
+                serde_json::json!({
+                    "stream": { "app": "web" },
+                    "values": [
+                        ["1572566400000000001", "first"],
+                        ["1572566400000000002", "second"],
+                    ],
+                }),
+            ]
+        );
+    }
+}
@@ -12,12 +12,17 @@ pub mod datadog_metrics;
 pub mod elasticsearch;
 pub mod file;
 pub mod http;
+pub mod influxdb;
 #[cfg(feature = "rdkafka")]
 pub mod kafka;
+pub mod loki;
 pub mod prometheus;
+pub mod prometheus_remote_write;
 pub mod splunk_hec;
 pub mod statsd;
 pub mod tcp;
+pub mod udp;
+pub mod unix;
 pub mod util;
 pub mod vector;
 
@@ -1,6 +1,6 @@
 use crate::{
     buffers::Acker,
-    event::{metric::Direction, Metric},
+    event::{CounterState, Metric, MetricKind},
     topology::config::{DataType, SinkConfig, SinkDescription},
     Event,
 };
@@ -12,12 +12,13 @@ use prometheus::{Encoder, Registry, TextEncoder};
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
     net::SocketAddr,
     ops::Add,
     sync::{
         mpsc::{channel, Sender},
-        Arc,
+        Arc, Mutex,
     },
     time::{Duration, Instant},
 };
@@ -101,15 +102,157 @@ struct PrometheusSink {
     flush_channel: Option<Sender<prometheus::IntGauge>>,
     config: PrometheusSinkConfig,
     counters: HashMap<String, prometheus::CounterVec>,
+    absolute_counters: CounterState,
     gauges: HashMap<String, prometheus::GaugeVec>,
     histograms: HashMap<String, prometheus::HistogramVec>,
     sets: HashMap<String, (prometheus::IntGaugeVec, HashSet<String>)>,
+    aggregated: Arc<Mutex<AggregatedFamilies>>,
     acker: Acker,
 }
 
+/// A metric name with its labels sorted by name.
+type SeriesKey = (String, Vec<(String, String)>);
+
+struct HistogramValue {
+    buckets: Vec<f64>,
+    counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+struct SummaryValue {
+    quantiles: Vec<f64>,
+    values: Vec<f64>,
+    count: u64,
+    sum: f64,
+}
+
+/// Aggregated histograms and summaries. The metric vectors of the
+/// `prometheus` crate can only build these from single observations, and
+/// its encoder can't write summaries, so they are written out here as one
+/// histogram or summary family per name.
+#[derive(Default)]
+struct AggregatedFamilies {
+    histograms: BTreeMap<SeriesKey, HistogramValue>,
+    summaries: BTreeMap<SeriesKey, SummaryValue>,
+}
+
+impl AggregatedFamilies {
+    fn add_histogram(&mut self, key: SeriesKey, value: HistogramValue, kind: MetricKind) {
+        match self.histograms.get_mut(&key) {
+            // increments can only be added up bucket by bucket
+            Some(existing) if kind.is_incremental() && existing.buckets == value.buckets => {
+                for (total, count) in existing.counts.iter_mut().zip(value.counts) {
+                    *total += count;
+                }
+                existing.count += value.count;
+                existing.sum += value.sum;
+            }
+            _ => {
+                self.histograms.insert(key, value);
+            }
+        }
+    }
+
+    fn add_summary(&mut self, key: SeriesKey, value: SummaryValue, kind: MetricKind) {
+        match self.summaries.get_mut(&key) {
+            // quantiles can't be added up, only replaced
+            Some(existing) if kind.is_incremental() => {
+                existing.quantiles = value.quantiles;
+                existing.values = value.values;
+                existing.count += value.count;
+                existing.sum += value.sum;
+            }
+            _ => {
+                self.summaries.insert(key, value);
+            }
+        }
+    }
+
+    /// Writes the families in the text exposition format.
+    fn encode(&self, output: &mut String) {
+        let mut family = None;
+        for ((name, labels), value) in &self.histograms {
+            if family != Some(name) {
+                write_family_header(output, name, "histogram");
+                family = Some(name);
+            }
+            let mut cumulative = 0;
+            for (bucket, count) in value.buckets.iter().zip(&value.counts) {
+                cumulative += count;
+                let le = Some(("le", format_value(*bucket)));
+                write_sample(output, name, "_bucket", labels, le, cumulative as f64);
+            }
+            let le = Some(("le", "+Inf".into()));
+            write_sample(output, name, "_bucket", labels, le, value.count as f64);
+            write_sample(output, name, "_sum", labels, None, value.sum);
+            write_sample(output, name, "_count", labels, None, value.count as f64);
+        }
+
+        let mut family = None;
+        for ((name, labels), value) in &self.summaries {
+            if family != Some(name) {
+                write_family_header(output, name, "summary");
+                family = Some(name);
+            }
+            for (quantile, value) in value.quantiles.iter().zip(&value.values) {
+                let quantile = Some(("quantile", format_value(*quantile)));
+                write_sample(output, name, "", labels, quantile, *value);
+            }
+            write_sample(output, name, "_sum", labels, None, value.sum);
+            write_sample(output, name, "_count", labels, None, value.count as f64);
+        }
+    }
+}
+
+fn write_family_header(output: &mut String, name: &str, kind: &str) {
+    let _ = writeln!(output, "# HELP {} {}", name, name);
+    let _ = writeln!(output, "# TYPE {} {}", name, kind);
+}
+
+fn write_sample(
+    output: &mut String,
+    name: &str,
+    suffix: &str,
+    labels: &[(String, String)],
+    extra: Option<(&str, String)>,
+    value: f64,
+) {
+    let labels = labels
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .chain(extra.as_ref().map(|(name, value)| (*name, value.as_str())))
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect::<Vec<_>>();
+
+    output.push_str(name);
+    output.push_str(suffix);
+    if !labels.is_empty() {
+        let _ = write!(output, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(output, " {}", format_value(value));
+}
+
+fn format_value(value: f64) -> String {
+    if value == std::f64::INFINITY {
+        "+Inf".into()
+    } else if value == std::f64::NEG_INFINITY {
+        "-Inf".into()
+    } else {
+        value.to_string()
+    }
+}
+
 fn handle(
     req: Request<Body>,
     registry: &Registry,
+    aggregated: &Mutex<AggregatedFamilies>,
 ) -> Box<dyn Future<Item = Response<Body>, Error = hyper::Error> + Send> {
     let mut response = Response::new(Body::empty());
 
@@ -119,6 +262,9 @@ fn handle(
             let encoder = TextEncoder::new();
             let metric_families = registry.gather();
             encoder.encode(&metric_families, &mut buffer).unwrap();
+            let mut text = String::new();
+            aggregated.lock().unwrap().encode(&mut text);
+            buffer.extend_from_slice(text.as_bytes());
             *response.body_mut() = buffer.into();
 
             response.headers_mut().insert(
@@ -146,9 +292,11 @@ impl PrometheusSink {
             flush_channel: None,
             config,
             counters: HashMap::new(),
+            absolute_counters: CounterState::default(),
             gauges: HashMap::new(),
             histograms: HashMap::new(),
             sets: HashMap::new(),
+            aggregated: Arc::new(Mutex::new(AggregatedFamilies::default())),
             acker,
         }
     }
@@ -251,14 +399,71 @@ impl PrometheusSink {
         }
     }
 
+    fn add_counter(&mut self, counter: Metric) {
+        // Prometheus counters only go up, so absolute values are added as
+        // their increase since the last one
+        let (name, val, tags) = match self.absolute_counters.incremental(counter) {
+            Some(Metric::Counter {
+                name, val, tags, ..
+            }) => (name, val, tags.unwrap_or_default()),
+            _ => return,
+        };
+        let labels = tags_to_labels(&tags);
+        self.with_counter(name, &labels, |counter| {
+            if let Ok(c) = counter.get_metric_with(&labels) {
+                c.inc_by(val);
+            } else {
+                error!(
+                    "Error getting Prometheus counter with labels: {:?}",
+                    &labels
+                );
+            }
+        })
+    }
+
+    fn add_gauge(
+        &mut self,
+        name: String,
+        val: f64,
+        tags: HashMap<String, String>,
+        kind: MetricKind,
+    ) {
+        let labels = tags_to_labels(&tags);
+        self.with_gauge(name, &labels, |gauge| {
+            if let Ok(g) = gauge.get_metric_with(&labels) {
+                match kind {
+                    MetricKind::Absolute => g.set(val),
+                    MetricKind::Incremental => g.add(val),
+                }
+            } else {
+                error!("Error getting Prometheus gauge with labels: {:?}", &labels);
+            }
+        })
+    }
+
+    fn series_key(&self, name: String, tags: Option<HashMap<String, String>>) -> SeriesKey {
+        let name = if self.config.namespace.is_empty() {
+            name
+        } else {
+            format!("{}_{}", self.config.namespace, name)
+        };
+        let mut labels = tags
+            .map(|tags| tags.into_iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        labels.sort();
+        (name, labels)
+    }
+
     fn start_server_if_needed(&mut self) {
         if self.server_shutdown_trigger.is_some() {
             return;
         }
 
         let registry = Arc::clone(&self.registry);
+        let aggregated = Arc::clone(&self.aggregated);
         let new_service = move || {
             let registry = Arc::clone(&registry);
+            let aggregated = Arc::clone(&aggregated);
 
             service_fn(move |req| {
                 info_span!(
@@ -266,7 +471,7 @@ impl PrometheusSink {
                     method = field::debug(req.method()),
                     path = field::debug(req.uri().path()),
                 )
-                .in_scope(|| handle(req, &registry))
+                .in_scope(|| handle(req, &registry, &aggregated))
             })
         };
 
@@ -327,43 +532,14 @@ impl Sink for PrometheusSink {
         self.start_server_if_needed();
 
         match event.into_metric() {
-            Metric::Counter {
-                name, val, tags, ..
-            } => {
-                let tags = tags.unwrap_or_default();
-                let labels = tags_to_labels(&tags);
-                self.with_counter(name, &labels, |counter| {
-                    if let Ok(c) = counter.get_metric_with(&labels) {
-                        c.inc_by(val);
-                    } else {
-                        error!(
-                            "Error getting Prometheus counter with labels: {:?}",
-                            &labels
-                        );
-                    }
-                })
-            }
+            counter @ Metric::Counter { .. } => self.add_counter(counter),
             Metric::Gauge {
                 name,
                 val,
-                direction,
                 tags,
+                kind,
                 ..
-            } => {
-                let tags = tags.unwrap_or_default();
-                let labels = tags_to_labels(&tags);
-                self.with_gauge(name, &labels, |gauge| {
-                    if let Ok(g) = gauge.get_metric_with(&labels) {
-                        match direction {
-                            None => g.set(val),
-                            Some(Direction::Plus) => g.add(val),
-                            Some(Direction::Minus) => g.sub(val),
-                        }
-                    } else {
-                        error!("Error getting Prometheus gauge with labels: {:?}", &labels);
-                    }
-                })
-            }
+            } => self.add_gauge(name, val, tags.unwrap_or_default(), kind),
             Metric::Histogram {
                 name,
                 val,
@@ -426,6 +602,50 @@ impl Sink for PrometheusSink {
                     }
                 });
             }
+            Metric::AggregatedHistogram {
+                name,
+                buckets,
+                counts,
+                count,
+                sum,
+                tags,
+                kind,
+                ..
+            } => {
+                let value = HistogramValue {
+                    buckets,
+                    counts,
+                    count,
+                    sum,
+                };
+                let key = self.series_key(name, tags);
+                self.aggregated
+                    .lock()
+                    .unwrap()
+                    .add_histogram(key, value, kind);
+            }
+            Metric::AggregatedSummary {
+                name,
+                quantiles,
+                values,
+                count,
+                sum,
+                tags,
+                kind,
+                ..
+            } => {
+                let value = SummaryValue {
+                    quantiles,
+                    values,
+                    count,
+                    sum,
+                };
+                let key = self.series_key(name, tags);
+                self.aggregated
+                    .lock()
+                    .unwrap()
+                    .add_summary(key, value, kind);
+            }
         }
 
         self.acker.ack(1);
@@ -443,3 +663,101 @@ impl Sink for PrometheusSink {
 fn tags_to_labels<'a>(tags: &'a HashMap<String, String>) -> HashMap<&'a str, &'a str> {
     tags.iter().map(|(k, v)| (k.as_ref(), v.as_ref())).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AggregatedFamilies, HistogramValue, SummaryValue};
+    use crate::event::MetricKind;
+    use pretty_assertions::assert_eq;
+
+    fn labels() -> Vec<(String, String)> {
+        vec![("code".to_owned(), "200".to_owned())]
+    }
+
+    #[test]
+    fn prometheus_aggregated_histograms_and_summaries() {
+        let mut families = AggregatedFamilies::default();
+        let histogram = |counts, count, sum| HistogramValue {
+            buckets: vec![0.5, 1.0],
+            counts,
+            count,
+            sum,
+        };
+        let key = ("vector_latency".to_owned(), labels());
+        families.add_histogram(
+            key.clone(),
+            histogram(vec![1, 2], 4, 5.0),
+            MetricKind::Incremental,
+        );
+        families.add_histogram(key, histogram(vec![1, 0], 1, 0.25), MetricKind::Incremental);
+        families.add_histogram(
+            ("vector_latency".to_owned(), vec![]),
+            histogram(vec![3, 0], 3, 1.0),
+            MetricKind::Absolute,
+        );
+
+        let summary = |values, count, sum| SummaryValue {
+            quantiles: vec![0.5, 0.99],
+            values,
+            count,
+            sum,
+        };
+        let key = ("vector_duration".to_owned(), labels());
+        families.add_summary(
+            key.clone(),
+            summary(vec![0.1, 0.2], 2, 0.3),
+            MetricKind::Absolute,
+        );
+        families.add_summary(key, summary(vec![0.3, 0.4], 5, 1.5), MetricKind::Absolute);
+
+        let mut output = String::new();
+        families.encode(&mut output);
+        assert_eq!(
+            output,
+            "# HELP vector_latency vector_latency\n\
+             # TYPE vector_latency histogram\n\
+             vector_latency_bucket{le=\"0.5\"} 3\n\
+             vector_latency_bucket{le=\"1\"} 3\n\
+             vector_latency_bucket{le=\"+Inf\"} 3\n\
+             vector_latency_sum 1\n\
+             vector_latency_count 3\n\
+             vector_latency_bucket{code=\"200\",le=\"0.5\"} 2\n\
+             vector_latency_bucket{code=\"200\",le=\"1\"} 4\n\
+             vector_latency_bucket{code=\"200\",le=\"+Inf\"} 5\n\
+             vector_latency_sum{code=\"200\"} 5.25\n\
+             vector_latency_count{code=\"200\"} 5\n\
+             # HELP vector_duration vector_duration\n\
+             # TYPE vector_duration summary\n\
+             vector_duration{code=\"200\",quantile=\"0.5\"} 0.3\n\
+             vector_duration{code=\"200\",quantile=\"0.99\"} 0.4\n\
+             vector_duration_sum{code=\"200\"} 1.5\n\
+             vector_duration_count{code=\"200\"} 5\n"
+        );
+    }
+
+    #[test]
+    fn prometheus_escapes_label_values() {
+        let mut families = AggregatedFamilies::default();
+        let labels = vec![("path".to_owned(), "C:\\\"tmp\"\n".to_owned())];
+        families.add_summary(
+            ("duration".to_owned(), labels),
+            SummaryValue {
+                quantiles: vec![],
+                values: vec![],
+                count: 1,
+                sum: std::f64::INFINITY,
+            },
+            MetricKind::Absolute,
+        );
+
+        let mut output = String::new();
+        families.encode(&mut output);
+        assert_eq!(
+            output,
+            "# HELP duration duration\n\
+             # TYPE duration summary\n\
+             duration_sum{path=\"C:\\\\\\\"tmp\\\"\\n\"} +Inf\n\
+             duration_count{path=\"C:\\\\\\\"tmp\\\"\\n\"} 1\n"
+        );
+    }
+}
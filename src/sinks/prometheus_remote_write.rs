@@ -0,0 +1,704 @@
+use crate::{
+    buffers::Acker,
+    event::{Metric, MetricKind},
+    sinks::util::{
+        http::{HttpRetryLogic, HttpService},
+        retries::FixedRetryPolicy,
+        tls::{TlsOptions, TlsSettings},
+        BatchConfig, BatchServiceSink, MetricBuffer, SinkExt,
+    },
+    topology::config::{DataType, SinkConfig, SinkDescription},
+};
+use chrono::{DateTime, Utc};
+use futures::{future, Poll};
+use http::{Method, Uri};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::time::Duration;
+use tower::{Service, ServiceBuilder};
+
+mod proto {
+    include!(concat!(env!("OUT_DIR"), "/prometheus.rs"));
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteWriteConfig {
+    pub namespace: String,
+    pub endpoint: String,
+    #[serde(default = "super::prometheus::default_histogram_buckets")]
+    pub buckets: Vec<f64>,
+    #[serde(default, flatten)]
+    pub batch: BatchConfig,
+
+    // Tower Request based configuration
+    pub request_in_flight_limit: Option<usize>,
+    pub request_timeout_secs: Option<u64>,
+    pub request_rate_limit_duration_secs: Option<u64>,
+    pub request_rate_limit_num: Option<u64>,
+    pub request_retry_attempts: Option<usize>,
+    pub request_retry_backoff_secs: Option<u64>,
+
+    pub tls: Option<TlsOptions>,
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<RemoteWriteConfig>("prometheus_remote_write")
+}
+
+#[typetag::serde(name = "prometheus_remote_write")]
+impl SinkConfig for RemoteWriteConfig {
+    fn build(&self, acker: Acker) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        let sink = remote_write(self.clone(), acker)?;
+        // The remote write protocol has no endpoint to check without
+        // writing samples.
+        let healthcheck = Box::new(future::ok(()));
+
+        Ok((sink, healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "prometheus_remote_write"
+    }
+}
+
+fn remote_write(config: RemoteWriteConfig, acker: Acker) -> crate::Result<super::RouterSink> {
+    let batch = config.batch.unwrap_or(1000, 1);
+
+    let timeout = config.request_timeout_secs.unwrap_or(30);
+    let in_flight_limit = config.request_in_flight_limit.unwrap_or(5);
+    let rate_limit_duration = config.request_rate_limit_duration_secs.unwrap_or(1);
+    let rate_limit_num = config.request_rate_limit_num.unwrap_or(5);
+    let retry_attempts = config.request_retry_attempts.unwrap_or(5);
+    let retry_backoff_secs = config.request_retry_backoff_secs.unwrap_or(1);
+
+    let policy = FixedRetryPolicy::new(
+        retry_attempts,
+        Duration::from_secs(retry_backoff_secs),
+        HttpRetryLogic,
+    );
+
+    let uri = config
+        .endpoint
+        .parse::<Uri>()
+        .context(super::UriParseError)?;
+    let tls_settings = TlsSettings::from_options(&config.tls)?;
+
+    let http_service =
+        HttpService::builder()
+            .tls_settings(tls_settings)
+            .build(move |body: Vec<u8>| {
+                let mut builder = hyper::Request::builder();
+                builder.method(Method::POST);
+                builder.uri(uri.clone());
+
+                builder.header("Content-Type", "application/x-protobuf");
+                builder.header("Content-Encoding", "snappy");
+                builder.header("X-Prometheus-Remote-Write-Version", "0.1.0");
+
+                builder.body(body).unwrap()
+            });
+
+    let service = ServiceBuilder::new()
+        .concurrency_limit(in_flight_limit)
+        .rate_limit(rate_limit_num, Duration::from_secs(rate_limit_duration))
+        .retry(policy)
+        .timeout(Duration::from_secs(timeout))
+        .service(http_service);
+
+    let remote_write_service = RemoteWriteService {
+        state: RemoteWriteState::new(config.namespace, config.buckets),
+        inner: service,
+    };
+
+    let sink = BatchServiceSink::new(remote_write_service, acker)
+        .batched_with_min(MetricBuffer::new(), &batch);
+
+    Ok(Box::new(sink))
+}
+
+/// Encodes each batch into a compressed `WriteRequest` before handing it
+/// to the wrapped HTTP service. This sits outside of the retry layer, so
+/// retried requests don't count the same samples twice.
+struct RemoteWriteService<S> {
+    state: RemoteWriteState,
+    inner: S,
+}
+
+impl<S> Service<Vec<Metric>> for RemoteWriteService<S>
+where
+    S: Service<Vec<u8>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.inner.poll_ready()
+    }
+
+    fn call(&mut self, items: Vec<Metric>) -> Self::Future {
+        let request = self.state.encode(items, Utc::now().timestamp_millis());
+
+        let mut buffer = Vec::with_capacity(request.encoded_len());
+        request.encode(&mut buffer).unwrap();
+        let body = snap::raw::Encoder::new().compress_vec(&buffer).unwrap();
+
+        self.inner.call(body)
+    }
+}
+
+/// A metric name with its labels sorted by name.
+type SeriesKey = (String, Vec<(String, String)>);
+
+struct HistogramState {
+    /// Cumulative counts of observations less than or equal to each bucket.
+    counts: Vec<f64>,
+    count: f64,
+    sum: f64,
+}
+
+/// Prometheus expects counters and histograms as running totals, while
+/// Vector's are increments. The totals are kept here for as long as the
+/// sink runs; Prometheus handles the reset when it restarts like that
+/// of any other process.
+struct RemoteWriteState {
+    namespace: String,
+    buckets: Vec<f64>,
+    counters: HashMap<SeriesKey, f64>,
+    histograms: HashMap<SeriesKey, HistogramState>,
+    /// Aggregated histograms bring their own buckets, kept with the totals.
+    aggregated_histograms: HashMap<SeriesKey, (Vec<f64>, HistogramState)>,
+}
+
+impl RemoteWriteState {
+    fn new(namespace: String, buckets: Vec<f64>) -> Self {
+        Self {
+            namespace,
+            buckets,
+            counters: HashMap::new(),
+            histograms: HashMap::new(),
+            aggregated_histograms: HashMap::new(),
+        }
+    }
+
+    fn key(&self, name: String, tags: Option<HashMap<String, String>>) -> SeriesKey {
+        let name = if self.namespace.is_empty() {
+            name
+        } else {
+            format!("{}_{}", self.namespace, name)
+        };
+        let mut labels = tags
+            .map(|tags| tags.into_iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        labels.sort();
+        (name, labels)
+    }
+
+    fn encode(&mut self, metrics: Vec<Metric>, now: i64) -> proto::WriteRequest {
+        let mut timeseries = Vec::new();
+        let mut sets = HashMap::new();
+        // Histograms are written once per batch, with the totals of all of
+        // their values in it and the latest timestamp.
+        let mut histograms = HashMap::new();
+        let mut aggregated_histograms = HashMap::new();
+
+        for metric in metrics {
+            match metric {
+                Metric::Counter {
+                    name,
+                    val,
+                    timestamp,
+                    tags,
+                    kind: MetricKind::Incremental,
+                } => {
+                    let key = self.key(name, tags);
+                    let total = self.counters.entry(key.clone()).or_insert(0.0);
+                    *total += val;
+                    timeseries.push(series(&key, None, *total, encode_timestamp(timestamp, now)));
+                }
+                Metric::Gauge {
+                    name,
+                    val,
+                    timestamp,
+                    tags,
+                    kind: MetricKind::Absolute,
+                } => {
+                    let key = self.key(name, tags);
+                    timeseries.push(series(&key, None, val, encode_timestamp(timestamp, now)));
+                }
+                Metric::Histogram {
+                    name,
+                    val,
+                    sample_rate,
+                    timestamp,
+                    tags,
+                    ..
+                } => {
+                    let key = self.key(name, tags);
+                    let buckets = &self.buckets;
+                    let state =
+                        self.histograms
+                            .entry(key.clone())
+                            .or_insert_with(|| HistogramState {
+                                counts: vec![0.0; buckets.len()],
+                                count: 0.0,
+                                sum: 0.0,
+                            });
+
+                    let sample_rate = f64::from(sample_rate);
+                    for (bucket, count) in buckets.iter().zip(state.counts.iter_mut()) {
+                        if val <= *bucket {
+                            *count += sample_rate;
+                        }
+                    }
+                    state.count += sample_rate;
+                    state.sum += val * sample_rate;
+
+                    let timestamp = encode_timestamp(timestamp, now);
+                    let latest = histograms.entry(key).or_insert(timestamp);
+                    *latest = (*latest).max(timestamp);
+                }
+                Metric::AggregatedHistogram {
+                    name,
+                    buckets,
+                    counts,
+                    count,
+                    sum,
+                    timestamp,
+                    tags,
+                    kind: MetricKind::Incremental,
+                } => {
+                    let key = self.key(name, tags);
+                    let (state_buckets, state) = self
+                        .aggregated_histograms
+                        .entry(key.clone())
+                        .or_insert_with(|| {
+                            (
+                                Vec::new(),
+                                HistogramState {
+                                    counts: Vec::new(),
+                                    count: 0.0,
+                                    sum: 0.0,
+                                },
+                            )
+                        });
+
+                    // totals can't carry over to different buckets
+                    if *state_buckets != buckets {
+                        *state_buckets = buckets;
+                        *state = HistogramState {
+                            counts: vec![0.0; state_buckets.len()],
+                            count: 0.0,
+                            sum: 0.0,
+                        };
+                    }
+
+                    let mut cumulative = 0.0;
+                    for (total, bucket_count) in state.counts.iter_mut().zip(counts) {
+                        cumulative += bucket_count as f64;
+                        *total += cumulative;
+                    }
+                    state.count += count as f64;
+                    state.sum += sum;
+
+                    let timestamp = encode_timestamp(timestamp, now);
+                    let latest = aggregated_histograms.entry(key).or_insert(timestamp);
+                    *latest = (*latest).max(timestamp);
+                }
+                Metric::AggregatedSummary {
+                    name,
+                    quantiles,
+                    values,
+                    count,
+                    sum,
+                    timestamp,
+                    tags,
+                    kind: MetricKind::Absolute,
+                } => {
+                    let key = self.key(name, tags);
+                    let timestamp = encode_timestamp(timestamp, now);
+                    for (quantile, value) in quantiles.iter().zip(values) {
+                        let quantile = ("quantile", quantile.to_string());
+                        timeseries.push(series(&key, Some(quantile), value, timestamp));
+                    }
+
+                    let (name, labels) = &key;
+                    let sum_key = (format!("{}_sum", name), labels.clone());
+                    timeseries.push(series(&sum_key, None, sum, timestamp));
+                    let count_key = (format!("{}_count", name), labels.clone());
+                    timeseries.push(series(&count_key, None, count as f64, timestamp));
+                }
+                // Sets are sent as a gauge of the unique values in the batch.
+                Metric::Set {
+                    name,
+                    timestamp,
+                    tags,
+                    ..
+                } => {
+                    let key = self.key(name, tags);
+                    let timestamp = encode_timestamp(timestamp, now);
+                    let set = sets.entry(key).or_insert((0.0, timestamp));
+                    set.0 += 1.0;
+                    set.1 = set.1.max(timestamp);
+                }
+                // `MetricBuffer` hands over counters and histograms as
+                // increments and gauges and summaries as absolute values.
+                metric => warn!(
+                    message = "Unsupported metric kind; Dropping metric.",
+                    name = metric.name(),
+                    kind = ?metric.kind(),
+                    rate_limit_secs = 30,
+                ),
+            }
+        }
+
+        for (key, (count, timestamp)) in sets {
+            timeseries.push(series(&key, None, count, timestamp));
+        }
+        for (key, timestamp) in histograms {
+            let state = &self.histograms[&key];
+            histogram_series(&key, &self.buckets, state, timestamp, &mut timeseries);
+        }
+        for (key, timestamp) in aggregated_histograms {
+            let (buckets, state) = &self.aggregated_histograms[&key];
+            histogram_series(&key, buckets, state, timestamp, &mut timeseries);
+        }
+
+        proto::WriteRequest { timeseries }
+    }
+}
+
+fn histogram_series(
+    (name, labels): &SeriesKey,
+    buckets: &[f64],
+    state: &HistogramState,
+    timestamp: i64,
+    timeseries: &mut Vec<proto::TimeSeries>,
+) {
+    let bucket_key = (format!("{}_bucket", name), labels.clone());
+    for (bucket, count) in buckets.iter().zip(state.counts.iter()) {
+        let le = ("le", bucket.to_string());
+        timeseries.push(series(&bucket_key, Some(le), *count, timestamp));
+    }
+    let le = ("le", "+Inf".to_string());
+    timeseries.push(series(&bucket_key, Some(le), state.count, timestamp));
+
+    let sum_key = (format!("{}_sum", name), labels.clone());
+    timeseries.push(series(&sum_key, None, state.sum, timestamp));
+    let count_key = (format!("{}_count", name), labels.clone());
+    timeseries.push(series(&count_key, None, state.count, timestamp));
+}
+
+fn encode_timestamp(timestamp: Option<DateTime<Utc>>, now: i64) -> i64 {
+    timestamp.map(|ts| ts.timestamp_millis()).unwrap_or(now)
+}
+
+fn series(
+    (name, labels): &SeriesKey,
+    extra: Option<(&str, String)>,
+    value: f64,
+    timestamp: i64,
+) -> proto::TimeSeries {
+    let mut labels = labels
+        .iter()
+        .map(|(name, value)| proto::Label {
+            name: name.clone(),
+            value: value.clone(),
+        })
+        .collect::<Vec<_>>();
+    labels.push(proto::Label {
+        name: "__name__".into(),
+        value: name.clone(),
+    });
+    if let Some((name, value)) = extra {
+        labels.push(proto::Label {
+            name: name.into(),
+            value,
+        });
+    }
+    labels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    proto::TimeSeries {
+        labels,
+        samples: vec![proto::Sample { value, timestamp }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::Event,
+        runtime::Runtime,
+        test_util::{next_addr, shutdown_on_idle},
+    };
+    use chrono::offset::TimeZone;
+    use futures::{stream, sync::mpsc, Future, Sink, Stream};
+    use hyper::service::service_fn_ok;
+    use hyper::{Body, Request, Response, Server};
+    use pretty_assertions::assert_eq;
+
+    fn ts() -> DateTime<Utc> {
+        Utc.ymd(2018, 11, 14).and_hms_nano(8, 9, 10, 11)
+    }
+
+    fn tags() -> Option<HashMap<String, String>> {
+        Some(
+            vec![("region".to_owned(), "us-west".to_owned())]
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    /// Flattens the series into `name{labels} value timestamp` lines.
+    fn lines(request: proto::WriteRequest) -> Vec<String> {
+        request
+            .timeseries
+            .into_iter()
+            .map(|series| {
+                let labels = series
+                    .labels
+                    .iter()
+                    .map(|label| format!("{}={}", label.name, label.value))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let sample = &series.samples[0];
+                format!("{{{}}} {} {}", labels, sample.value, sample.timestamp)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn remote_write_counters_are_cumulative() {
+        let mut state = RemoteWriteState::new("vector".into(), vec![]);
+        let counter = |val| Metric::Counter {
+            name: "requests".into(),
+            val,
+            timestamp: None,
+            tags: tags(),
+            kind: MetricKind::Incremental,
+        };
+
+        let first = state.encode(vec![counter(1.0)], 10);
+        let second = state.encode(vec![counter(2.5)], 20);
+
+        assert_eq!(
+            lines(first),
+            vec!["{__name__=vector_requests,region=us-west} 1 10"]
+        );
+        assert_eq!(
+            lines(second),
+            vec!["{__name__=vector_requests,region=us-west} 3.5 20"]
+        );
+    }
+
+    #[test]
+    fn remote_write_gauges_and_sets() {
+        let mut state = RemoteWriteState::new("".into(), vec![]);
+        let mut metrics = vec![
+            Metric::Gauge {
+                name: "temperature".into(),
+                val: -4.0,
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Absolute,
+            },
+            Metric::Gauge {
+                name: "relative".into(),
+                val: 1.0,
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Incremental,
+            },
+        ];
+        for user in &["a", "b"] {
+            metrics.push(Metric::Set {
+                name: "users".into(),
+                val: user.to_string(),
+                timestamp: Some(ts()),
+                tags: None,
+                kind: MetricKind::Incremental,
+            });
+        }
+
+        assert_eq!(
+            lines(state.encode(metrics, 0)),
+            vec![
+                "{__name__=temperature} -4 1542182950000",
+                "{__name__=users} 2 1542182950000",
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_write_histograms() {
+        let mut state = RemoteWriteState::new("".into(), vec![1.0, 2.5]);
+        let histogram = |val, sample_rate| Metric::Histogram {
+            name: "latency".into(),
+            val,
+            sample_rate,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        };
+
+        state.encode(vec![histogram(0.5, 2)], 0);
+        let request = state.encode(vec![histogram(2.0, 1)], 5);
+
+        assert_eq!(
+            lines(request),
+            vec![
+                "{__name__=latency_bucket,le=1} 2 5",
+                "{__name__=latency_bucket,le=2.5} 3 5",
+                "{__name__=latency_bucket,le=+Inf} 3 5",
+                "{__name__=latency_sum} 3 5",
+                "{__name__=latency_count} 3 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_write_aggregated_histograms_and_summaries() {
+        let mut state = RemoteWriteState::new("".into(), vec![]);
+        let histogram = |counts, count, sum| Metric::AggregatedHistogram {
+            name: "latency".into(),
+            buckets: vec![1.0, 2.5],
+            counts,
+            count,
+            sum,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        };
+        let summary = Metric::AggregatedSummary {
+            name: "duration".into(),
+            quantiles: vec![0.5],
+            values: vec![0.75],
+            count: 2,
+            sum: 1.5,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        };
+
+        state.encode(vec![histogram(vec![2, 0], 2, 1.0)], 0);
+        let request = state.encode(vec![histogram(vec![0, 1], 2, 5.0), summary], 5);
+
+        assert_eq!(
+            lines(request),
+            vec![
+                "{__name__=duration,quantile=0.5} 0.75 5",
+                "{__name__=duration_sum} 1.5 5",
+                "{__name__=duration_count} 2 5",
+                "{__name__=latency_bucket,le=1} 2 5",
+                "{__name__=latency_bucket,le=2.5} 3 5",
+                "{__name__=latency_bucket,le=+Inf} 4 5",
+                "{__name__=latency_sum} 6 5",
+                "{__name__=latency_count} 4 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_write_merges_histogram_values_in_a_batch() {
+        let mut state = RemoteWriteState::new("".into(), vec![1.0, 2.5]);
+        let histogram = |val, timestamp| Metric::Histogram {
+            name: "latency".into(),
+            val,
+            sample_rate: 1,
+            timestamp: Some(Utc.timestamp(timestamp, 0)),
+            tags: tags(),
+            kind: MetricKind::Incremental,
+        };
+
+        let request = state.encode(vec![histogram(0.5, 10), histogram(2.0, 20)], 0);
+
+        assert_eq!(
+            lines(request),
+            vec![
+                "{__name__=latency_bucket,le=1,region=us-west} 1 20000",
+                "{__name__=latency_bucket,le=2.5,region=us-west} 2 20000",
+                "{__name__=latency_bucket,le=+Inf,region=us-west} 2 20000",
+                "{__name__=latency_sum,region=us-west} 2.5 20000",
+                "{__name__=latency_count,region=us-west} 2 20000",
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_write_happy_path() {
+        let in_addr = next_addr();
+
+        let config = r#"
+        namespace = "vector"
+        endpoint = "http://$IN_ADDR/api/v1/write"
+    "#
+        .replace("$IN_ADDR", &format!("{}", in_addr));
+        let config: RemoteWriteConfig = toml::from_str(&config).unwrap();
+
+        let (tx, rx) = mpsc::channel(100);
+        let service = move || {
+            let tx = tx.clone();
+            service_fn_ok(move |req: Request<Body>| {
+                let (parts, body) = req.into_parts();
+
+                let tx = tx.clone();
+                tokio::spawn(
+                    body.concat2()
+                        .map_err(|e| panic!(e))
+                        .and_then(|body| tx.send((parts, body)))
+                        .map(|_| ())
+                        .map_err(|e| panic!(e)),
+                );
+
+                Response::new(Body::empty())
+            })
+        };
+
+        let (trigger, tripwire) = stream_cancel::Tripwire::new();
+        let server = Server::bind(&in_addr)
+            .serve(service)
+            .with_graceful_shutdown(tripwire)
+            .map_err(|e| panic!("server error: {}", e));
+
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let events = vec![Event::Metric(Metric::Counter {
+            name: "requests".into(),
+            val: 2.0,
+            timestamp: Some(ts()),
+            tags: tags(),
+            kind: MetricKind::Incremental,
+        })];
+        let pump = sink.send_all(stream::iter_ok(events));
+
+        let mut rt = Runtime::new().unwrap();
+        rt.spawn(server);
+
+        let _ = rt.block_on(pump).unwrap();
+        drop(trigger);
+
+        let requests = rx.wait().map(Result::unwrap).collect::<Vec<_>>();
+
+        shutdown_on_idle(rt);
+
+        assert_eq!(requests.len(), 1);
+        let (parts, body) = &requests[0];
+        assert_eq!(parts.uri.path(), "/api/v1/write");
+        assert_eq!(parts.headers["Content-Encoding"], "snappy");
+
+        let body = snap::raw::Decoder::new().decompress_vec(&body[..]).unwrap();
+        let request = proto::WriteRequest::decode(body).unwrap();
+        assert_eq!(
+            lines(request),
+            vec!["{__name__=vector_requests,region=us-west} 2 1542182950000"]
+        );
+    }
+}
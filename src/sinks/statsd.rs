@@ -1,14 +1,23 @@
 use crate::{
     buffers::Acker,
-    event::{metric::Direction, Event, Metric},
-    sinks::util::{BatchConfig, BatchServiceSink, Buffer, SinkExt},
+    event::{CounterState, Event, Metric, MetricKind},
+    sinks::{
+        tcp::{resolve_address, tcp_healthcheck, tls_settings, TcpConnector, TcpSink, TlsConfig},
+        util::{
+            unix::{unix_healthcheck, UnixConnector, UnixSink},
+            BatchConfig, BatchServiceSink, Buffer, SinkExt,
+        },
+    },
     topology::config::{DataType, SinkConfig, SinkDescription},
 };
-use futures::{future, sink::Sink, Future, Poll};
-use serde::{Deserialize, Serialize};
+use bytes::Bytes;
+use derive_is_enum_variant::is_enum_variant;
+use futures::{future, sink::Sink, stream::iter_ok, Future, Poll};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use snafu::{ResultExt, Snafu};
 use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
 use tower::{Service, ServiceBuilder};
 
 #[derive(Debug, Snafu)]
@@ -41,20 +50,110 @@ impl Client {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(deny_unknown_fields)]
+#[derive(Serialize, Debug, Clone)]
 pub struct StatsdSinkConfig {
     pub namespace: String,
-    #[serde(default = "default_address")]
-    pub address: SocketAddr,
-    #[serde(default, flatten)]
+    #[serde(flatten)]
+    pub mode: Mode,
+    pub dogstatsd_tags: bool,
+    #[serde(flatten)]
     pub batch: BatchConfig,
 }
 
+#[derive(Serialize, Debug, Clone, is_enum_variant)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Mode {
+    Tcp {
+        address: String,
+        tls: Option<TlsConfig>,
+    },
+    Udp {
+        address: SocketAddr,
+    },
+    Unix {
+        path: PathBuf,
+    },
+}
+
+/// The options of every mode, so that unknown options can be rejected,
+/// which serde can't do with flattened fields. The mode defaults to udp, the
+/// only one before there was a choice.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StatsdSinkOptions {
+    namespace: String,
+    #[serde(default)]
+    mode: ModeName,
+    address: Option<String>,
+    tls: Option<TlsConfig>,
+    path: Option<PathBuf>,
+    #[serde(default = "default_dogstatsd_tags")]
+    dogstatsd_tags: bool,
+    batch_size: Option<usize>,
+    batch_timeout: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ModeName {
+    Tcp,
+    Udp,
+    Unix,
+}
+
+impl Default for ModeName {
+    fn default() -> Self {
+        ModeName::Udp
+    }
+}
+
+impl<'de> Deserialize<'de> for StatsdSinkConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let options = StatsdSinkOptions::deserialize(deserializer)?;
+        let mode = match (options.mode, options.address, options.tls, options.path) {
+            (ModeName::Tcp, Some(address), tls, None) => Mode::Tcp { address, tls },
+            (ModeName::Udp, address, None, None) => Mode::Udp {
+                address: match address {
+                    Some(address) => address.parse().map_err(de::Error::custom)?,
+                    None => default_address(),
+                },
+            },
+            (ModeName::Unix, None, None, Some(path)) => Mode::Unix { path },
+            (ModeName::Tcp, _, _, _) => {
+                return Err(de::Error::custom(
+                    "tcp mode takes an `address`, optionally `tls`, and no `path`",
+                ))
+            }
+            (ModeName::Udp, _, _, _) => {
+                return Err(de::Error::custom("udp mode takes no `tls` or `path`"))
+            }
+            (ModeName::Unix, _, _, _) => {
+                return Err(de::Error::custom(
+                    "unix mode takes a `path` and no `address` or `tls`",
+                ))
+            }
+        };
+
+        Ok(Self {
+            namespace: options.namespace,
+            mode,
+            dogstatsd_tags: options.dogstatsd_tags,
+            batch: BatchConfig {
+                batch_size: options.batch_size,
+                batch_timeout: options.batch_timeout,
+            },
+        })
+    }
+}
+
 pub fn default_address() -> SocketAddr {
     SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8125)
 }
 
+fn default_dogstatsd_tags() -> bool {
+    true
+}
+
 inventory::submit! {
     SinkDescription::new_without_default::<StatsdSinkConfig>("statsd")
 }
@@ -62,9 +161,27 @@ inventory::submit! {
 #[typetag::serde(name = "statsd")]
 impl SinkConfig for StatsdSinkConfig {
     fn build(&self, acker: Acker) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
-        let sink = StatsdSvc::new(self.clone(), acker)?;
-        let healthcheck = StatsdSvc::healthcheck(self.clone())?;
-        Ok((sink, healthcheck))
+        let mut encode = self.encoder();
+        match &self.mode {
+            Mode::Tcp { address, tls } => {
+                let addr = resolve_address(address)?;
+                let tls = tls_settings(tls)?;
+                let sink = TcpSink::new(TcpConnector::new(address.clone(), addr, tls))
+                    .stream_ack(acker)
+                    .with_flat_map(move |event| iter_ok(encode(event).map(Bytes::from)));
+                Ok((Box::new(sink), tcp_healthcheck(addr)))
+            }
+            Mode::Udp { address } => {
+                let sink = StatsdSvc::new(*address, &self.batch, acker, encode)?;
+                Ok((sink, Box::new(future::ok(()))))
+            }
+            Mode::Unix { path } => {
+                let sink = UnixSink::new(UnixConnector::new(path.clone()))
+                    .stream_ack(acker)
+                    .with_flat_map(move |event| iter_ok(encode(event).map(Bytes::from)));
+                Ok((Box::new(sink), unix_healthcheck(path.clone())))
+            }
+        }
     }
 
     fn input_type(&self) -> DataType {
@@ -76,31 +193,49 @@ impl SinkConfig for StatsdSinkConfig {
     }
 }
 
+impl StatsdSinkConfig {
+    /// Encodes events into newline terminated statsd lines. Statsd counters
+    /// and histograms are always increments, so absolute ones are sent as
+    /// their increase since the previous value of the series.
+    fn encoder(&self) -> impl FnMut(Event) -> Option<Vec<u8>> {
+        let namespace = self.namespace.clone();
+        let dogstatsd_tags = self.dogstatsd_tags;
+        let mut counters = CounterState::default();
+        move |event| {
+            counters
+                .incremental(event.into_metric())
+                .and_then(|metric| {
+                    encode_event(Event::Metric(metric), &namespace, dogstatsd_tags).ok()
+                })
+        }
+    }
+}
+
 impl StatsdSvc {
-    pub fn new(config: StatsdSinkConfig, acker: Acker) -> crate::Result<super::RouterSink> {
+    pub fn new(
+        address: SocketAddr,
+        batch: &BatchConfig,
+        acker: Acker,
+        mut encode: impl FnMut(Event) -> Option<Vec<u8>> + Send + 'static,
+    ) -> crate::Result<super::RouterSink> {
         // 1432 bytes is a recommended packet size to fit into MTU
         // https://github.com/statsd/statsd/blob/master/docs/metric_types.md#multi-metric-packets
         // However we need to leave some space for +1 extra trailing event in the buffer.
         // Also one might keep an eye on server side limitations, like
         // mentioned here https://github.com/DataDog/dd-agent/issues/2638
-        let batch = config.batch.unwrap_or(1300, 1);
-        let namespace = config.namespace.clone();
+        let batch = batch.unwrap_or(1300, 1);
 
-        let client = Client::new(config.address)?;
+        let client = Client::new(address)?;
         let service = StatsdSvc { client };
 
         let svc = ServiceBuilder::new().service(service);
 
         let sink = BatchServiceSink::new(svc, acker)
             .batched_with_min(Buffer::new(false), &batch)
-            .with(move |event| encode_event(event, &namespace));
+            .with_flat_map(move |event| iter_ok(encode(event)));
 
         Ok(Box::new(sink))
     }
-
-    fn healthcheck(_config: StatsdSinkConfig) -> crate::Result<super::Healthcheck> {
-        Ok(Box::new(future::ok(())))
-    }
 }
 
 fn encode_tags(tags: &HashMap<String, String>) -> String {
@@ -118,42 +253,55 @@ fn encode_tags(tags: &HashMap<String, String>) -> String {
     parts.join(",")
 }
 
-fn encode_event(event: Event, namespace: &str) -> Result<Vec<u8>, ()> {
+fn encode_event(event: Event, namespace: &str, dogstatsd_tags: bool) -> Result<Vec<u8>, ()> {
     let mut buf = Vec::new();
 
     match event.as_metric() {
-        Metric::Counter {
-            name, val, tags, ..
-        } => {
+        Metric::Counter { name, val, .. } => {
             buf.push(format!("{}:{}", name, val));
             buf.push("c".to_string());
-            if let Some(t) = tags {
-                buf.push(format!("#{}", encode_tags(t)));
-            };
         }
         Metric::Gauge {
             name,
             val,
-            direction,
             tags,
+            kind,
             ..
         } => {
-            let val_with_direction = match direction {
-                None => format!("{}", val),
-                Some(Direction::Plus) => format!("+{}", val),
-                Some(Direction::Minus) => format!("-{}", val),
+            let val_with_direction = match kind {
+                // a leading sign makes statsd apply the value as a change,
+                // so negative values are set by resetting the gauge to zero
+                // and then decrementing it
+                MetricKind::Absolute if *val < 0.0 => {
+                    let gauge = |val, kind| {
+                        Event::Metric(Metric::Gauge {
+                            name: name.clone(),
+                            val,
+                            timestamp: None,
+                            tags: tags.clone(),
+                            kind,
+                        })
+                    };
+                    let mut body =
+                        encode_event(gauge(0.0, MetricKind::Absolute), namespace, dogstatsd_tags)?;
+                    body.extend(encode_event(
+                        gauge(*val, MetricKind::Incremental),
+                        namespace,
+                        dogstatsd_tags,
+                    )?);
+                    return Ok(body);
+                }
+                MetricKind::Absolute => format!("{}", val),
+                MetricKind::Incremental if *val < 0.0 => format!("{}", val),
+                MetricKind::Incremental => format!("+{}", val),
             };
             buf.push(format!("{}:{}", name, val_with_direction));
             buf.push("g".to_string());
-            if let Some(t) = tags {
-                buf.push(format!("#{}", encode_tags(t)));
-            };
         }
         Metric::Histogram {
             name,
             val,
             sample_rate,
-            tags,
             ..
         } => {
             buf.push(format!("{}:{}", name, val));
@@ -161,21 +309,50 @@ fn encode_event(event: Event, namespace: &str) -> Result<Vec<u8>, ()> {
             if *sample_rate != 1 {
                 buf.push(format!("@{}", 1.0 / f64::from(*sample_rate)));
             };
-            if let Some(t) = tags {
-                buf.push(format!("#{}", encode_tags(t)));
-            };
         }
-        Metric::Set {
-            name, val, tags, ..
-        } => {
+        Metric::Set { name, val, .. } => {
             buf.push(format!("{}:{}", name, val));
             buf.push("s".to_string());
-            if let Some(t) = tags {
-                buf.push(format!("#{}", encode_tags(t)));
-            };
+        }
+        // each bucket is sent as a sampled observation of its upper bound,
+        // so samples above the largest bound can't be represented
+        Metric::AggregatedHistogram {
+            name,
+            buckets,
+            counts,
+            tags,
+            ..
+        } => {
+            let mut body = Vec::new();
+            for (bucket, count) in buckets.iter().zip(counts).filter(|(_, c)| **c > 0) {
+                let hist = Event::Metric(Metric::Histogram {
+                    name: name.clone(),
+                    val: *bucket,
+                    sample_rate: (*count).min(u64::from(u32::max_value())) as u32,
+                    timestamp: None,
+                    tags: tags.clone(),
+                    kind: MetricKind::Incremental,
+                });
+                body.extend(encode_event(hist, namespace, dogstatsd_tags)?);
+            }
+            return Ok(body);
+        }
+        Metric::AggregatedSummary { name, .. } => {
+            debug!(
+                message = "Statsd can't represent aggregated summaries, dropping.",
+                name = &name[..],
+                rate_limit_secs = 30
+            );
+            return Err(());
         }
     };
 
+    if dogstatsd_tags {
+        if let Some(tags) = event.as_metric().tags() {
+            buf.push(format!("#{}", encode_tags(tags)));
+        }
+    }
+
     let mut message: String = buf.join("|");
     if !namespace.is_empty() {
         message = format!("{}.{}", namespace, message);
@@ -212,12 +389,15 @@ mod test {
     use crate::{
         buffers::Acker,
         sources::statsd::parser::parse,
-        test_util::{collect_n, runtime},
+        test_util::{collect_n, next_addr, receive, runtime, temp_dir},
         Event,
     };
     use bytes::Bytes;
     use futures::{stream, stream::Stream, sync::mpsc, Sink};
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
     use std::str::from_utf8;
+    use std::thread;
     use tokio::{
         self,
         codec::BytesCodec,
@@ -242,6 +422,113 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_config() {
+        let config: StatsdSinkConfig = toml::from_str(
+            r#"
+            namespace = "vector"
+            mode = "tcp"
+            address = "localhost:8125"
+
+            [tls]
+            enabled = true
+          "#,
+        )
+        .unwrap();
+        assert!(config.mode.is_tcp());
+        assert!(config.dogstatsd_tags);
+
+        let config: StatsdSinkConfig = toml::from_str(
+            r#"
+            namespace = "vector"
+            mode = "udp"
+            dogstatsd_tags = false
+          "#,
+        )
+        .unwrap();
+        match config.mode {
+            Mode::Udp { address } => assert_eq!(address, default_address()),
+            _ => panic!("expected the udp mode"),
+        }
+        assert!(!config.dogstatsd_tags);
+
+        let config: StatsdSinkConfig = toml::from_str(
+            r#"
+            namespace = "vector"
+            mode = "unix"
+            path = "/tmp/statsd.sock"
+          "#,
+        )
+        .unwrap();
+        assert!(config.mode.is_unix());
+    }
+
+    #[test]
+    fn test_config_defaults_to_udp() {
+        // The only options before modes were added
+        let config: StatsdSinkConfig = toml::from_str(
+            r#"
+            namespace = "vector"
+            batch_size = 512
+          "#,
+        )
+        .unwrap();
+        match config.mode {
+            Mode::Udp { address } => assert_eq!(address, default_address()),
+            _ => panic!("expected the udp mode"),
+        }
+        assert_eq!(config.batch.batch_size, Some(512));
+
+        let config: StatsdSinkConfig = toml::from_str(
+            r#"
+            namespace = "vector"
+            address = "127.0.0.1:9125"
+          "#,
+        )
+        .unwrap();
+        match config.mode {
+            Mode::Udp { address } => assert_eq!(address, "127.0.0.1:9125".parse().unwrap()),
+            _ => panic!("expected the udp mode"),
+        }
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_options() {
+        let invalid = vec![
+            r#"namespace = "vector"
+               adress = "127.0.0.1:8125""#,
+            r#"namespace = "vector"
+               path = "/tmp/statsd.sock""#,
+            r#"namespace = "vector"
+               mode = "unix"
+               address = "127.0.0.1:8125""#,
+            r#"namespace = "vector"
+               mode = "tcp""#,
+            r#"namespace = "vector"
+               address = "localhost""#,
+        ];
+        for config in invalid {
+            assert!(
+                toml::from_str::<StatsdSinkConfig>(config).is_err(),
+                "accepted {}",
+                config
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_without_dogstatsd_tags() {
+        let event = Event::Metric(Metric::Counter {
+            name: "counter".to_owned(),
+            val: 1.5,
+            timestamp: None,
+            tags: Some(tags()),
+            kind: MetricKind::Incremental,
+        });
+        let frame = encode_event(event, "ns", false).unwrap();
+        assert_eq!(from_utf8(&frame).unwrap(), "ns.counter:1.5|c\n");
+    }
+
     #[test]
     fn test_encode_counter() {
         let metric1 = Metric::Counter {
@@ -249,9 +536,10 @@ mod test {
             val: 1.5,
             timestamp: None,
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         };
         let event = Event::Metric(metric1.clone());
-        let frame = &encode_event(event, "").unwrap();
+        let frame = &encode_event(event, "", true).unwrap();
         let metric2 = parse(from_utf8(&frame).unwrap().trim()).unwrap();
         assert_eq!(metric1, metric2);
     }
@@ -261,12 +549,12 @@ mod test {
         let metric1 = Metric::Gauge {
             name: "gauge".to_owned(),
             val: 1.5,
-            direction: Some(Direction::Minus),
             timestamp: None,
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         };
         let event = Event::Metric(metric1.clone());
-        let frame = &encode_event(event, "").unwrap();
+        let frame = &encode_event(event, "", true).unwrap();
         let metric2 = parse(from_utf8(&frame).unwrap().trim()).unwrap();
         assert_eq!(metric1, metric2);
     }
@@ -279,9 +567,10 @@ mod test {
             sample_rate: 1,
             timestamp: None,
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         };
         let event = Event::Metric(metric1.clone());
-        let frame = &encode_event(event, "").unwrap();
+        let frame = &encode_event(event, "", true).unwrap();
         let metric2 = parse(from_utf8(&frame).unwrap().trim()).unwrap();
         assert_eq!(metric1, metric2);
     }
@@ -293,18 +582,64 @@ mod test {
             val: "abc".to_owned(),
             timestamp: None,
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         };
         let event = Event::Metric(metric1.clone());
-        let frame = &encode_event(event, "").unwrap();
+        let frame = &encode_event(event, "", true).unwrap();
         let metric2 = parse(from_utf8(&frame).unwrap().trim()).unwrap();
         assert_eq!(metric1, metric2);
     }
 
+    #[test]
+    fn test_encode_negative_gauges() {
+        let gauge = |val, kind| {
+            Event::Metric(Metric::Gauge {
+                name: "gauge".to_owned(),
+                val,
+                timestamp: None,
+                tags: None,
+                kind,
+            })
+        };
+
+        let frame = encode_event(gauge(-2.5, MetricKind::Incremental), "ns", true).unwrap();
+        assert_eq!(from_utf8(&frame).unwrap(), "ns.gauge:-2.5|g\n");
+
+        let frame = encode_event(gauge(-2.5, MetricKind::Absolute), "ns", true).unwrap();
+        assert_eq!(
+            from_utf8(&frame).unwrap(),
+            "ns.gauge:0|g\nns.gauge:-2.5|g\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_aggregated_histogram() {
+        let event = Event::Metric(Metric::AggregatedHistogram {
+            name: "hist".to_owned(),
+            buckets: vec![1.0, 2.5, 5.0],
+            counts: vec![4, 0, 1],
+            count: 6,
+            sum: 10.0,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        });
+
+        let frame = encode_event(event, "ns", true).unwrap();
+        assert_eq!(
+            from_utf8(&frame).unwrap(),
+            "ns.hist:1|h|@0.25\nns.hist:5|h\n"
+        );
+    }
+
     #[test]
     fn test_send_to_statsd() {
         let config = StatsdSinkConfig {
             namespace: "vector".into(),
-            address: default_address(),
+            mode: Mode::Udp {
+                address: default_address(),
+            },
+            dogstatsd_tags: true,
             batch: BatchConfig {
                 batch_size: Some(512),
                 batch_timeout: Some(1),
@@ -312,7 +647,7 @@ mod test {
         };
 
         let mut rt = runtime();
-        let sink = StatsdSvc::new(config, Acker::Null).unwrap();
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
 
         let mut events = Vec::new();
         let event = Event::Metric(Metric::Counter {
@@ -320,6 +655,7 @@ mod test {
             val: 1.5,
             timestamp: None,
             tags: Some(tags()),
+            kind: MetricKind::Incremental,
         });
         events.push(event);
 
@@ -329,6 +665,7 @@ mod test {
             sample_rate: 100,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Incremental,
         });
         events.push(event);
 
@@ -360,4 +697,88 @@ mod test {
             Bytes::from("vector.counter:1.5|c|#empty_tag:,normal_tag:value,true_tag\nvector.histogram:2|h|@0.01")
         );
     }
+
+    fn stream_events() -> Vec<Event> {
+        vec![
+            Event::Metric(Metric::Counter {
+                name: "counter".to_owned(),
+                val: 1.5,
+                timestamp: None,
+                tags: Some(tags()),
+                kind: MetricKind::Incremental,
+            }),
+            Event::Metric(Metric::Gauge {
+                name: "gauge".to_owned(),
+                val: 2.0,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Absolute,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_send_to_statsd_tcp() {
+        let addr = next_addr();
+        let config = StatsdSinkConfig {
+            namespace: "vector".into(),
+            mode: Mode::Tcp {
+                address: addr.to_string(),
+                tls: None,
+            },
+            dogstatsd_tags: false,
+            batch: BatchConfig::default(),
+        };
+
+        let mut rt = runtime();
+        let receiver = receive(&addr);
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let stream = stream::iter_ok(stream_events().into_iter());
+        let _ = rt.block_on(sink.send_all(stream)).unwrap();
+
+        // Give the lines some time to arrive
+        thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(
+            receiver.wait(),
+            vec!["vector.counter:1.5|c", "vector.gauge:2|g"]
+        );
+    }
+
+    #[test]
+    fn test_send_to_statsd_unix() {
+        let path = temp_dir().with_extension("sock");
+        let config = StatsdSinkConfig {
+            namespace: "vector".into(),
+            mode: Mode::Unix { path: path.clone() },
+            dogstatsd_tags: true,
+            batch: BatchConfig::default(),
+        };
+
+        let listener = UnixListener::bind(&path).unwrap();
+        let receiver = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            BufReader::new(socket)
+                .lines()
+                .take(2)
+                .map(|line| line.unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let mut rt = runtime();
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let stream = stream::iter_ok(stream_events().into_iter());
+        let _ = rt.block_on(sink.send_all(stream)).unwrap();
+
+        assert_eq!(
+            receiver.join().unwrap(),
+            vec![
+                "vector.counter:1.5|c|#empty_tag:,normal_tag:value,true_tag",
+                "vector.gauge:2|g"
+            ]
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
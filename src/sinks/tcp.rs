@@ -2,27 +2,23 @@ use crate::{
     buffers::Acker,
     event::{self, Event},
     sinks::util::{
+        reconnect::{Connector, ReconnectingSink},
         tls::{TlsConnectorExt, TlsOptions, TlsSettings},
         SinkExt,
     },
     topology::config::{DataType, SinkConfig, SinkDescription},
 };
 use bytes::Bytes;
-use futures::{
-    future, stream::iter_ok, try_ready, Async, AsyncSink, Future, Poll, Sink, StartSend,
-};
+use futures::{future, stream::iter_ok, Future, Sink};
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
+use std::fmt;
 use std::net::{SocketAddr, ToSocketAddrs};
-use std::time::{Duration, Instant};
 use tokio::{
     codec::{BytesCodec, FramedWrite},
-    net::tcp::{ConnectFuture, TcpStream},
-    timer::Delay,
+    net::tcp::TcpStream,
 };
-use tokio_retry::strategy::ExponentialBackoff;
-use tokio_tls::{Connect as TlsConnect, TlsConnector, TlsStream};
-use tracing::field;
+use tokio_tls::{TlsConnector, TlsStream};
 
 #[derive(Debug, Snafu)]
 enum BuildError {
@@ -36,6 +32,12 @@ enum BuildError {
     DerExportError { source: openssl::error::ErrorStack },
 }
 
+#[derive(Debug, Snafu)]
+enum TlsConnectError {
+    #[snafu(display("Could not negotiate TLS: {}", source))]
+    TlsNegotiateError { source: native_tls::Error },
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct TcpSinkConfig {
@@ -76,25 +78,8 @@ inventory::submit! {
 #[typetag::serde(name = "tcp")]
 impl SinkConfig for TcpSinkConfig {
     fn build(&self, acker: Acker) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
-        let addr = self
-            .address
-            .to_socket_addrs()
-            .context(super::SocketAddressError)?
-            .next()
-            .ok_or(Box::new(super::BuildError::DNSFailure {
-                address: self.address.clone(),
-            }))?;
-
-        let tls = match self.tls {
-            Some(ref tls) => {
-                if tls.enabled.unwrap_or(false) {
-                    Some(TlsSettings::from_options(&Some(tls.options.clone()))?)
-                } else {
-                    None
-                }
-            }
-            None => None,
-        };
+        let addr = resolve_address(&self.address)?;
+        let tls = tls_settings(&self.tls)?;
 
         let sink = raw_tcp(
             self.address.clone(),
@@ -117,176 +102,95 @@ impl SinkConfig for TcpSinkConfig {
     }
 }
 
-pub struct TcpSink {
+/// Resolves `address` to the first socket address it names.
+pub fn resolve_address(address: &str) -> crate::Result<SocketAddr> {
+    let addr = address
+        .to_socket_addrs()
+        .context(super::SocketAddressError)?
+        .next()
+        .ok_or(Box::new(super::BuildError::DNSFailure {
+            address: address.to_owned(),
+        }))?;
+    Ok(addr)
+}
+
+/// Builds the TLS settings to connect with, or `None` if TLS isn't enabled.
+pub fn tls_settings(tls: &Option<TlsConfig>) -> crate::Result<Option<TlsSettings>> {
+    match tls {
+        Some(tls) if tls.enabled.unwrap_or(false) => {
+            Ok(Some(TlsSettings::from_options(&Some(tls.options.clone()))?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Writes frames to a TCP connection, optionally over TLS, reconnecting with
+/// a backoff whenever the connection is lost.
+pub type TcpSink = ReconnectingSink<TcpConnector>;
+
+pub struct TcpConnector {
     hostname: String,
     addr: SocketAddr,
     tls: Option<TlsSettings>,
-    state: TcpSinkState,
-    backoff: ExponentialBackoff,
-}
-
-enum TcpSinkState {
-    Disconnected,
-    Connecting(ConnectFuture),
-    TlsConnecting(TlsConnect<TcpStream>),
-    Connected(TcpOrTlsStream),
-    Backoff(Delay),
 }
 
-type TcpOrTlsStream = MaybeTlsStream<
+pub type TcpOrTlsStream = MaybeTlsStream<
     FramedWrite<TcpStream, BytesCodec>,
     FramedWrite<TlsStream<TcpStream>, BytesCodec>,
 >;
 
-impl TcpSink {
+impl TcpConnector {
     pub fn new(hostname: String, addr: SocketAddr, tls: Option<TlsSettings>) -> Self {
         Self {
             hostname,
             addr,
             tls,
-            state: TcpSinkState::Disconnected,
-            backoff: Self::fresh_backoff(),
-        }
-    }
-
-    fn fresh_backoff() -> ExponentialBackoff {
-        // TODO: make configurable
-        ExponentialBackoff::from_millis(2)
-            .factor(250)
-            .max_delay(Duration::from_secs(60))
-    }
-
-    fn next_delay(&mut self) -> Delay {
-        Delay::new(Instant::now() + self.backoff.next().unwrap())
-    }
-
-    fn poll_connection(&mut self) -> Poll<&mut TcpOrTlsStream, ()> {
-        loop {
-            self.state = match self.state {
-                TcpSinkState::Disconnected => {
-                    debug!(message = "connecting", addr = &field::display(&self.addr));
-                    TcpSinkState::Connecting(TcpStream::connect(&self.addr))
-                }
-                TcpSinkState::Backoff(ref mut delay) => match delay.poll() {
-                    Ok(Async::NotReady) => return Ok(Async::NotReady),
-                    // Err can only occur if the tokio runtime has been shutdown or if more than 2^63 timers have been created
-                    Err(err) => unreachable!(err),
-                    Ok(Async::Ready(())) => {
-                        debug!(
-                            message = "disconnected.",
-                            addr = &field::display(&self.addr)
-                        );
-                        TcpSinkState::Disconnected
-                    }
-                },
-                TcpSinkState::Connecting(ref mut connect_future) => match connect_future.poll() {
-                    Ok(Async::Ready(socket)) => {
-                        let addr = socket.peer_addr().unwrap_or(self.addr);
-                        debug!(message = "connected", addr = &field::display(&addr));
-                        self.backoff = Self::fresh_backoff();
-                        match self.tls {
-                            Some(ref tls) => match native_tls::TlsConnector::builder()
-                                .use_tls_settings(tls.clone())
-                                .build()
-                                .context(TlsBuildError)
-                            {
-                                Ok(connector) => TcpSinkState::TlsConnecting(
-                                    TlsConnector::from(connector).connect(&self.hostname, socket),
-                                ),
-                                Err(err) => {
-                                    error!(message = "unable to establish TLS connection.", error = %err);
-                                    TcpSinkState::Backoff(self.next_delay())
-                                }
-                            },
-                            None => TcpSinkState::Connected(MaybeTlsStream::Raw(FramedWrite::new(
-                                socket,
-                                BytesCodec::new(),
-                            ))),
-                        }
-                    }
-                    Ok(Async::NotReady) => {
-                        return Ok(Async::NotReady);
-                    }
-                    Err(err) => {
-                        error!("Error connecting to {}: {}", self.addr, err);
-                        TcpSinkState::Backoff(self.next_delay())
-                    }
-                },
-                TcpSinkState::TlsConnecting(ref mut connect_future) => {
-                    match connect_future.poll() {
-                        Ok(Async::Ready(socket)) => {
-                            debug!(message = "negotiated TLS.");
-                            self.backoff = Self::fresh_backoff();
-                            TcpSinkState::Connected(MaybeTlsStream::Tls(FramedWrite::new(
-                                socket,
-                                BytesCodec::new(),
-                            )))
-                        }
-                        Ok(Async::NotReady) => return Ok(Async::NotReady),
-                        Err(err) => {
-                            error!(message = "unable to negotiate TLS.", addr = %self.addr, error = %err);
-                            TcpSinkState::Backoff(self.next_delay())
-                        }
-                    }
-                }
-                TcpSinkState::Connected(ref mut connection) => {
-                    return Ok(Async::Ready(connection));
-                }
-            };
         }
     }
 }
 
-impl Sink for TcpSink {
-    type SinkItem = Bytes;
-    type SinkError = ();
-
-    fn start_send(&mut self, line: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
-        match self.poll_connection() {
-            Ok(Async::Ready(connection)) => {
-                debug!(
-                    message = "sending event.",
-                    bytes = &field::display(line.len())
-                );
-                match connection.start_send(line) {
-                    Err(err) => {
-                        debug!(
-                            message = "disconnected.",
-                            addr = &field::display(&self.addr)
-                        );
-                        error!("Error in connection {}: {}", self.addr, err);
-                        self.state = TcpSinkState::Disconnected;
-                        Ok(AsyncSink::Ready)
-                    }
-                    Ok(ok) => Ok(ok),
-                }
-            }
-            Ok(Async::NotReady) => Ok(AsyncSink::NotReady(line)),
-            Err(_) => unreachable!(),
-        }
+impl fmt::Display for TcpConnector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.addr)
     }
+}
 
-    fn poll_complete(&mut self) -> Result<Async<()>, Self::SinkError> {
-        // Stream::forward will immediately poll_complete the sink it's forwarding to,
-        // but we don't want to connect before the first event actually comes through.
-        if let TcpSinkState::Disconnected = self.state {
-            return Ok(Async::Ready(()));
-        }
-
-        let connection = try_ready!(self.poll_connection());
-
-        match connection.poll_complete() {
-            Err(err) => {
-                debug!(
-                    message = "disconnected.",
-                    addr = &field::display(&self.addr)
-                );
-                error!("Error in connection {}: {}", self.addr, err);
-                self.state = TcpSinkState::Disconnected;
-                Ok(Async::Ready(()))
-            }
-            Ok(ok) => Ok(ok),
-        }
+impl Connector for TcpConnector {
+    type Connection = TcpOrTlsStream;
+    type Future = Box<dyn Future<Item = Self::Connection, Error = crate::Error> + Send>;
+
+    fn connect(&self) -> Self::Future {
+        let hostname = self.hostname.clone();
+        let tls = self.tls.clone();
+
+        let connect = TcpStream::connect(&self.addr)
+            .map_err(crate::Error::from)
+            .and_then(move |socket| match tls {
+                Some(tls) => future::Either::A(
+                    future::result(
+                        native_tls::TlsConnector::builder()
+                            .use_tls_settings(tls)
+                            .build()
+                            .context(TlsBuildError),
+                    )
+                    .map_err(crate::Error::from)
+                    .and_then(move |connector| {
+                        TlsConnector::from(connector)
+                            .connect(&hostname, socket)
+                            .map_err(|source| TlsConnectError::TlsNegotiateError { source }.into())
+                    })
+                    .map(|socket| {
+                        debug!(message = "negotiated TLS.");
+                        MaybeTlsStream::Tls(FramedWrite::new(socket, BytesCodec::new()))
+                    }),
+                ),
+                None => future::Either::B(future::ok(MaybeTlsStream::Raw(FramedWrite::new(
+                    socket,
+                    BytesCodec::new(),
+                )))),
+            });
+
+        Box::new(connect)
     }
 }
 
@@ -298,7 +202,7 @@ pub fn raw_tcp(
     tls: Option<TlsSettings>,
 ) -> super::RouterSink {
     Box::new(
-        TcpSink::new(hostname, addr, tls)
+        TcpSink::new(TcpConnector::new(hostname, addr, tls))
             .stream_ack(acker)
             .with_flat_map(move |event| iter_ok(encode_event(event, &encoding))),
     )
@@ -321,7 +225,15 @@ pub fn tcp_healthcheck(addr: SocketAddr) -> super::Healthcheck {
     Box::new(check)
 }
 
-fn encode_event(event: Event, encoding: &Encoding) -> Option<Bytes> {
+pub fn encode_event(event: Event, encoding: &Encoding) -> Option<Bytes> {
+    encode_log(event, encoding).map(|mut b| {
+        b.push(b'\n');
+        Bytes::from(b)
+    })
+}
+
+/// Encodes a log event without any trailing delimiter.
+pub fn encode_log(event: Event, encoding: &Encoding) -> Option<Vec<u8>> {
     let log = event.into_log();
 
     let b = match encoding {
@@ -335,15 +247,11 @@ fn encode_event(event: Event, encoding: &Encoding) -> Option<Bytes> {
         }
     };
 
-    b.map(|mut b| {
-        b.push(b'\n');
-        Bytes::from(b)
-    })
-    .map_err(|error| error!(message = "Unable to encode.", %error))
-    .ok()
+    b.map_err(|error| error!(message = "Unable to encode.", %error))
+        .ok()
 }
 
-enum MaybeTlsStream<R, T> {
+pub enum MaybeTlsStream<R, T> {
     Raw(R),
     Tls(T),
 }
@@ -0,0 +1,196 @@
+use crate::{
+    buffers::Acker,
+    event::Event,
+    sinks::{
+        tcp::{encode_log, resolve_address, Encoding},
+        util::SinkExt,
+    },
+    topology::config::{DataType, SinkConfig, SinkDescription},
+};
+use bytes::Bytes;
+use futures::{future, stream::iter_ok, Async, AsyncSink, Poll, Sink, StartSend};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::net::UdpSocket;
+use tracing::field;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Failed to bind to udp socket: {}", source))]
+    SocketBindError { source: std::io::Error },
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UdpSinkConfig {
+    pub address: String,
+    pub encoding: Encoding,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+}
+
+/// The largest payload that fits in a single IPv4 UDP datagram.
+pub fn default_max_length() -> usize {
+    65507
+}
+
+impl UdpSinkConfig {
+    pub fn new(address: String) -> Self {
+        Self {
+            address,
+            encoding: Encoding::Text,
+            max_length: default_max_length(),
+        }
+    }
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<UdpSinkConfig>("udp")
+}
+
+#[typetag::serde(name = "udp")]
+impl SinkConfig for UdpSinkConfig {
+    fn build(&self, acker: Acker) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        let addr = resolve_address(&self.address)?;
+
+        let unspecified = match addr {
+            SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        let socket = UdpSocket::bind(&SocketAddr::new(unspecified, 0)).context(SocketBindError)?;
+
+        let encoding = self.encoding.clone();
+        let max_length = self.max_length;
+        let sink = UdpSink::new(socket, addr)
+            .stream_ack(acker)
+            .with_flat_map(move |event| iter_ok(encode_datagram(event, &encoding, max_length)));
+
+        // There is no connection to check, datagrams are sent blindly
+        Ok((Box::new(sink), Box::new(future::ok(()))))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "udp"
+    }
+}
+
+/// Encodes the event as the payload of a single datagram, dropping it if
+/// it doesn't fit in `max_length` bytes.
+pub fn encode_datagram(event: Event, encoding: &Encoding, max_length: usize) -> Option<Bytes> {
+    let payload = encode_log(event, encoding)?;
+    if payload.len() > max_length {
+        warn!(
+            message = "Event is larger than the maximum datagram size; Dropping event.",
+            bytes = payload.len(),
+            max_length,
+            rate_limit_secs = 30,
+        );
+        return None;
+    }
+    Some(Bytes::from(payload))
+}
+
+/// Sends each frame as a datagram to `addr`. Failed sends are logged and the
+/// frame dropped, as there is no connection to recover, so frames are
+/// delivered at most once.
+pub struct UdpSink {
+    socket: UdpSocket,
+    addr: SocketAddr,
+}
+
+impl UdpSink {
+    pub fn new(socket: UdpSocket, addr: SocketAddr) -> Self {
+        Self { socket, addr }
+    }
+}
+
+impl Sink for UdpSink {
+    type SinkItem = Bytes;
+    type SinkError = ();
+
+    fn start_send(&mut self, frame: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        match self.socket.poll_send_to(&frame, &self.addr) {
+            Ok(Async::Ready(_)) => {
+                debug!(
+                    message = "sent datagram.",
+                    bytes = &field::display(frame.len())
+                );
+                Ok(AsyncSink::Ready)
+            }
+            Ok(Async::NotReady) => Ok(AsyncSink::NotReady(frame)),
+            Err(error) => {
+                error!(
+                    message = "error sending datagram.",
+                    addr = &field::display(&self.addr),
+                    %error,
+                    rate_limit_secs = 30,
+                );
+                Ok(AsyncSink::Ready)
+            }
+        }
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        Ok(Async::Ready(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        event::{self, Event},
+        test_util::{next_addr, runtime},
+    };
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    #[test]
+    fn udp_sends_datagrams() {
+        let addr = next_addr();
+        let receiver = UdpSocket::bind(&addr).unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let config = UdpSinkConfig::new(addr.to_string());
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let events = vec![
+            Event::from("first line"),
+            Event::from("x".repeat(100_000)),
+            Event::from("second line"),
+        ];
+
+        let mut rt = runtime();
+        let _ = rt
+            .block_on(sink.send_all(futures::stream::iter_ok(events.into_iter())))
+            .unwrap();
+
+        // the oversized event is dropped
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"first line");
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"second line");
+    }
+
+    #[test]
+    fn udp_encodes_json() {
+        let mut event = Event::from("hello");
+        event
+            .as_mut_log()
+            .insert_explicit("key".into(), "value".into());
+
+        let bytes = encode_datagram(event, &Encoding::Json, default_max_length()).unwrap();
+        let map: std::collections::HashMap<String, String> =
+            serde_json::from_slice(&bytes[..]).unwrap();
+        assert_eq!(map[&event::MESSAGE.to_string()], "hello");
+        assert_eq!(map["key"], "value");
+    }
+}
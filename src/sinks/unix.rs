@@ -0,0 +1,244 @@
+use crate::{
+    buffers::Acker,
+    sinks::{
+        tcp::{encode_event, Encoding},
+        udp::{default_max_length, encode_datagram},
+        util::{
+            unix::{unix_healthcheck, UnixConnector, UnixSink},
+            SinkExt,
+        },
+    },
+    topology::config::{DataType, SinkConfig, SinkDescription},
+};
+use bytes::Bytes;
+use futures::{future, stream::iter_ok, Async, AsyncSink, Poll, Sink, StartSend};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::path::PathBuf;
+use tokio_uds::UnixDatagram;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Failed to create unix datagram socket: {}", source))]
+    SocketCreateError { source: std::io::Error },
+}
+
+#[derive(Debug, Snafu)]
+enum HealthcheckError {
+    #[snafu(display("Connect error: {}", source))]
+    ConnectError { source: std::io::Error },
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UnixSinkConfig {
+    pub path: PathBuf,
+    pub encoding: Encoding,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    Stream,
+    Datagram,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Stream
+    }
+}
+
+impl UnixSinkConfig {
+    pub fn new(path: PathBuf, mode: Mode) -> Self {
+        Self {
+            path,
+            encoding: Encoding::Text,
+            mode,
+            max_length: default_max_length(),
+        }
+    }
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<UnixSinkConfig>("unix")
+}
+
+#[typetag::serde(name = "unix")]
+impl SinkConfig for UnixSinkConfig {
+    fn build(&self, acker: Acker) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        let encoding = self.encoding.clone();
+        match self.mode {
+            Mode::Stream => {
+                let sink = UnixSink::new(UnixConnector::new(self.path.clone()))
+                    .stream_ack(acker)
+                    .with_flat_map(move |event| iter_ok(encode_event(event, &encoding)));
+                Ok((Box::new(sink), unix_healthcheck(self.path.clone())))
+            }
+            Mode::Datagram => {
+                let socket = UnixDatagram::unbound().context(SocketCreateError)?;
+                let max_length = self.max_length;
+                let sink = UnixDatagramSink::new(socket, self.path.clone())
+                    .stream_ack(acker)
+                    .with_flat_map(move |event| {
+                        iter_ok(encode_datagram(event, &encoding, max_length))
+                    });
+                Ok((Box::new(sink), datagram_healthcheck(self.path.clone())))
+            }
+        }
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "unix"
+    }
+}
+
+fn datagram_healthcheck(path: PathBuf) -> super::Healthcheck {
+    // Connecting fails unless something is bound to the path
+    let check = future::lazy(move || {
+        std::os::unix::net::UnixDatagram::unbound()
+            .and_then(|socket| socket.connect(&path))
+            .map_err(|source| HealthcheckError::ConnectError { source }.into())
+    });
+
+    Box::new(check)
+}
+
+/// Sends each frame as a datagram to the socket at `path`. Failed sends are
+/// logged and the frame dropped, as there is no connection to recover, so
+/// frames are delivered at most once.
+pub struct UnixDatagramSink {
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl UnixDatagramSink {
+    pub fn new(socket: UnixDatagram, path: PathBuf) -> Self {
+        Self { socket, path }
+    }
+}
+
+impl Sink for UnixDatagramSink {
+    type SinkItem = Bytes;
+    type SinkError = ();
+
+    fn start_send(&mut self, frame: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        match self.socket.poll_send_to(&frame, &self.path) {
+            Ok(Async::Ready(_)) => {
+                debug!(message = "sent datagram.", bytes = frame.len());
+                Ok(AsyncSink::Ready)
+            }
+            Ok(Async::NotReady) => Ok(AsyncSink::NotReady(frame)),
+            Err(error) => {
+                error!(
+                    message = "error sending datagram.",
+                    path = ?self.path,
+                    %error,
+                    rate_limit_secs = 30,
+                );
+                Ok(AsyncSink::Ready)
+            }
+        }
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        Ok(Async::Ready(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_util::{runtime, temp_dir},
+        Event,
+    };
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::{UnixDatagram, UnixListener};
+    use std::thread;
+    use std::time::Duration;
+
+    fn events() -> Vec<Event> {
+        vec![Event::from("first line"), Event::from("second line")]
+    }
+
+    #[test]
+    fn unix_config_defaults_to_stream() {
+        let config: UnixSinkConfig = toml::from_str(
+            r#"
+            path = "/tmp/vector.sock"
+            encoding = "text"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.mode, Mode::Stream);
+        assert_eq!(config.max_length, default_max_length());
+    }
+
+    #[test]
+    fn unix_sends_lines() {
+        let path = temp_dir().with_extension("sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let receiver = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            BufReader::new(socket)
+                .lines()
+                .take(2)
+                .map(|line| line.unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        let config = UnixSinkConfig::new(path.clone(), Mode::Stream);
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let mut rt = runtime();
+        let _ = rt
+            .block_on(sink.send_all(futures::stream::iter_ok(events().into_iter())))
+            .unwrap();
+
+        assert_eq!(receiver.join().unwrap(), vec!["first line", "second line"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unix_sends_datagrams() {
+        let path = temp_dir().with_extension("sock");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let mut config = UnixSinkConfig::new(path.clone(), Mode::Datagram);
+        config.max_length = 10;
+        let (sink, _healthcheck) = config.build(Acker::Null).unwrap();
+
+        let events = vec![
+            Event::from("short"),
+            Event::from("longer than ten bytes"),
+            Event::from("tiny"),
+        ];
+
+        let mut rt = runtime();
+        let _ = rt
+            .block_on(sink.send_all(futures::stream::iter_ok(events.into_iter())))
+            .unwrap();
+
+        // the oversized event is dropped
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"short");
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"tiny");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
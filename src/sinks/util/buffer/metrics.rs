@@ -1,4 +1,4 @@
-use crate::event::{Event, Metric};
+use crate::event::{Event, Metric, MetricKind};
 use crate::sinks::util::Batch;
 use std::collections::{hash_map::DefaultHasher, HashSet};
 use std::hash::{Hash, Hasher};
@@ -27,6 +27,13 @@ impl Hash for MetricEntry {
                 name.hash(state);
                 val.to_bits().hash(state);
             }
+            Metric::AggregatedHistogram { name, buckets, .. } => {
+                name.hash(state);
+                buckets.iter().for_each(|b| b.to_bits().hash(state));
+            }
+            Metric::AggregatedSummary { name, .. } => {
+                name.hash(state);
+            }
         }
 
         self.0
@@ -50,6 +57,9 @@ impl PartialEq for MetricEntry {
     }
 }
 
+/// Aggregates metrics for a batch. Gauges and summaries always come out
+/// absolute, counters and aggregated histograms incremental, with the state
+/// needed for that conversion kept across batches.
 #[derive(Clone, PartialEq)]
 pub struct MetricBuffer {
     state: HashSet<MetricEntry>,
@@ -63,6 +73,15 @@ impl MetricBuffer {
             metrics: HashSet::new(),
         }
     }
+
+    fn merge(&mut self, item: Metric) {
+        if let Some(MetricEntry(mut existing)) = self.metrics.take(&MetricEntry(item.clone())) {
+            existing.merge(&item);
+            self.metrics.insert(MetricEntry(existing));
+        } else {
+            self.metrics.insert(MetricEntry(item));
+        }
+    }
 }
 
 impl Batch for MetricBuffer {
@@ -95,27 +114,37 @@ impl Batch for MetricBuffer {
                         Metric::Gauge {
                             name: name.clone(),
                             val: 0.0,
-                            direction: None,
                             timestamp: None,
                             tags: None,
+                            kind: MetricKind::Absolute,
                         }
                     };
                     initial.merge(&item);
                     self.metrics.insert(MetricEntry(initial));
                 }
             }
+            // absolute counters and histograms are turned into increments
+            // since the previous observation, which we keep in permanent
+            // state; the first observation only serves as the baseline
+            Metric::Counter {
+                kind: MetricKind::Absolute,
+                ..
+            }
+            | Metric::AggregatedHistogram {
+                kind: MetricKind::Absolute,
+                ..
+            } => {
+                if let Some(MetricEntry(previous)) = self.state.replace(new) {
+                    if let Some(delta) = item.to_incremental(&previous) {
+                        self.merge(delta);
+                    }
+                }
+            }
             // set observations are simply deduplicated
             Metric::Set { .. } => {
                 self.metrics.insert(new);
             }
-            _ => {
-                if let Some(MetricEntry(mut existing)) = self.metrics.take(&new) {
-                    existing.merge(&item);
-                    self.metrics.insert(MetricEntry(existing));
-                } else {
-                    self.metrics.insert(new);
-                }
-            }
+            item => self.merge(item),
         }
     }
 
@@ -127,7 +156,7 @@ impl Batch for MetricBuffer {
         let mut state = self.state.clone();
         for entry in self.metrics.iter() {
             if entry.0.is_gauge() {
-                state.insert(entry.clone());
+                state.replace(entry.clone());
             }
         }
 
@@ -151,7 +180,7 @@ mod test {
     use super::*;
     use crate::sinks::util::batch::BatchSink;
     use crate::{
-        event::metric::{Direction, Metric},
+        event::metric::{Metric, MetricKind},
         Event,
     };
     use futures::{future::Future, stream, Sink};
@@ -182,6 +211,7 @@ mod test {
                 val: i as f64,
                 timestamp: None,
                 tags: Some(tag("production")),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -192,6 +222,7 @@ mod test {
                 val: i as f64,
                 timestamp: None,
                 tags: Some(tag("staging")),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -202,6 +233,7 @@ mod test {
                 val: i as f64,
                 timestamp: None,
                 tags: Some(tag("production")),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -224,36 +256,42 @@ mod test {
                     val: 0.0,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Counter {
                     name: "counter-0".into(),
                     val: 6.0,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Counter {
                     name: "counter-1".into(),
                     val: 1.0,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Counter {
                     name: "counter-1".into(),
                     val: 1.0,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Counter {
                     name: "counter-2".into(),
                     val: 2.0,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Counter {
                     name: "counter-3".into(),
                     val: 3.0,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Incremental,
                 },
             ]
         );
@@ -266,12 +304,14 @@ mod test {
                     val: 2.0,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Counter {
                     name: "counter-3".into(),
                     val: 3.0,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
             ]
         );
@@ -286,9 +326,9 @@ mod test {
             let event = Event::Metric(Metric::Gauge {
                 name: "gauge-0".into(),
                 val: i as f64,
-                direction: None,
                 timestamp: None,
                 tags: Some(tag("production")),
+                kind: MetricKind::Absolute,
             });
             events.push(event);
         }
@@ -297,9 +337,9 @@ mod test {
             let event = Event::Metric(Metric::Gauge {
                 name: format!("gauge-{}", i),
                 val: i as f64,
-                direction: None,
                 timestamp: None,
                 tags: Some(tag("staging")),
+                kind: MetricKind::Absolute,
             });
             events.push(event);
         }
@@ -308,9 +348,9 @@ mod test {
             let event = Event::Metric(Metric::Gauge {
                 name: format!("gauge-{}", i),
                 val: i as f64,
-                direction: Some(Direction::Plus),
                 timestamp: None,
                 tags: Some(tag("staging")),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -332,30 +372,30 @@ mod test {
                 Metric::Gauge {
                     name: "gauge-0".into(),
                     val: 0.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
                 Metric::Gauge {
                     name: "gauge-0".into(),
                     val: 3.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Absolute,
                 },
                 Metric::Gauge {
                     name: "gauge-1".into(),
                     val: 1.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
                 Metric::Gauge {
                     name: "gauge-2".into(),
                     val: 2.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
             ]
         );
@@ -366,30 +406,30 @@ mod test {
                 Metric::Gauge {
                     name: "gauge-0".into(),
                     val: 0.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
                 Metric::Gauge {
                     name: "gauge-1".into(),
                     val: 1.0 + 1.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
                 Metric::Gauge {
                     name: "gauge-3".into(),
                     val: 3.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
                 Metric::Gauge {
                     name: "gauge-4".into(),
                     val: 4.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
             ]
         );
@@ -400,28 +440,135 @@ mod test {
                 Metric::Gauge {
                     name: "gauge-2".into(),
                     val: 2.0 + 2.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
                 Metric::Gauge {
                     name: "gauge-3".into(),
                     val: 3.0 + 3.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
                 Metric::Gauge {
                     name: "gauge-4".into(),
                     val: 4.0 + 4.0,
-                    direction: None,
                     timestamp: None,
                     tags: Some(tag("staging")),
+                    kind: MetricKind::Absolute,
                 },
             ]
         );
     }
 
+    #[test]
+    fn metric_buffer_absolute_counters() {
+        let sink = BatchSink::new_min(vec![], MetricBuffer::new(), 1, Some(Duration::from_secs(1)));
+
+        let counter = |name: &str, val: f64, kind| {
+            Event::Metric(Metric::Counter {
+                name: name.into(),
+                val,
+                timestamp: None,
+                tags: Some(tag("production")),
+                kind,
+            })
+        };
+
+        let events = vec![
+            counter("counter-0", 10.0, MetricKind::Absolute),
+            counter("counter-0", 12.0, MetricKind::Absolute),
+            counter("counter-0", 1.0, MetricKind::Incremental),
+            counter("counter-1", 5.0, MetricKind::Absolute),
+            counter("counter-0", 20.0, MetricKind::Absolute),
+            // the counter was reset
+            counter("counter-1", 3.0, MetricKind::Absolute),
+        ];
+
+        let (buffer, _) = sink
+            .send_all(stream::iter_ok(events.into_iter()))
+            .wait()
+            .unwrap();
+
+        let buffer = buffer
+            .into_inner()
+            .into_iter()
+            .map(|batch| sorted(&batch.finish()))
+            .collect::<Vec<_>>();
+
+        let counter = |name: &str, val: f64| Metric::Counter {
+            name: name.into(),
+            val,
+            timestamp: None,
+            tags: Some(tag("production")),
+            kind: MetricKind::Incremental,
+        };
+
+        assert_eq!(
+            buffer,
+            vec![
+                vec![counter("counter-0", 2.0)],
+                vec![counter("counter-0", 1.0)],
+                vec![counter("counter-0", 8.0)],
+                vec![counter("counter-1", 3.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn metric_buffer_aggregated_histograms() {
+        let sink = BatchSink::new_min(vec![], MetricBuffer::new(), 1, Some(Duration::from_secs(1)));
+
+        let hist = |counts: Vec<u64>, count, sum, kind| Metric::AggregatedHistogram {
+            name: "hist".into(),
+            buckets: vec![1.0, 2.0],
+            counts,
+            count,
+            sum,
+            timestamp: None,
+            tags: Some(tag("production")),
+            kind,
+        };
+        let summary = Metric::AggregatedSummary {
+            name: "summary".into(),
+            quantiles: vec![0.5, 0.99],
+            values: vec![1.0, 2.5],
+            count: 10,
+            sum: 12.0,
+            timestamp: None,
+            tags: Some(tag("production")),
+            kind: MetricKind::Absolute,
+        };
+
+        let events = vec![
+            Event::Metric(hist(vec![1, 2], 3, 4.0, MetricKind::Absolute)),
+            Event::Metric(hist(vec![3, 5], 9, 12.0, MetricKind::Absolute)),
+            Event::Metric(hist(vec![1, 0], 2, 5.0, MetricKind::Incremental)),
+            Event::Metric(summary.clone()),
+        ];
+
+        let (buffer, _) = sink
+            .send_all(stream::iter_ok(events.into_iter()))
+            .wait()
+            .unwrap();
+
+        let buffer = buffer
+            .into_inner()
+            .into_iter()
+            .map(|batch| batch.finish())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            buffer,
+            vec![
+                vec![hist(vec![2, 3], 6, 8.0, MetricKind::Incremental)],
+                vec![hist(vec![1, 0], 2, 5.0, MetricKind::Incremental)],
+                vec![summary],
+            ]
+        );
+    }
+
     #[test]
     fn metric_buffer_sets() {
         let sink = BatchSink::new_min(vec![], MetricBuffer::new(), 6, Some(Duration::from_secs(1)));
@@ -433,6 +580,7 @@ mod test {
                 val: format!("{}", i),
                 timestamp: None,
                 tags: Some(tag("production")),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -443,6 +591,7 @@ mod test {
                 val: format!("{}", i),
                 timestamp: None,
                 tags: Some(tag("production")),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -463,24 +612,28 @@ mod test {
                     val: "0".into(),
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Set {
                     name: "set-0".into(),
                     val: "1".into(),
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Set {
                     name: "set-0".into(),
                     val: "2".into(),
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Set {
                     name: "set-0".into(),
                     val: "3".into(),
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
             ]
         );
@@ -498,6 +651,7 @@ mod test {
                 sample_rate: 10,
                 timestamp: None,
                 tags: Some(tag("production")),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -509,6 +663,7 @@ mod test {
                 sample_rate: 10,
                 timestamp: None,
                 tags: Some(tag("production")),
+                kind: MetricKind::Incremental,
             });
             events.push(event);
         }
@@ -530,6 +685,7 @@ mod test {
                     sample_rate: 50,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Histogram {
                     name: "hist-3".into(),
@@ -537,6 +693,7 @@ mod test {
                     sample_rate: 10,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Histogram {
                     name: "hist-4".into(),
@@ -544,6 +701,7 @@ mod test {
                     sample_rate: 10,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
                 Metric::Histogram {
                     name: "hist-5".into(),
@@ -551,6 +709,7 @@ mod test {
                     sample_rate: 10,
                     timestamp: None,
                     tags: Some(tag("production")),
+                    kind: MetricKind::Incremental,
                 },
             ]
         );
@@ -1,8 +1,10 @@
 pub mod batch;
 pub mod buffer;
 pub mod http;
+pub mod reconnect;
 pub mod retries;
 pub mod tls;
+pub mod unix;
 
 use crate::buffers::Acker;
 use futures::{
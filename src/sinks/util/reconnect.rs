@@ -0,0 +1,130 @@
+use bytes::Bytes;
+use futures::{try_ready, Async, AsyncSink, Future, Poll, Sink, StartSend};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+use tokio_retry::strategy::ExponentialBackoff;
+
+/// Opens the connections a `ReconnectingSink` writes to. It is displayed as
+/// the peer in logs.
+pub trait Connector: fmt::Display {
+    type Connection: Sink<SinkItem = Bytes, SinkError = std::io::Error>;
+    type Future: Future<Item = Self::Connection, Error = crate::Error>;
+
+    fn connect(&self) -> Self::Future;
+}
+
+/// Writes frames to a connection, reconnecting with a backoff whenever the
+/// connection is lost. Frames that failed to be written are dropped.
+pub struct ReconnectingSink<C: Connector> {
+    connector: C,
+    state: State<C>,
+    backoff: ExponentialBackoff,
+}
+
+enum State<C: Connector> {
+    Disconnected,
+    Connecting(C::Future),
+    Connected(C::Connection),
+    Backoff(Delay),
+}
+
+impl<C: Connector> ReconnectingSink<C> {
+    pub fn new(connector: C) -> Self {
+        Self {
+            connector,
+            state: State::Disconnected,
+            backoff: Self::fresh_backoff(),
+        }
+    }
+
+    fn fresh_backoff() -> ExponentialBackoff {
+        // TODO: make configurable
+        ExponentialBackoff::from_millis(2)
+            .factor(250)
+            .max_delay(Duration::from_secs(60))
+    }
+
+    fn next_delay(&mut self) -> Delay {
+        Delay::new(Instant::now() + self.backoff.next().unwrap())
+    }
+
+    fn poll_connection(&mut self) -> Poll<&mut C::Connection, ()> {
+        loop {
+            self.state = match self.state {
+                State::Disconnected => {
+                    debug!(message = "connecting", peer = %self.connector);
+                    State::Connecting(self.connector.connect())
+                }
+                State::Backoff(ref mut delay) => match delay.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    // Err can only occur if the tokio runtime has been shutdown or if more than 2^63 timers have been created
+                    Err(err) => unreachable!(err),
+                    Ok(Async::Ready(())) => {
+                        debug!(message = "disconnected.", peer = %self.connector);
+                        State::Disconnected
+                    }
+                },
+                State::Connecting(ref mut connect_future) => match connect_future.poll() {
+                    Ok(Async::Ready(connection)) => {
+                        debug!(message = "connected", peer = %self.connector);
+                        self.backoff = Self::fresh_backoff();
+                        State::Connected(connection)
+                    }
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(err) => {
+                        error!("Error connecting to {}: {}", self.connector, err);
+                        State::Backoff(self.next_delay())
+                    }
+                },
+                State::Connected(ref mut connection) => {
+                    return Ok(Async::Ready(connection));
+                }
+            };
+        }
+    }
+}
+
+impl<C: Connector> Sink for ReconnectingSink<C> {
+    type SinkItem = Bytes;
+    type SinkError = ();
+
+    fn start_send(&mut self, line: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        match self.poll_connection() {
+            Ok(Async::Ready(connection)) => {
+                debug!(message = "sending event.", bytes = line.len());
+                match connection.start_send(line) {
+                    Err(err) => {
+                        debug!(message = "disconnected.", peer = %self.connector);
+                        error!("Error in connection {}: {}", self.connector, err);
+                        self.state = State::Disconnected;
+                        Ok(AsyncSink::Ready)
+                    }
+                    Ok(ok) => Ok(ok),
+                }
+            }
+            Ok(Async::NotReady) => Ok(AsyncSink::NotReady(line)),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    fn poll_complete(&mut self) -> Result<Async<()>, Self::SinkError> {
+        // Stream::forward will immediately poll_complete the sink it's forwarding to,
+        // but we don't want to connect before the first event actually comes through.
+        if let State::Disconnected = self.state {
+            return Ok(Async::Ready(()));
+        }
+
+        let connection = try_ready!(self.poll_connection());
+
+        match connection.poll_complete() {
+            Err(err) => {
+                debug!(message = "disconnected.", peer = %self.connector);
+                error!("Error in connection {}: {}", self.connector, err);
+                self.state = State::Disconnected;
+                Ok(Async::Ready(()))
+            }
+            Ok(ok) => Ok(ok),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use super::reconnect::{Connector, ReconnectingSink};
+use futures::{future, Future};
+use snafu::Snafu;
+use std::fmt;
+use std::path::PathBuf;
+use tokio::codec::{BytesCodec, FramedWrite};
+use tokio_uds::UnixStream;
+
+#[derive(Debug, Snafu)]
+enum HealthcheckError {
+    #[snafu(display("Connect error: {}", source))]
+    ConnectError { source: std::io::Error },
+}
+
+pub fn unix_healthcheck(path: PathBuf) -> crate::sinks::Healthcheck {
+    // Lazy to avoid immediately connecting
+    let check = future::lazy(move || {
+        UnixStream::connect(&path)
+            .map(|_| ())
+            .map_err(|source| HealthcheckError::ConnectError { source }.into())
+    });
+
+    Box::new(check)
+}
+
+/// Writes frames to a Unix stream socket, reconnecting with a backoff
+/// whenever the connection is lost.
+pub type UnixSink = ReconnectingSink<UnixConnector>;
+
+pub struct UnixConnector {
+    path: PathBuf,
+}
+
+impl UnixConnector {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl fmt::Display for UnixConnector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.path.display())
+    }
+}
+
+impl Connector for UnixConnector {
+    type Connection = FramedWrite<UnixStream, BytesCodec>;
+    type Future = Box<dyn Future<Item = Self::Connection, Error = crate::Error> + Send>;
+
+    fn connect(&self) -> Self::Future {
+        Box::new(
+            UnixStream::connect(&self.path)
+                .map(|socket| FramedWrite::new(socket, BytesCodec::new()))
+                .map_err(crate::Error::from),
+        )
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     buffers::Acker,
     event::proto,
-    sinks::tcp::TcpSink,
+    sinks::tcp::{TcpConnector, TcpSink},
     sinks::util::SinkExt,
     topology::config::{DataType, SinkConfig, SinkDescription},
     Event,
@@ -59,7 +59,7 @@ impl SinkConfig for VectorSinkConfig {
 
 pub fn vector(hostname: String, addr: SocketAddr, acker: Acker) -> super::RouterSink {
     Box::new(
-        TcpSink::new(hostname, addr, None)
+        TcpSink::new(TcpConnector::new(hostname, addr, None))
             .stream_ack(acker)
             .with(encode_event),
     )
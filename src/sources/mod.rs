@@ -5,6 +5,7 @@ pub mod file;
 pub mod journald;
 #[cfg(feature = "rdkafka")]
 pub mod kafka;
+pub mod prometheus;
 pub mod statsd;
 pub mod stdin;
 pub mod syslog;
@@ -0,0 +1,302 @@
+use crate::{
+    event::{CounterState, Event, Metric},
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+};
+use futures::{future, stream, sync::mpsc, Future, Sink, Stream};
+use http::Uri;
+use hyper::{Body, Client, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::{timer::Interval, util::FutureExt};
+use tracing::field;
+
+pub mod parser;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid endpoint {:?}: {}", endpoint, source))]
+    InvalidEndpoint {
+        endpoint: String,
+        source: http::uri::InvalidUri,
+    },
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PrometheusConfig {
+    pub endpoints: Vec<String>,
+    #[serde(default = "default_scrape_interval_secs")]
+    pub scrape_interval_secs: u64,
+}
+
+pub fn default_scrape_interval_secs() -> u64 {
+    15
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<PrometheusConfig>("prometheus")
+}
+
+#[typetag::serde(name = "prometheus")]
+impl SourceConfig for PrometheusConfig {
+    fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        let mut urls = Vec::new();
+        for endpoint in &self.endpoints {
+            let base = endpoint
+                .parse::<Uri>()
+                .context(InvalidEndpoint { endpoint })?;
+            // Scrape the conventional path unless one is given.
+            let url = match base.path() {
+                "" | "/" => format!("{}/metrics", endpoint.trim_end_matches('/'))
+                    .parse::<Uri>()
+                    .context(InvalidEndpoint { endpoint })?,
+                _ => base,
+            };
+            urls.push(url);
+        }
+
+        Ok(prometheus(urls, self.scrape_interval_secs, out))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn source_type(&self) -> &'static str {
+        "prometheus"
+    }
+}
+
+fn prometheus(urls: Vec<Uri>, interval: u64, out: mpsc::Sender<Event>) -> super::Source {
+    let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
+
+    Box::new(future::lazy(move || {
+        let https = HttpsConnector::new(4).expect("TLS initialization failed");
+        let client = Client::builder().build::<_, Body>(https);
+        let mut counters = HashMap::new();
+
+        Interval::new(Instant::now(), Duration::from_secs(interval))
+            .map_err(|e| error!("timer error: {:?}", e))
+            .map(move |_| {
+                // All endpoints are scraped at once, and a request is given up
+                // after an interval so a stuck endpoint can't delay the next
+                // scrape.
+                let scrapes = urls.iter().map(|url| {
+                    let request = Request::get(url).body(Body::empty()).unwrap();
+                    let url = url.clone();
+
+                    client
+                        .request(request)
+                        .and_then(|response| {
+                            let status = response.status();
+                            response
+                                .into_body()
+                                .concat2()
+                                .map(move |body| (status, body))
+                        })
+                        .timeout(Duration::from_secs(interval))
+                        .then(move |result| -> Result<_, ()> {
+                            match result {
+                                Ok((StatusCode::OK, body)) => Ok(Some((url, body))),
+                                Ok((status, _)) => {
+                                    error!(
+                                        message = "Unexpected status scraping metrics.",
+                                        url = &field::display(&url),
+                                        status = &field::display(status),
+                                    );
+                                    Ok(None)
+                                }
+                                Err(error) => {
+                                    error!(
+                                        message = "Error scraping metrics.",
+                                        url = &field::display(&url),
+                                        %error,
+                                    );
+                                    Ok(None)
+                                }
+                            }
+                        })
+                });
+                stream::futures_unordered(scrapes)
+            })
+            .flatten()
+            .filter_map(|scrape| scrape)
+            .map(move |(url, body)| {
+                let metrics = match parser::parse(&String::from_utf8_lossy(&body)) {
+                    Ok(metrics) => incremental(&mut counters, &url, metrics),
+                    Err(error) => {
+                        error!(
+                            message = "Error parsing scraped metrics.",
+                            url = &field::display(&url),
+                            %error,
+                        );
+                        Vec::new()
+                    }
+                };
+                stream::iter_ok::<_, ()>(metrics.into_iter().map(Event::Metric))
+            })
+            .flatten()
+            .forward(out)
+            .map(|_| info!("finished sending"))
+    }))
+}
+
+/// Replaces counters and histograms with their increase since the last
+/// scrape, keeping the series of each endpoint apart. Series missing from
+/// the scrape are forgotten.
+fn incremental(
+    counters: &mut HashMap<String, CounterState>,
+    url: &Uri,
+    metrics: Vec<Metric>,
+) -> Vec<Metric> {
+    let counters = counters.entry(url.to_string()).or_default();
+    let metrics = metrics
+        .into_iter()
+        .filter_map(|metric| counters.incremental(metric))
+        .collect();
+    counters.evict_unseen();
+    metrics
+}
+
+#[cfg(test)]
+mod test {
+    use super::{incremental, PrometheusConfig};
+    use crate::{
+        event::{Event, Metric, MetricKind},
+        runtime::Runtime,
+        test_util::{collect_n, next_addr},
+        topology::config::{GlobalOptions, SourceConfig},
+    };
+    use futures::{sync::mpsc, Future};
+    use hyper::service::service_fn_ok;
+    use hyper::{Body, Request, Response, Server};
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn counter(val: f64, kind: MetricKind) -> Metric {
+        Metric::Counter {
+            name: "requests_total".into(),
+            val,
+            timestamp: None,
+            tags: None,
+            kind,
+        }
+    }
+
+    #[test]
+    fn prometheus_counters_to_incremental() {
+        let mut state = HashMap::new();
+        let url = "http://localhost:9090/metrics".parse().unwrap();
+        let other = "http://localhost:9091/metrics".parse().unwrap();
+
+        assert_eq!(
+            incremental(&mut state, &url, vec![counter(10.0, MetricKind::Absolute)]),
+            vec![]
+        );
+        assert_eq!(
+            incremental(&mut state, &other, vec![counter(3.0, MetricKind::Absolute)]),
+            vec![]
+        );
+        assert_eq!(
+            incremental(&mut state, &url, vec![counter(15.0, MetricKind::Absolute)]),
+            vec![counter(5.0, MetricKind::Incremental)]
+        );
+        assert_eq!(
+            incremental(&mut state, &other, vec![counter(4.0, MetricKind::Absolute)]),
+            vec![counter(1.0, MetricKind::Incremental)]
+        );
+
+        // The counter is missing from a scrape and starts over afterwards
+        assert_eq!(incremental(&mut state, &url, vec![]), vec![]);
+        assert_eq!(
+            incremental(&mut state, &url, vec![counter(20.0, MetricKind::Absolute)]),
+            vec![]
+        );
+        assert_eq!(
+            incremental(&mut state, &url, vec![counter(21.0, MetricKind::Absolute)]),
+            vec![counter(1.0, MetricKind::Incremental)]
+        );
+    }
+
+    #[test]
+    fn prometheus_scrapes_endpoints() {
+        let in_addr = next_addr();
+        let scrapes = Arc::new(AtomicUsize::new(0));
+
+        let service = {
+            let scrapes = Arc::clone(&scrapes);
+            move || {
+                let scrapes = Arc::clone(&scrapes);
+                service_fn_ok(move |req: Request<Body>| {
+                    assert_eq!(req.uri().path(), "/metrics");
+                    let count = scrapes.fetch_add(1, Ordering::SeqCst) + 1;
+                    Response::new(Body::from(format!(
+                        "# TYPE requests_total counter\n\
+                         requests_total {}\n\
+                         # TYPE temperature gauge\n\
+                         temperature 21.5\n",
+                        count * 10
+                    )))
+                })
+            }
+        };
+
+        let (_trigger, tripwire) = stream_cancel::Tripwire::new();
+        let server = Server::bind(&in_addr)
+            .serve(service)
+            .with_graceful_shutdown(tripwire)
+            .map_err(|e| panic!("server error: {}", e));
+
+        // Accepts connections but never responds
+        let stuck_addr = next_addr();
+        let _stuck = std::net::TcpListener::bind(&stuck_addr).unwrap();
+
+        let config = PrometheusConfig {
+            endpoints: vec![
+                format!("http://{}", stuck_addr),
+                format!("http://{}", in_addr),
+            ],
+            scrape_interval_secs: 1,
+        };
+        let (tx, rx) = mpsc::channel(10);
+        let source = config.build("in", &GlobalOptions::default(), tx).unwrap();
+
+        let mut rt = Runtime::new().unwrap();
+        rt.spawn(server);
+        rt.spawn(source);
+
+        let events = rt.block_on(collect_n(rx, 3)).unwrap();
+        let metrics = events
+            .into_iter()
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+
+        let gauge = Metric::Gauge {
+            name: "temperature".into(),
+            val: 21.5,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        };
+        // The first counter value is only the baseline, and the stuck
+        // endpoint doesn't hold up the next scrape.
+        assert_eq!(
+            metrics,
+            vec![gauge.clone(), counter(10.0, MetricKind::Incremental), gauge]
+        );
+    }
+}
@@ -0,0 +1,651 @@
+use crate::event::{Metric, MetricKind};
+use chrono::{DateTime, TimeZone, Utc};
+use snafu::Snafu;
+use std::{cmp::Ordering, collections::HashMap};
+
+#[derive(Debug, Snafu, PartialEq)]
+pub enum ParserError {
+    #[snafu(display("Malformed sample on line {}: {}", line, message))]
+    Malformed { line: usize, message: &'static str },
+    #[snafu(display("Invalid value {:?} on line {}", value, line))]
+    InvalidValue { line: usize, value: String },
+    #[snafu(display("Invalid timestamp {:?} on line {}", timestamp, line))]
+    InvalidTimestamp { line: usize, timestamp: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+    Untyped,
+}
+
+/// Parses metrics in the Prometheus text exposition format.
+///
+/// Counters are returned with their cumulative, absolute value, and untyped
+/// metrics as gauges. The `_bucket`, `_sum` and `_count` series of a
+/// histogram are assembled into an aggregated histogram, and those of a
+/// summary into an aggregated summary, at the position of their first sample.
+pub fn parse(input: &str) -> Result<Vec<Metric>, ParserError> {
+    let mut types = HashMap::new();
+    let mut entries = Vec::new();
+    let mut aggregates = Vec::new();
+    let mut aggregate_index = HashMap::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+
+        if line.starts_with('#') {
+            let mut parts = line[1..].split_whitespace();
+            if parts.next() == Some("TYPE") {
+                if let (Some(name), Some(kind)) = (parts.next(), parts.next()) {
+                    types.insert(name.to_string(), parse_type(kind));
+                }
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let sample = parse_sample(line, line_number)?;
+        let (metric_type, base) = metric_type(&types, &sample.name);
+
+        if let MetricType::Histogram | MetricType::Summary = metric_type {
+            let histogram = metric_type == MetricType::Histogram;
+            let suffix = &sample.name[base.len()..];
+            let mut labels = sample.labels;
+            let bound_label = if histogram { "le" } else { "quantile" };
+            // the rest are `_bucket` series or summary quantiles
+            let bound = match suffix {
+                "_sum" | "_count" => None,
+                _ => {
+                    let bound = labels.remove(bound_label).ok_or(ParserError::Malformed {
+                        line: line_number,
+                        message: if histogram {
+                            "expected an 'le' label"
+                        } else {
+                            "expected a 'quantile' label"
+                        },
+                    })?;
+                    Some(parse_value(&bound).ok_or(ParserError::InvalidValue {
+                        line: line_number,
+                        value: bound,
+                    })?)
+                }
+            };
+
+            let mut key = labels
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Vec<_>>();
+            key.sort();
+            let key = (base.to_string(), key);
+
+            let index = *aggregate_index.entry(key).or_insert_with(|| {
+                entries.push(Entry::Aggregate(aggregates.len()));
+                aggregates.push(Aggregate {
+                    line: line_number,
+                    name: base.to_string(),
+                    tags: labels,
+                    histogram,
+                    values: Vec::new(),
+                    count: None,
+                    sum: 0.0,
+                    timestamp: None,
+                });
+                aggregates.len() - 1
+            });
+            let aggregate = &mut aggregates[index];
+            match (suffix, bound) {
+                ("_sum", _) => aggregate.sum = sample.value,
+                ("_count", _) => aggregate.count = Some(sample.value),
+                (_, Some(bound)) => aggregate.values.push((bound, sample.value)),
+                _ => {}
+            }
+            aggregate.timestamp = aggregate.timestamp.max(sample.timestamp);
+            continue;
+        }
+
+        let tags = if sample.labels.is_empty() {
+            None
+        } else {
+            Some(sample.labels)
+        };
+        let metric = match metric_type {
+            MetricType::Counter => Metric::Counter {
+                name: sample.name,
+                val: sample.value,
+                timestamp: sample.timestamp,
+                tags,
+                kind: MetricKind::Absolute,
+            },
+            _ => Metric::Gauge {
+                name: sample.name,
+                val: sample.value,
+                timestamp: sample.timestamp,
+                tags,
+                kind: MetricKind::Absolute,
+            },
+        };
+        entries.push(Entry::Metric(metric));
+    }
+
+    let mut aggregates = aggregates
+        .into_iter()
+        .map(|aggregate| aggregate.into_metric().map(Some))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Entry::Metric(metric) => Some(metric),
+            Entry::Aggregate(index) => aggregates[index].take(),
+        })
+        .collect())
+}
+
+enum Entry {
+    Metric(Metric),
+    Aggregate(usize),
+}
+
+/// A histogram or summary being assembled from its series. `values` holds
+/// the cumulative count of each bucket, or the value of each quantile.
+struct Aggregate {
+    /// The line of the first sample, where errors are reported.
+    line: usize,
+    name: String,
+    tags: HashMap<String, String>,
+    histogram: bool,
+    values: Vec<(f64, f64)>,
+    count: Option<f64>,
+    sum: f64,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+impl Aggregate {
+    fn into_metric(mut self) -> Result<Metric, ParserError> {
+        self.values
+            .sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        let tags = if self.tags.is_empty() {
+            None
+        } else {
+            Some(self.tags)
+        };
+
+        let line = self.line;
+        let malformed = |message| ParserError::Malformed { line, message };
+        let count = match self.count.map(parse_count) {
+            Some(Some(count)) => Some(count),
+            Some(None) => return Err(malformed("invalid count")),
+            None => None,
+        };
+
+        if self.histogram {
+            let mut buckets = Vec::new();
+            let mut counts = Vec::new();
+            let mut previous = 0;
+            for (bucket, cumulative) in self.values {
+                let cumulative = match parse_count(cumulative) {
+                    Some(cumulative) if cumulative >= previous => cumulative,
+                    Some(_) => return Err(malformed("bucket counts are not cumulative")),
+                    None => return Err(malformed("invalid bucket count")),
+                };
+                // the `+Inf` bucket is the same as the count
+                if bucket.is_finite() {
+                    buckets.push(bucket);
+                    counts.push(cumulative - previous);
+                }
+                previous = cumulative;
+            }
+            if count.map_or(false, |count| previous > count) {
+                return Err(malformed("bucket counts exceed the count"));
+            }
+
+            Ok(Metric::AggregatedHistogram {
+                name: self.name,
+                buckets,
+                counts,
+                count: count.unwrap_or(0),
+                sum: self.sum,
+                timestamp: self.timestamp,
+                tags,
+                kind: MetricKind::Absolute,
+            })
+        } else {
+            let (quantiles, values) = self.values.into_iter().unzip();
+            Ok(Metric::AggregatedSummary {
+                name: self.name,
+                quantiles,
+                values,
+                count: count.unwrap_or(0),
+                sum: self.sum,
+                timestamp: self.timestamp,
+                tags,
+                kind: MetricKind::Absolute,
+            })
+        }
+    }
+}
+
+/// Converts a count of observations, which has to be a whole number that
+/// fits into a `u64`.
+fn parse_count(value: f64) -> Option<u64> {
+    if value >= 0.0 && value < std::u64::MAX as f64 && value.fract() == 0.0 {
+        Some(value as u64)
+    } else {
+        None
+    }
+}
+
+fn parse_type(kind: &str) -> MetricType {
+    match kind {
+        "counter" => MetricType::Counter,
+        "gauge" => MetricType::Gauge,
+        "histogram" => MetricType::Histogram,
+        "summary" => MetricType::Summary,
+        _ => MetricType::Untyped,
+    }
+}
+
+/// Looks up the type of a sample along with the name it was declared for,
+/// which for histograms and summaries is the name without the `_bucket`,
+/// `_sum` or `_count` suffix.
+fn metric_type<'a>(types: &HashMap<String, MetricType>, name: &'a str) -> (MetricType, &'a str) {
+    for suffix in &["_bucket", "_sum", "_count"] {
+        if name.ends_with(suffix) {
+            let base = &name[..name.len() - suffix.len()];
+            match types.get(base) {
+                Some(MetricType::Histogram) => return (MetricType::Histogram, base),
+                Some(MetricType::Summary) if *suffix != "_bucket" => {
+                    return (MetricType::Summary, base)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    match types.get(name) {
+        // histogram samples always have one of the suffixes
+        Some(MetricType::Histogram) | None => (MetricType::Untyped, name),
+        Some(kind) => (*kind, name),
+    }
+}
+
+struct Sample {
+    name: String,
+    labels: HashMap<String, String>,
+    value: f64,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+/// Parses `name{label="value",...} value [timestamp]`.
+fn parse_sample(line: &str, line_number: usize) -> Result<Sample, ParserError> {
+    let malformed = |message| ParserError::Malformed {
+        line: line_number,
+        message,
+    };
+
+    let name_end = line
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or_else(|| line.len());
+    if name_end == 0 {
+        return Err(malformed("expected a metric name"));
+    }
+    let name = line[..name_end].to_string();
+    let mut rest = line[name_end..].trim_start();
+
+    let mut labels = HashMap::new();
+    if rest.starts_with('{') {
+        rest = rest[1..].trim_start();
+        loop {
+            if rest.starts_with('}') {
+                rest = &rest[1..];
+                break;
+            }
+
+            let label_end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .ok_or_else(|| malformed("unterminated label set"))?;
+            if label_end == 0 {
+                return Err(malformed("expected a label name"));
+            }
+            let label = rest[..label_end].to_string();
+            rest = rest[label_end..].trim_start();
+
+            if !rest.starts_with('=') {
+                return Err(malformed("expected '=' after the label name"));
+            }
+            rest = rest[1..].trim_start();
+
+            let (value, remaining) =
+                parse_label_value(rest).ok_or_else(|| malformed("invalid label value"))?;
+            labels.insert(label, value);
+            rest = remaining.trim_start();
+
+            if rest.starts_with(',') {
+                rest = rest[1..].trim_start();
+            } else if !rest.starts_with('}') {
+                return Err(malformed("expected ',' or '}' after the label value"));
+            }
+        }
+    }
+
+    let mut parts = rest.split_whitespace();
+    let value = parts.next().ok_or_else(|| malformed("expected a value"))?;
+    let value = parse_value(value).ok_or_else(|| ParserError::InvalidValue {
+        line: line_number,
+        value: value.into(),
+    })?;
+
+    let timestamp = match parts.next() {
+        Some(timestamp) => Some(
+            timestamp
+                .parse::<i64>()
+                .map(|millis| Utc.timestamp_millis(millis))
+                .map_err(|_| ParserError::InvalidTimestamp {
+                    line: line_number,
+                    timestamp: timestamp.into(),
+                })?,
+        ),
+        None => None,
+    };
+
+    if parts.next().is_some() {
+        return Err(malformed("unexpected text after the timestamp"));
+    }
+
+    Ok(Sample {
+        name,
+        labels,
+        value,
+        timestamp,
+    })
+}
+
+/// Parses a quoted label value, returning it unescaped along with the
+/// remaining input.
+fn parse_label_value(input: &str) -> Option<(String, &str)> {
+    if !input.starts_with('"') {
+        return None;
+    }
+
+    let mut value = String::new();
+    let mut chars = input.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &input[index + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, c)) => value.push(c),
+                None => return None,
+            },
+            c => value.push(c),
+        }
+    }
+
+    None
+}
+
+fn parse_value(value: &str) -> Option<f64> {
+    match value {
+        "+Inf" | "Inf" => Some(std::f64::INFINITY),
+        "-Inf" => Some(std::f64::NEG_INFINITY),
+        "NaN" => Some(std::f64::NAN),
+        value => value.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, ParserError};
+    use crate::event::{Metric, MetricKind};
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    fn tags(tags: &[(&str, &str)]) -> Option<HashMap<String, String>> {
+        Some(
+            tags.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parse_counter_and_gauge() {
+        let input = r#"
+            # HELP http_requests_total The total number of HTTP requests.
+            # TYPE http_requests_total counter
+            http_requests_total{method="post",code="200"} 1027 1395066363000
+            http_requests_total{method="post",code="400"}    3 1395066363000
+
+            # A free-form comment
+            # TYPE temperature gauge
+            temperature -3.5e1
+            uptime 42
+            "#;
+
+        assert_eq!(
+            parse(input),
+            Ok(vec![
+                Metric::Counter {
+                    name: "http_requests_total".into(),
+                    val: 1027.0,
+                    timestamp: Some(Utc.timestamp_millis(1395066363000)),
+                    tags: tags(&[("method", "post"), ("code", "200")]),
+                    kind: MetricKind::Absolute,
+                },
+                Metric::Counter {
+                    name: "http_requests_total".into(),
+                    val: 3.0,
+                    timestamp: Some(Utc.timestamp_millis(1395066363000)),
+                    tags: tags(&[("method", "post"), ("code", "400")]),
+                    kind: MetricKind::Absolute,
+                },
+                Metric::Gauge {
+                    name: "temperature".into(),
+                    val: -35.0,
+                    timestamp: None,
+                    tags: None,
+                    kind: MetricKind::Absolute,
+                },
+                Metric::Gauge {
+                    name: "uptime".into(),
+                    val: 42.0,
+                    timestamp: None,
+                    tags: None,
+                    kind: MetricKind::Absolute,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_histogram() {
+        let input = r#"
+            # TYPE http_request_duration_seconds histogram
+            http_request_duration_seconds_bucket{le="0.05",code="200"} 24054
+            http_request_duration_seconds_bucket{le="0.5",code="200"} 129389
+            http_request_duration_seconds_bucket{le="0.1",code="200"} 33444
+            http_request_duration_seconds_bucket{le="+Inf",code="200"} 144320
+            http_request_duration_seconds_bucket{le="0.5",code="500"} 3
+            http_request_duration_seconds_sum{code="200"} 53423
+            http_request_duration_seconds_count{code="200"} 144320
+            uptime 42
+            "#;
+
+        assert_eq!(
+            parse(input),
+            Ok(vec![
+                Metric::AggregatedHistogram {
+                    name: "http_request_duration_seconds".into(),
+                    buckets: vec![0.05, 0.1, 0.5],
+                    counts: vec![24054, 9390, 95945],
+                    count: 144320,
+                    sum: 53423.0,
+                    timestamp: None,
+                    tags: tags(&[("code", "200")]),
+                    kind: MetricKind::Absolute,
+                },
+                Metric::AggregatedHistogram {
+                    name: "http_request_duration_seconds".into(),
+                    buckets: vec![0.5],
+                    counts: vec![3],
+                    count: 0,
+                    sum: 0.0,
+                    timestamp: None,
+                    tags: tags(&[("code", "500")]),
+                    kind: MetricKind::Absolute,
+                },
+                Metric::Gauge {
+                    name: "uptime".into(),
+                    val: 42.0,
+                    timestamp: None,
+                    tags: None,
+                    kind: MetricKind::Absolute,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_summary() {
+        let input = r#"
+            # TYPE rpc_duration_seconds summary
+            rpc_duration_seconds{quantile="0.99"} 76656
+            rpc_duration_seconds{quantile="0.5"} 4773
+            rpc_duration_seconds_sum 1.7560473e+07
+            rpc_duration_seconds_count 2693 1395066363000
+            "#;
+
+        assert_eq!(
+            parse(input),
+            Ok(vec![Metric::AggregatedSummary {
+                name: "rpc_duration_seconds".into(),
+                quantiles: vec![0.5, 0.99],
+                values: vec![4773.0, 76656.0],
+                count: 2693,
+                sum: 17560473.0,
+                timestamp: Some(Utc.timestamp_millis(1395066363000)),
+                tags: None,
+                kind: MetricKind::Absolute,
+            }])
+        );
+    }
+
+    #[test]
+    fn parse_label_escapes_and_special_values() {
+        let input = r#"msdos_file_access_time_seconds{path="C:\\DIR\\FILE.TXT",error="Cannot find file:\n\"FILE.TXT\"",} +Inf"#;
+
+        let metrics = parse(input).unwrap();
+        match &metrics[0] {
+            Metric::Gauge {
+                val, tags: labels, ..
+            } => {
+                assert_eq!(*val, std::f64::INFINITY);
+                assert_eq!(
+                    *labels,
+                    tags(&[
+                        ("path", "C:\\DIR\\FILE.TXT"),
+                        ("error", "Cannot find file:\n\"FILE.TXT\"")
+                    ])
+                );
+            }
+            other => panic!("unexpected metric {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("metric{label=\"value} 1"),
+            Err(ParserError::Malformed {
+                line: 1,
+                message: "invalid label value"
+            })
+        );
+        assert_eq!(
+            parse("\nmetric one"),
+            Err(ParserError::InvalidValue {
+                line: 2,
+                value: "one".into()
+            })
+        );
+        assert_eq!(
+            parse("metric 1 yesterday"),
+            Err(ParserError::InvalidTimestamp {
+                line: 1,
+                timestamp: "yesterday".into()
+            })
+        );
+        assert!(parse("{label=\"value\"} 1").is_err());
+        assert_eq!(
+            parse("# TYPE latency histogram\nlatency_bucket 1"),
+            Err(ParserError::Malformed {
+                line: 2,
+                message: "expected an 'le' label"
+            })
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid_aggregates() {
+        let histogram = r#"
+            # TYPE latency histogram
+            latency_bucket{le="0.5"} 3
+            latency_bucket{le="1"} 2
+            latency_bucket{le="+Inf"} 3
+            latency_count 3
+            "#;
+        assert_eq!(
+            parse(histogram),
+            Err(ParserError::Malformed {
+                line: 3,
+                message: "bucket counts are not cumulative"
+            })
+        );
+
+        let histogram = r#"
+            # TYPE latency histogram
+            latency_bucket{le="0.5"} 3
+            latency_count 2
+            "#;
+        assert_eq!(
+            parse(histogram),
+            Err(ParserError::Malformed {
+                line: 3,
+                message: "bucket counts exceed the count"
+            })
+        );
+
+        let histogram = r#"
+            # TYPE latency histogram
+            latency_bucket{le="0.5"} 0.5
+            latency_count 1
+            "#;
+        assert_eq!(
+            parse(histogram),
+            Err(ParserError::Malformed {
+                line: 3,
+                message: "invalid bucket count"
+            })
+        );
+
+        let summary = r#"
+            # TYPE duration summary
+            duration_sum 1
+            duration_count -1
+            "#;
+        assert_eq!(
+            parse(summary),
+            Err(ParserError::Malformed {
+                line: 3,
+                message: "invalid count"
+            })
+        );
+    }
+}
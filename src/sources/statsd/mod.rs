@@ -1,35 +1,117 @@
-use crate::{topology::config::GlobalOptions, Event};
+use super::util::{SocketListenAddr, TcpSource};
+use crate::{
+    topology::config::{DataType, GlobalOptions, SourceConfig},
+    Event,
+};
+use bytes::Bytes;
+use derive_is_enum_variant::is_enum_variant;
 use futures::{future, sync::mpsc, Future, Sink, Stream};
 use parser::parse;
-use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::{net::SocketAddr, path::PathBuf};
 use tokio::{
     self,
-    codec::BytesCodec,
+    codec::{BytesCodec, FramedRead, LinesCodec},
     net::{UdpFramed, UdpSocket},
 };
+use tokio_uds::UnixListener;
 use tracing::field;
+use tracing_futures::Instrument;
 
 pub mod parser;
 
-#[derive(Deserialize, Serialize, Debug)]
-struct StatsdConfig {
-    address: SocketAddr,
+#[derive(Serialize, Debug)]
+pub struct StatsdConfig {
+    #[serde(flatten)]
+    pub mode: Mode,
+}
+
+#[derive(Serialize, Debug, Clone, is_enum_variant)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Mode {
+    Tcp { address: SocketListenAddr },
+    Udp { address: SocketAddr },
+    Unix { path: PathBuf },
+}
+
+/// The options of every mode, so that unknown options can be rejected,
+/// which serde can't do for a flattened enum. The mode defaults to udp, the
+/// only one before there was a choice.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StatsdOptions {
+    #[serde(default)]
+    mode: ModeName,
+    address: Option<SocketListenAddr>,
+    path: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ModeName {
+    Tcp,
+    Udp,
+    Unix,
+}
+
+impl Default for ModeName {
+    fn default() -> Self {
+        ModeName::Udp
+    }
 }
 
+impl<'de> Deserialize<'de> for StatsdConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let options = StatsdOptions::deserialize(deserializer)?;
+        let mode = match (options.mode, options.address, options.path) {
+            (ModeName::Tcp, Some(address), None) => Mode::Tcp { address },
+            (ModeName::Udp, Some(SocketListenAddr::SocketAddr(address)), None) => {
+                Mode::Udp { address }
+            }
+            (ModeName::Udp, Some(SocketListenAddr::SystemdFd(_)), None) => {
+                return Err(de::Error::custom(
+                    "systemd sockets can't be used in udp mode",
+                ))
+            }
+            (ModeName::Unix, None, Some(path)) => Mode::Unix { path },
+            (ModeName::Tcp, _, _) | (ModeName::Udp, _, _) => {
+                return Err(de::Error::custom(
+                    "tcp and udp modes take an `address` and no `path`",
+                ))
+            }
+            (ModeName::Unix, _, _) => {
+                return Err(de::Error::custom(
+                    "unix mode takes a `path` and no `address`",
+                ))
+            }
+        };
+        Ok(Self { mode })
+    }
+}
+
+/// Metrics are sent one per line, so anything longer is not worth buffering.
+const MAX_LINE_LENGTH: usize = 100 * 1024;
+
 #[typetag::serde(name = "statsd")]
-impl crate::topology::config::SourceConfig for StatsdConfig {
+impl SourceConfig for StatsdConfig {
     fn build(
         &self,
         _name: &str,
         _globals: &GlobalOptions,
         out: mpsc::Sender<Event>,
     ) -> crate::Result<super::Source> {
-        Ok(statsd(self.address, out))
+        match self.mode.clone() {
+            Mode::Tcp { address } => {
+                let shutdown_secs = 30;
+                StatsdTcpSource.run(address, shutdown_secs, out)
+            }
+            Mode::Udp { address } => Ok(udp(address, out)),
+            Mode::Unix { path } => Ok(unix(path, out)),
+        }
     }
 
-    fn output_type(&self) -> crate::topology::config::DataType {
-        crate::topology::config::DataType::Metric
+    fn output_type(&self) -> DataType {
+        DataType::Metric
     }
 
     fn source_type(&self) -> &'static str {
@@ -37,7 +119,29 @@ impl crate::topology::config::SourceConfig for StatsdConfig {
     }
 }
 
-fn statsd(addr: SocketAddr, out: mpsc::Sender<Event>) -> super::Source {
+fn parse_line(line: &str) -> Option<Event> {
+    parse(line)
+        .map(Event::Metric)
+        .map_err(|e| error!("{}", e))
+        .ok()
+}
+
+#[derive(Debug, Clone)]
+struct StatsdTcpSource;
+
+impl TcpSource for StatsdTcpSource {
+    type Decoder = LinesCodec;
+
+    fn decoder(&self) -> Self::Decoder {
+        LinesCodec::new_with_max_length(MAX_LINE_LENGTH)
+    }
+
+    fn build_event(&self, line: String, _host: Option<Bytes>) -> Option<Event> {
+        parse_line(&line)
+    }
+}
+
+fn udp(addr: SocketAddr, out: mpsc::Sender<Event>) -> super::Source {
     let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
 
     Box::new(
@@ -56,12 +160,7 @@ fn statsd(addr: SocketAddr, out: mpsc::Sender<Event>) -> super::Source {
             let metrics_in = UdpFramed::new(socket, BytesCodec::new())
                 .map(|(bytes, _sock)| {
                     let packet = String::from_utf8_lossy(bytes.as_ref());
-                    let metrics = packet
-                        .lines()
-                        .map(parse)
-                        .filter_map(|res| res.map_err(|e| error!("{}", e)).ok())
-                        .map(Event::Metric)
-                        .collect::<Vec<_>>();
+                    let metrics = packet.lines().filter_map(parse_line).collect::<Vec<_>>();
                     futures::stream::iter_ok::<_, std::io::Error>(metrics)
                 })
                 .flatten()
@@ -72,16 +171,50 @@ fn statsd(addr: SocketAddr, out: mpsc::Sender<Event>) -> super::Source {
     )
 }
 
+fn unix(path: PathBuf, out: mpsc::Sender<Event>) -> super::Source {
+    let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
+
+    Box::new(future::lazy(move || {
+        let listener = UnixListener::bind(&path).expect("failed to bind to listener socket");
+
+        info!(message = "listening.", ?path, r#type = "unix");
+
+        listener
+            .incoming()
+            .map_err(|e| error!("failed to accept socket; error = {:?}", e))
+            .for_each(move |socket| {
+                let out = out.clone();
+                let span = info_span!("connection");
+
+                let metrics_in =
+                    FramedRead::new(socket, LinesCodec::new_with_max_length(MAX_LINE_LENGTH))
+                        .filter_map(|line| parse_line(&line))
+                        .map_err(|e| error!("error reading line: {:?}", e));
+
+                let handler = metrics_in.forward(out).map(|_| info!("finished sending"));
+
+                tokio::spawn(handler.instrument(span))
+            })
+    }))
+}
+
 #[cfg(test)]
 mod test {
-    use super::StatsdConfig;
+    use super::{Mode, StatsdConfig};
     use crate::{
+        event::metric::{Metric, MetricKind},
         sinks::prometheus::PrometheusSinkConfig,
-        test_util::{block_on, next_addr, runtime, shutdown_on_idle},
-        topology::{self, config},
+        test_util::{
+            block_on, collect_n, next_addr, runtime, send_lines, shutdown_on_idle, temp_dir,
+            wait_for_tcp,
+        },
+        topology::{
+            self,
+            config::{self, GlobalOptions, SourceConfig},
+        },
     };
-    use futures::Stream;
-    use std::{thread, time::Duration};
+    use futures::{sync::mpsc, Stream};
+    use std::{io::Write, os::unix::net::UnixStream, thread, time::Duration};
 
     fn parse_count(lines: &Vec<&str>, prefix: &str) -> usize {
         lines
@@ -93,13 +226,168 @@ mod test {
             .unwrap()
     }
 
+    fn expected_metrics() -> Vec<Metric> {
+        vec![
+            Metric::Counter {
+                name: "foo".into(),
+                val: 1.0,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Incremental,
+            },
+            Metric::Gauge {
+                name: "bar".into(),
+                val: 42.0,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Absolute,
+            },
+        ]
+    }
+
+    #[test]
+    fn config() {
+        let config: StatsdConfig = toml::from_str(
+            r#"
+            mode = "tcp"
+            address = "127.0.0.1:8125"
+          "#,
+        )
+        .unwrap();
+        assert!(config.mode.is_tcp());
+
+        let config: StatsdConfig = toml::from_str(
+            r#"
+            mode = "udp"
+            address = "127.0.0.1:8125"
+          "#,
+        )
+        .unwrap();
+        assert!(config.mode.is_udp());
+
+        let config: StatsdConfig = toml::from_str(
+            r#"
+            mode = "unix"
+            path = "/tmp/statsd.sock"
+          "#,
+        )
+        .unwrap();
+        assert!(config.mode.is_unix());
+    }
+
+    #[test]
+    fn config_defaults_to_udp() {
+        // The only option before modes were added
+        let config: StatsdConfig = toml::from_str(
+            r#"
+            address = "127.0.0.1:8125"
+          "#,
+        )
+        .unwrap();
+        match config.mode {
+            Mode::Udp { address } => assert_eq!(address, "127.0.0.1:8125".parse().unwrap()),
+            mode => panic!("unexpected mode: {:?}", mode),
+        }
+    }
+
+    #[test]
+    fn config_rejects_options_of_other_modes() {
+        let invalid = vec![
+            r#"address = "127.0.0.1:8125"
+               path = "/tmp/statsd.sock""#,
+            r#"mode = "unix"
+               address = "127.0.0.1:8125""#,
+            r#"mode = "udp"
+               address = "systemd""#,
+            r#"mode = "tcp""#,
+            r#"address = "127.0.0.1:8125"
+               unknown = true"#,
+        ];
+        for config in invalid {
+            assert!(
+                toml::from_str::<StatsdConfig>(config).is_err(),
+                "accepted {}",
+                config
+            );
+        }
+    }
+
+    #[test]
+    fn statsd_tcp() {
+        let (tx, rx) = mpsc::channel(10);
+
+        let addr = next_addr();
+        let config = StatsdConfig {
+            mode: Mode::Tcp {
+                address: addr.into(),
+            },
+        };
+        let server = config
+            .build("default", &GlobalOptions::default(), tx)
+            .unwrap();
+
+        let mut rt = runtime();
+        rt.spawn(server);
+        wait_for_tcp(addr);
+
+        let lines = vec!["foo:1|c".to_owned(), "bar:42|g".to_owned()];
+        rt.block_on(send_lines(addr, lines.into_iter())).unwrap();
+
+        let events = rt.block_on(collect_n(rx, 2)).unwrap();
+        let metrics = events
+            .into_iter()
+            .map(|e| e.into_metric())
+            .collect::<Vec<_>>();
+        assert_eq!(metrics, expected_metrics());
+    }
+
+    #[test]
+    fn statsd_unix() {
+        let (tx, rx) = mpsc::channel(10);
+
+        let path = temp_dir().with_extension("sock");
+        let config = StatsdConfig {
+            mode: Mode::Unix { path: path.clone() },
+        };
+        let server = config
+            .build("default", &GlobalOptions::default(), tx)
+            .unwrap();
+
+        let mut rt = runtime();
+        rt.spawn(server);
+
+        // Wait for server to accept connections
+        let mut socket = loop {
+            if let Ok(socket) = UnixStream::connect(&path) {
+                break socket;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        socket.write_all(b"foo:1|c\nbar:42|g\n").unwrap();
+        drop(socket);
+
+        let events = rt.block_on(collect_n(rx, 2)).unwrap();
+        let metrics = events
+            .into_iter()
+            .map(|e| e.into_metric())
+            .collect::<Vec<_>>();
+        assert_eq!(metrics, expected_metrics());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
     #[test]
     fn test_statsd() {
         let in_addr = next_addr();
         let out_addr = next_addr();
 
         let mut config = config::Config::empty();
-        config.add_source("in", StatsdConfig { address: in_addr });
+        config.add_source(
+            "in",
+            StatsdConfig {
+                mode: Mode::Udp { address: in_addr },
+            },
+        );
         config.add_sink(
             "out",
             &["in"],
@@ -1,4 +1,5 @@
-use crate::event::{metric::Direction, Metric};
+use crate::event::{Metric, MetricKind};
+use chrono::{DateTime, TimeZone, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::{
@@ -13,6 +14,13 @@ lazy_static! {
 }
 
 pub fn parse(packet: &str) -> Result<Metric, ParseError> {
+    if packet.starts_with("_e{") {
+        return parse_event(packet);
+    }
+    if packet.starts_with("_sc|") {
+        return parse_service_check(packet);
+    }
+
     // https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/#datagram-format
     let key_and_body = packet.splitn(2, ':').collect::<Vec<_>>();
     if key_and_body.len() != 2 {
@@ -32,26 +40,18 @@ pub fn parse(packet: &str) -> Result<Metric, ParseError> {
     let name = sanitize_key(key);
     let metric_type = parts[1];
 
-    // sampling part is optional and comes after metric type part
-    let sampling = parts.get(2).filter(|s| s.starts_with('@'));
-    let sample_rate = if let Some(s) = sampling {
-        1.0 / sanitize_sampling(parse_sampling(s)?)
-    } else {
-        1.0
-    };
-
-    // tags are optional and could be found either after sampling of after metric type part
-    let tags = if sampling.is_none() {
-        parts.get(2)
-    } else {
-        parts.get(3)
-    };
-    let tags = tags.filter(|s| s.starts_with('#'));
-    let tags = if let Some(t) = tags {
-        Some(parse_tags(t)?)
-    } else {
-        None
-    };
+    // sampling and tags are optional and may come in either order after
+    // the metric type; other DogStatsD extensions, like container ids,
+    // are ignored
+    let mut sample_rate = 1.0;
+    let mut tags = None;
+    for part in &parts[2..] {
+        if part.starts_with('@') {
+            sample_rate = 1.0 / sanitize_sampling(parse_sampling(part)?);
+        } else if part.starts_with('#') {
+            tags = Some(parse_tags(part)?);
+        }
+    }
 
     let metric = match metric_type {
         "c" => {
@@ -61,9 +61,10 @@ pub fn parse(packet: &str) -> Result<Metric, ParseError> {
                 val: val * sample_rate,
                 timestamp: None,
                 tags,
+                kind: MetricKind::Incremental,
             }
         }
-        unit @ "h" | unit @ "ms" => {
+        unit @ "h" | unit @ "ms" | unit @ "d" => {
             let val: f64 = parts[0].parse()?;
             Metric::Histogram {
                 name,
@@ -71,35 +72,157 @@ pub fn parse(packet: &str) -> Result<Metric, ParseError> {
                 sample_rate: sample_rate as u32,
                 timestamp: None,
                 tags,
+                kind: MetricKind::Incremental,
+            }
+        }
+        "g" => {
+            let (val, kind) = parse_gauge(parts[0])?;
+            Metric::Gauge {
+                name,
+                val,
+                timestamp: None,
+                tags,
+                kind,
             }
         }
-        "g" => Metric::Gauge {
-            name,
-            val: if parts[0]
-                .chars()
-                .next()
-                .map(|c| c.is_ascii_digit())
-                .ok_or_else(|| ParseError::Malformed("empty first body component"))?
-            {
-                parts[0].parse()?
-            } else {
-                parts[0][1..].parse()?
-            },
-            direction: parse_direction(parts[0])?,
-            timestamp: None,
-            tags,
-        },
         "s" => Metric::Set {
             name,
             val: parts[0].into(),
             timestamp: None,
             tags,
+            kind: MetricKind::Incremental,
         },
         other => return Err(ParseError::UnknownMetricType(other.into())),
     };
     Ok(metric)
 }
 
+/// Parses a DogStatsD event into a counter of the events with its title,
+/// tagged with the text and the rest of the event's metadata.
+///
+/// https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/#events
+fn parse_event(packet: &str) -> Result<Metric, ParseError> {
+    let header_end = packet.find("}:").ok_or(ParseError::Malformed(
+        "event should start with a '_e{<title length>,<text length>}:' header",
+    ))?;
+    let lengths = packet[3..header_end].splitn(2, ',').collect::<Vec<_>>();
+    if lengths.len() != 2 {
+        return Err(ParseError::Malformed(
+            "event header should have the title and text lengths",
+        ));
+    }
+    let title_length: usize = lengths[0].parse()?;
+    let text_length: usize = lengths[1].parse()?;
+
+    let body = &packet[header_end + 2..];
+    let title = body.get(..title_length).ok_or(ParseError::Malformed(
+        "event title is shorter than its length",
+    ))?;
+    let rest = &body[title_length..];
+    if !rest.starts_with('|') {
+        return Err(ParseError::Malformed("expected '|' after the event title"));
+    }
+    let text = rest[1..].get(..text_length).ok_or(ParseError::Malformed(
+        "event text is shorter than its length",
+    ))?;
+    let metadata = &rest[1 + text_length..];
+    if !metadata.is_empty() && !metadata.starts_with('|') {
+        return Err(ParseError::Malformed("expected '|' after the event text"));
+    }
+
+    let mut timestamp = None;
+    let mut tags = HashMap::new();
+    let mut extra = vec![("text", text.replace("\\n", "\n"))];
+    for part in metadata.split('|').skip(1) {
+        let tag = match part.get(..2) {
+            Some("d:") => {
+                timestamp = Some(parse_timestamp(&part[2..])?);
+                continue;
+            }
+            Some("h:") => "host",
+            Some("k:") => "aggregation_key",
+            Some("p:") => "priority",
+            Some("s:") => "source_type_name",
+            Some("t:") => "alert_type",
+            _ if part.starts_with('#') => {
+                tags = parse_tags(part)?;
+                continue;
+            }
+            _ => continue,
+        };
+        extra.push((tag, part[2..].to_owned()));
+    }
+    for (key, value) in extra {
+        tags.insert(key.to_owned(), value);
+    }
+
+    Ok(Metric::Counter {
+        name: sanitize_key(title),
+        val: 1.0,
+        timestamp,
+        tags: Some(tags),
+        kind: MetricKind::Incremental,
+    })
+}
+
+/// Parses a DogStatsD service check into a gauge of its status: 0 for OK,
+/// 1 for warning, 2 for critical and 3 for unknown.
+///
+/// https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/#service-checks
+fn parse_service_check(packet: &str) -> Result<Metric, ParseError> {
+    // the message comes last and may contain any character
+    let (packet, message) = match packet.find("|m:") {
+        Some(index) => (&packet[..index], Some(&packet[index + 3..])),
+        None => (packet, None),
+    };
+
+    let parts = packet.split('|').collect::<Vec<_>>();
+    if parts.len() < 3 {
+        return Err(ParseError::Malformed(
+            "service check should have a name and a status",
+        ));
+    }
+    let status: u8 = parts[2].parse()?;
+    if status > 3 {
+        return Err(ParseError::Malformed(
+            "service check status should be 0, 1, 2 or 3",
+        ));
+    }
+
+    let mut timestamp = None;
+    let mut tags = HashMap::new();
+    let mut host = None;
+    for part in &parts[3..] {
+        if part.starts_with("d:") {
+            timestamp = Some(parse_timestamp(&part[2..])?);
+        } else if part.starts_with("h:") {
+            host = Some(part[2..].to_owned());
+        } else if part.starts_with('#') {
+            tags = parse_tags(part)?;
+        }
+    }
+    if let Some(host) = host {
+        tags.insert("host".to_owned(), host);
+    }
+    if let Some(message) = message {
+        tags.insert("message".to_owned(), message.replace("\\n", "\n"));
+    }
+
+    Ok(Metric::Gauge {
+        name: sanitize_key(parts[1]),
+        val: f64::from(status),
+        timestamp,
+        tags: if tags.is_empty() { None } else { Some(tags) },
+        kind: MetricKind::Absolute,
+    })
+}
+
+fn parse_timestamp(input: &str) -> Result<DateTime<Utc>, ParseError> {
+    Utc.timestamp_opt(input.parse()?, 0)
+        .single()
+        .ok_or(ParseError::Malformed("timestamp is out of range"))
+}
+
 fn parse_sampling(input: &str) -> Result<f64, ParseError> {
     if !input.starts_with('@') || input.len() < 2 {
         return Err(ParseError::Malformed(
@@ -138,15 +261,16 @@ fn parse_tags(input: &str) -> Result<HashMap<String, String>, ParseError> {
     Ok(result)
 }
 
-fn parse_direction(input: &str) -> Result<Option<Direction>, ParseError> {
+/// Gauge values with a leading sign are changes to the current value.
+fn parse_gauge(input: &str) -> Result<(f64, MetricKind), ParseError> {
     match input
         .chars()
         .next()
-        .ok_or_else(|| ParseError::Malformed("empty body component"))?
+        .ok_or_else(|| ParseError::Malformed("empty first body component"))?
     {
-        '+' => Ok(Some(Direction::Plus)),
-        '-' => Ok(Some(Direction::Minus)),
-        c if c.is_ascii_digit() => Ok(None),
+        '+' => Ok((input[1..].parse()?, MetricKind::Incremental)),
+        '-' => Ok((input.parse()?, MetricKind::Incremental)),
+        c if c.is_ascii_digit() => Ok((input.parse()?, MetricKind::Absolute)),
         _other => Err(ParseError::Malformed("invalid gauge value prefix")),
     }
 }
@@ -205,8 +329,9 @@ impl From<ParseFloatError> for ParseError {
 
 #[cfg(test)]
 mod test {
-    use super::{parse, sanitize_key, sanitize_sampling};
-    use crate::event::{metric::Direction, Metric};
+    use super::{parse, sanitize_key, sanitize_sampling, ParseError};
+    use crate::event::{Metric, MetricKind};
+    use chrono::{TimeZone, Utc};
 
     #[test]
     fn basic_counter() {
@@ -217,6 +342,7 @@ mod test {
                 val: 1.0,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             }),
         );
     }
@@ -237,6 +363,7 @@ mod test {
                     .into_iter()
                     .collect(),
                 ),
+                kind: MetricKind::Incremental,
             }),
         );
     }
@@ -250,6 +377,7 @@ mod test {
                 val: 20.0,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             }),
         );
     }
@@ -263,6 +391,7 @@ mod test {
                 val: 2.0,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             }),
         );
     }
@@ -277,6 +406,7 @@ mod test {
                 sample_rate: 10,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             }),
         );
     }
@@ -299,6 +429,7 @@ mod test {
                     .into_iter()
                     .collect(),
                 ),
+                kind: MetricKind::Incremental,
             }),
         );
     }
@@ -310,9 +441,9 @@ mod test {
             Ok(Metric::Gauge {
                 name: "gaugor".into(),
                 val: 333.0,
-                direction: None,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Absolute,
             }),
         );
     }
@@ -323,10 +454,10 @@ mod test {
             parse("gaugor:-4|g"),
             Ok(Metric::Gauge {
                 name: "gaugor".into(),
-                val: 4.0,
-                direction: Some(Direction::Minus),
+                val: -4.0,
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             }),
         );
         assert_eq!(
@@ -334,9 +465,9 @@ mod test {
             Ok(Metric::Gauge {
                 name: "gaugor".into(),
                 val: 10.0,
-                direction: Some(Direction::Plus),
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
             }),
         );
     }
@@ -350,8 +481,89 @@ mod test {
                 val: "765".into(),
                 timestamp: None,
                 tags: None,
+                kind: MetricKind::Incremental,
+            }),
+        );
+    }
+
+    #[test]
+    fn tags_before_sampling() {
+        assert_eq!(
+            parse("glork:320|d|#region:us-west1|@0.5|c:83c0a99c0a54"),
+            Ok(Metric::Histogram {
+                name: "glork".into(),
+                val: 320.0,
+                sample_rate: 2,
+                timestamp: None,
+                tags: Some(
+                    vec![("region".to_owned(), "us-west1".to_owned())]
+                        .into_iter()
+                        .collect()
+                ),
+                kind: MetricKind::Incremental,
+            }),
+        );
+    }
+
+    #[test]
+    fn dogstatsd_event() {
+        assert_eq!(
+            parse("_e{10,16}:Deployment|Rolled out\\nv1.2|d:1542182950|h:web-1|p:low|t:success|#env:prod"),
+            Ok(Metric::Counter {
+                name: "Deployment".into(),
+                val: 1.0,
+                timestamp: Some(Utc.timestamp(1542182950, 0)),
+                tags: Some(
+                    vec![
+                        ("env".to_owned(), "prod".to_owned()),
+                        ("text".to_owned(), "Rolled out\nv1.2".to_owned()),
+                        ("host".to_owned(), "web-1".to_owned()),
+                        ("priority".to_owned(), "low".to_owned()),
+                        ("alert_type".to_owned(), "success".to_owned()),
+                    ]
+                    .into_iter()
+                    .collect()
+                ),
+                kind: MetricKind::Incremental,
+            }),
+        );
+        assert_eq!(
+            parse("_e{10,40}:Deployment|too short"),
+            Err(ParseError::Malformed(
+                "event text is shorter than its length"
+            ))
+        );
+    }
+
+    #[test]
+    fn dogstatsd_service_check() {
+        assert_eq!(
+            parse("_sc|redis.can_connect|2|h:db-1|#env:prod|m:Connection refused|retrying"),
+            Ok(Metric::Gauge {
+                name: "redis.can_connect".into(),
+                val: 2.0,
+                timestamp: None,
+                tags: Some(
+                    vec![
+                        ("env".to_owned(), "prod".to_owned()),
+                        ("host".to_owned(), "db-1".to_owned()),
+                        (
+                            "message".to_owned(),
+                            "Connection refused|retrying".to_owned()
+                        ),
+                    ]
+                    .into_iter()
+                    .collect()
+                ),
+                kind: MetricKind::Absolute,
             }),
         );
+        assert_eq!(
+            parse("_sc|redis.can_connect|4"),
+            Err(ParseError::Malformed(
+                "service check status should be 0, 1, 2 or 3"
+            ))
+        );
     }
 
     #[test]
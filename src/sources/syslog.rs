@@ -1,15 +1,15 @@
 use super::util::{SocketListenAddr, TcpSource};
 use crate::{
     event::{self, Event},
+    syslog,
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
 };
 use bytes::Bytes;
-use chrono::{TimeZone, Utc};
+use chrono::Utc;
 use derive_is_enum_variant::is_enum_variant;
 use futures::{future, sync::mpsc, Future, Sink, Stream};
 use serde::{Deserialize, Serialize};
 use std::{net::SocketAddr, path::PathBuf};
-use syslog_rfc5424::{self, message::ProcId::*, SyslogMessage};
 use tokio::{
     self,
     codec::{BytesCodec, FramedRead, LinesCodec},
@@ -218,8 +218,7 @@ fn event_from_str(
         bytes = &field::display(line.len())
     );
 
-    let line = line.trim();
-    syslog_rfc5424::parse_message(line)
+    syslog::parse_rfc5424(line)
         .map(|parsed| {
             let mut event = Event::from(&parsed.msg[..]);
 
@@ -233,15 +232,12 @@ fn event_from_str(
                     .insert_implicit(host_key.into(), default_host.into());
             }
 
-            let timestamp = parsed
-                .timestamp
-                .map(|ts| Utc.timestamp(ts, parsed.timestamp_nanos.unwrap_or(0) as u32))
-                .unwrap_or_else(Utc::now);
+            let timestamp = parsed.timestamp.unwrap_or_else(Utc::now);
             event
                 .as_mut_log()
                 .insert_implicit(event::TIMESTAMP.clone(), timestamp.into());
 
-            insert_fields_from_rfc5424(&mut event, parsed);
+            parsed.insert_fields(event.as_mut_log());
 
             trace!(
                 message = "processing one event.",
@@ -254,35 +250,6 @@ fn event_from_str(
         .ok()
 }
 
-fn insert_fields_from_rfc5424(event: &mut Event, parsed: SyslogMessage) {
-    let log = event.as_mut_log();
-
-    log.insert_implicit("severity".into(), parsed.severity.as_str().into());
-    log.insert_implicit("facility".into(), parsed.facility.as_str().into());
-    log.insert_implicit("version".into(), parsed.version.into());
-
-    if let Some(app_name) = parsed.appname {
-        log.insert_implicit("appname".into(), app_name.into());
-    }
-    if let Some(msg_id) = parsed.msgid {
-        log.insert_implicit("msgid".into(), msg_id.into());
-    }
-    if let Some(proc_id) = parsed.procid {
-        let value = match proc_id {
-            PID(pid) => pid.into(),
-            Name(name) => name.into(),
-        };
-        log.insert_implicit("procid".into(), value);
-    }
-
-    for (id, data) in parsed.sd.iter() {
-        for (name, value) in data.iter() {
-            let key = format!("{}.{}", id, name);
-            log.insert_explicit(key.into(), value.clone().into());
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::{event_from_str, SyslogConfig};
@@ -456,6 +423,13 @@ mod test {
         );
     }
 
+    #[test]
+    fn rfc3164_is_not_accepted() {
+        // the source is strictly RFC 5424, unlike the `syslog_parser` transform
+        let raw = "<190>Feb 13 21:31:56 74794bfb6795 liblogging-stdlog[123]: start";
+        assert!(event_from_str(&"host".to_string(), None, raw).is_none());
+    }
+
     #[test]
     #[ignore]
     fn rsyslog_omfwd_tcp_default() {
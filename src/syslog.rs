@@ -0,0 +1,260 @@
+//! Parsing of syslog messages, shared by the `syslog` source and the
+//! `syslog_parser` transform.
+
+use crate::event::{LogEvent, ValueKind};
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeZone, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use snafu::Snafu;
+use string_cache::DefaultAtom as Atom;
+use syslog_rfc5424::message::ProcId;
+
+lazy_static! {
+    // <PRI>Mmm dd hh:mm:ss HOSTNAME TAG[PID]: MSG
+    static ref RFC3164: Regex = Regex::new(
+        r"^<(?P<pri>\d{1,3})>(?P<ts>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<host>\S+) (?P<tag>[^:\[\s]+)(?:\[(?P<pid>[^\]]*)\])?:\s?(?P<msg>.*)$"
+    )
+    .unwrap();
+}
+
+const FACILITIES: [&str; 24] = [
+    "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news", "uucp", "cron", "authpriv",
+    "ftp", "ntp", "audit", "alert", "clockd", "local0", "local1", "local2", "local3", "local4",
+    "local5", "local6", "local7",
+];
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+#[derive(Debug, Snafu)]
+pub enum ParseError {
+    #[snafu(display("Invalid syslog message: {}", message))]
+    InvalidMessage { message: String },
+}
+
+/// A syslog message parsed from either the RFC 5424 or the RFC 3164 (BSD)
+/// format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub hostname: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub msg: String,
+    fields: Vec<(Atom, ValueKind)>,
+    structured_data: Vec<(Atom, ValueKind)>,
+}
+
+impl Message {
+    /// Insert the parsed header fields (`severity`, `facility`, `version`,
+    /// `appname`, `msgid`, `procid`) and structured data, as
+    /// `<sd-id>.<param>`, into `log`. The message body, host and timestamp
+    /// are left to the caller.
+    pub fn insert_fields(self, log: &mut LogEvent) {
+        for (key, value) in self.fields {
+            log.insert_implicit(key, value);
+        }
+        for (key, value) in self.structured_data {
+            log.insert_explicit(key, value);
+        }
+    }
+}
+
+/// Parse a syslog line, trying RFC 5424 first and falling back to RFC 3164.
+pub fn parse(line: &str) -> Result<Message, ParseError> {
+    let line = line.trim();
+    match syslog_rfc5424::parse_message(line) {
+        Ok(parsed) => Ok(from_rfc5424(parsed)),
+        Err(error) => parse_rfc3164(line, Utc::now()).ok_or_else(|| ParseError::InvalidMessage {
+            message: error.to_string(),
+        }),
+    }
+}
+
+/// Parse a syslog line in the RFC 5424 format only.
+pub fn parse_rfc5424(line: &str) -> Result<Message, ParseError> {
+    syslog_rfc5424::parse_message(line.trim())
+        .map(from_rfc5424)
+        .map_err(|error| ParseError::InvalidMessage {
+            message: error.to_string(),
+        })
+}
+
+fn from_rfc5424(parsed: syslog_rfc5424::SyslogMessage) -> Message {
+    let mut fields: Vec<(Atom, ValueKind)> = vec![
+        ("severity".into(), parsed.severity.as_str().into()),
+        ("facility".into(), parsed.facility.as_str().into()),
+        ("version".into(), parsed.version.into()),
+    ];
+
+    if let Some(app_name) = parsed.appname {
+        fields.push(("appname".into(), app_name.into()));
+    }
+    if let Some(msg_id) = parsed.msgid {
+        fields.push(("msgid".into(), msg_id.into()));
+    }
+    if let Some(proc_id) = parsed.procid {
+        let value = match proc_id {
+            ProcId::PID(pid) => pid.into(),
+            ProcId::Name(name) => name.into(),
+        };
+        fields.push(("procid".into(), value));
+    }
+
+    let mut structured_data = Vec::new();
+    for (id, data) in parsed.sd.iter() {
+        for (name, value) in data.iter() {
+            let key = format!("{}.{}", id, name);
+            structured_data.push((key.into(), value.clone().into()));
+        }
+    }
+
+    let timestamp = parsed
+        .timestamp
+        .map(|ts| Utc.timestamp(ts, parsed.timestamp_nanos.unwrap_or(0) as u32));
+
+    Message {
+        hostname: parsed.hostname,
+        timestamp,
+        msg: parsed.msg,
+        fields,
+        structured_data,
+    }
+}
+
+/// RFC 3164 timestamps carry no year or time zone, so they are taken to be
+/// UTC in the year of `now`. Like rsyslog, a December timestamp seen in
+/// January is placed in the previous year, and a January timestamp seen in
+/// December in the next one.
+fn parse_rfc3164(line: &str, now: DateTime<Utc>) -> Option<Message> {
+    let caps = RFC3164.captures(line)?;
+
+    let pri = caps["pri"].parse::<usize>().ok()?;
+    let facility = FACILITIES.get(pri / 8)?;
+    let severity = SEVERITIES[pri % 8];
+
+    let timestamp = NaiveDateTime::parse_from_str(
+        &format!("{} {}", now.year(), &caps["ts"]),
+        "%Y %b %e %H:%M:%S",
+    )
+    .ok()
+    .and_then(|ts| match (ts.month(), now.month()) {
+        (12, 1) => ts.with_year(now.year() - 1),
+        (1, 12) => ts.with_year(now.year() + 1),
+        _ => Some(ts),
+    })
+    .map(|ts| Utc.from_utc_datetime(&ts));
+
+    let mut fields: Vec<(Atom, ValueKind)> = vec![
+        ("severity".into(), severity.into()),
+        ("facility".into(), (*facility).into()),
+        ("appname".into(), caps["tag"].into()),
+    ];
+    if let Some(pid) = caps.name("pid") {
+        let value = match pid.as_str().parse::<i32>() {
+            Ok(pid) => pid.into(),
+            Err(_) => pid.as_str().into(),
+        };
+        fields.push(("procid".into(), value));
+    }
+
+    Some(Message {
+        hostname: Some(caps["host"].into()),
+        timestamp,
+        msg: caps["msg"].into(),
+        fields,
+        structured_data: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_rfc3164, parse_rfc5424};
+    use crate::event::Event;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn parses_rfc5424() {
+        let message = parse(
+            r#"<13>1 2019-02-13T19:48:34+00:00 74794bfb6795 root 8449 - [meta sequenceId="1"] i am foobar"#,
+        )
+        .unwrap();
+
+        assert_eq!(message.hostname, Some("74794bfb6795".into()));
+        assert_eq!(
+            message.timestamp,
+            Some(Utc.ymd(2019, 2, 13).and_hms(19, 48, 34))
+        );
+        assert_eq!(message.msg, "i am foobar");
+
+        let mut log = Event::new_empty_log().into_log();
+        message.insert_fields(&mut log);
+        assert_eq!(log[&"severity".into()], "notice".into());
+        assert_eq!(log[&"facility".into()], "user".into());
+        assert_eq!(log[&"version".into()], 1.into());
+        assert_eq!(log[&"appname".into()], "root".into());
+        assert_eq!(log[&"procid".into()], 8449.into());
+        assert_eq!(log[&"meta.sequenceId".into()], "1".into());
+    }
+
+    #[test]
+    fn parses_rfc3164() {
+        let now = Utc.ymd(2019, 6, 1).and_hms(0, 0, 0);
+        let message = parse_rfc3164(
+            "<190>Feb  3 21:31:56 74794bfb6795 liblogging-stdlog[123]: start",
+            now,
+        )
+        .unwrap();
+
+        assert_eq!(message.hostname, Some("74794bfb6795".into()));
+        assert_eq!(
+            message.timestamp,
+            Some(Utc.ymd(2019, 2, 3).and_hms(21, 31, 56))
+        );
+        assert_eq!(message.msg, "start");
+
+        let mut log = Event::new_empty_log().into_log();
+        message.insert_fields(&mut log);
+        assert_eq!(log[&"severity".into()], "info".into());
+        assert_eq!(log[&"facility".into()], "local7".into());
+        assert_eq!(log[&"appname".into()], "liblogging-stdlog".into());
+        assert_eq!(log[&"procid".into()], 123.into());
+    }
+
+    #[test]
+    fn infers_rfc3164_year_around_new_year() {
+        let line = |ts: &str| format!("<13>{} host app: msg", ts);
+
+        let now = Utc.ymd(2020, 1, 1).and_hms(0, 0, 5);
+        let message = parse_rfc3164(&line("Dec 31 23:59:58"), now).unwrap();
+        assert_eq!(
+            message.timestamp,
+            Some(Utc.ymd(2019, 12, 31).and_hms(23, 59, 58))
+        );
+
+        let now = Utc.ymd(2019, 12, 31).and_hms(23, 59, 59);
+        let message = parse_rfc3164(&line("Jan  1 00:00:01"), now).unwrap();
+        assert_eq!(
+            message.timestamp,
+            Some(Utc.ymd(2020, 1, 1).and_hms(0, 0, 1))
+        );
+
+        let message = parse_rfc3164(&line("Dec 30 12:00:00"), now).unwrap();
+        assert_eq!(
+            message.timestamp,
+            Some(Utc.ymd(2019, 12, 30).and_hms(12, 0, 0))
+        );
+    }
+
+    #[test]
+    fn rfc5424_only_rejects_rfc3164() {
+        let line = "<190>Feb  3 21:31:56 74794bfb6795 liblogging-stdlog[123]: start";
+        assert!(parse(line).is_ok());
+        assert!(parse_rfc5424(line).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_messages() {
+        assert!(parse("i am not syslog").is_err());
+        assert!(parse("<999>Feb  3 21:31:56 host app: msg").is_err());
+    }
+}
@@ -142,7 +142,7 @@ pub fn build_pieces(config: &super::Config) -> Result<(Pieces, Vec<String>), Vec
 
         let typetag = &transform.inner.transform_type();
 
-        let mut transform = match transform.inner.build() {
+        let transform = match transform.inner.build() {
             Err(error) => {
                 errors.push(format!("Transform \"{}\": {}", name, error));
                 continue;
@@ -155,13 +155,8 @@ pub fn build_pieces(config: &super::Config) -> Result<(Pieces, Vec<String>), Vec
 
         let (output, control) = Fanout::new();
 
-        let transform = input_rx
-            .map(move |event| {
-                let mut output = Vec::with_capacity(1);
-                transform.transform_into(&mut output, event);
-                futures::stream::iter_ok(output.into_iter())
-            })
-            .flatten()
+        let transform = transform
+            .transform_stream(Box::new(input_rx))
             .forward(output)
             .map(|_| ());
         let task = Task::new(&name, &typetag, transform);
@@ -69,7 +69,10 @@ impl Transform for AddTags {
 #[cfg(test)]
 mod tests {
     use super::AddTags;
-    use crate::{event::Event, event::Metric, transforms::Transform};
+    use crate::{
+        event::{Event, Metric, MetricKind},
+        transforms::Transform,
+    };
     use indexmap::IndexMap;
     use string_cache::DefaultAtom as Atom;
 
@@ -78,9 +81,9 @@ mod tests {
         let event = Event::Metric(Metric::Gauge {
             name: "bar".into(),
             val: 10.0,
-            direction: None,
             timestamp: None,
             tags: None,
+            kind: MetricKind::Absolute,
         });
 
         let map: IndexMap<Atom, String> = vec![
@@ -0,0 +1,265 @@
+use super::Transform;
+use crate::{
+    event::{Event, Metric},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use futures::Stream;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::mem::Discriminant;
+use std::time::Duration;
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct AggregateConfig {
+    pub interval_ms: u64,
+}
+
+impl Default for AggregateConfig {
+    fn default() -> Self {
+        Self { interval_ms: 10000 }
+    }
+}
+
+inventory::submit! {
+    TransformDescription::new::<AggregateConfig>("aggregate")
+}
+
+#[typetag::serde(name = "aggregate")]
+impl TransformConfig for AggregateConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        Ok(Box::new(Aggregate::new(Duration::from_millis(
+            self.interval_ms,
+        ))))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "aggregate"
+    }
+}
+
+/// Identifies a single series. Sets and histograms also include the
+/// observed value, as `Metric::merge` only folds together identical set
+/// members and histogram samples of the same value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SeriesKey {
+    kind: Discriminant<Metric>,
+    name: String,
+    tags: Vec<(String, String)>,
+    value: Option<String>,
+}
+
+impl SeriesKey {
+    fn new(metric: &Metric) -> Self {
+        let (name, value) = match metric {
+            Metric::Counter { name, .. } | Metric::Gauge { name, .. } => (name, None),
+            Metric::Histogram { name, val, .. } => (name, Some(val.to_bits().to_string())),
+            Metric::Set { name, val, .. } => (name, Some(val.clone())),
+            Metric::AggregatedHistogram { name, .. } | Metric::AggregatedSummary { name, .. } => {
+                (name, None)
+            }
+        };
+
+        let mut tags = metric
+            .tags()
+            .as_ref()
+            .map(|tags| {
+                tags.iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        tags.sort();
+
+        Self {
+            kind: std::mem::discriminant(metric),
+            name: name.clone(),
+            tags,
+            value,
+        }
+    }
+}
+
+pub struct Aggregate {
+    interval: Duration,
+    series: IndexMap<SeriesKey, Metric>,
+}
+
+impl Aggregate {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            series: IndexMap::new(),
+        }
+    }
+
+    fn record(&mut self, metric: Metric) {
+        let key = SeriesKey::new(&metric);
+        match self.series.get_mut(&key) {
+            Some(existing) => existing.merge(&metric),
+            None => {
+                self.series.insert(key, metric);
+            }
+        }
+    }
+
+    fn flush_into(&mut self, output: &mut Vec<Event>) {
+        output.extend(
+            self.series
+                .drain(..)
+                .map(|(_, metric)| Event::Metric(metric)),
+        );
+    }
+}
+
+impl Transform for Aggregate {
+    // Metrics are only emitted when the interval elapses, see
+    // `transform_stream`.
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        self.record(event.into_metric());
+        None
+    }
+
+    fn transform_stream(
+        self: Box<Self>,
+        input_rx: Box<dyn Stream<Item = Event, Error = ()> + Send>,
+    ) -> Box<dyn Stream<Item = Event, Error = ()> + Send>
+    where
+        Self: 'static,
+    {
+        let interval = self.interval;
+        super::stream_with_timer(self, interval, input_rx, Self::flush_into, Self::flush_into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Aggregate;
+    use crate::{
+        event::{Event, Metric, MetricKind},
+        transforms::Transform,
+    };
+    use futures::{stream, Stream};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn tags(value: &str) -> Option<HashMap<String, String>> {
+        Some(
+            vec![("env".to_owned(), value.to_owned())]
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    fn counter(name: &str, val: f64, env: &str) -> Event {
+        Event::Metric(Metric::Counter {
+            name: name.into(),
+            val,
+            timestamp: None,
+            tags: tags(env),
+            kind: MetricKind::Incremental,
+        })
+    }
+
+    fn gauge(val: f64, kind: MetricKind) -> Event {
+        Event::Metric(Metric::Gauge {
+            name: "gauge".into(),
+            val,
+            timestamp: None,
+            tags: None,
+            kind,
+        })
+    }
+
+    fn aggregate_all(events: Vec<Event>) -> Vec<Event> {
+        let aggregate = Box::new(Aggregate::new(Duration::from_secs(3600)));
+        let mut rt = crate::runtime::Runtime::new().unwrap();
+        rt.block_on(
+            aggregate
+                .transform_stream(Box::new(stream::iter_ok(events)))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn aggregate_only_emits_on_flush() {
+        let mut aggregate = Aggregate::new(Duration::from_secs(10));
+        assert!(aggregate.transform(counter("a", 1.0, "prod")).is_none());
+
+        let mut output = Vec::new();
+        aggregate.flush_into(&mut output);
+        assert_eq!(output, vec![counter("a", 1.0, "prod")]);
+
+        output.clear();
+        aggregate.flush_into(&mut output);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn aggregate_counters_by_name_and_tags() {
+        let output = aggregate_all(vec![
+            counter("a", 1.0, "prod"),
+            counter("a", 2.0, "prod"),
+            counter("a", 5.0, "staging"),
+            counter("b", 1.0, "prod"),
+            counter("a", 3.0, "prod"),
+        ]);
+
+        assert_eq!(
+            output,
+            vec![
+                counter("a", 6.0, "prod"),
+                counter("a", 5.0, "staging"),
+                counter("b", 1.0, "prod"),
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregate_gauges() {
+        let output = aggregate_all(vec![
+            gauge(10.0, MetricKind::Absolute),
+            gauge(2.0, MetricKind::Incremental),
+            gauge(-5.0, MetricKind::Incremental),
+        ]);
+        assert_eq!(output, vec![gauge(7.0, MetricKind::Absolute)]);
+
+        let output = aggregate_all(vec![
+            gauge(2.0, MetricKind::Incremental),
+            gauge(-3.0, MetricKind::Incremental),
+        ]);
+        assert_eq!(output, vec![gauge(-1.0, MetricKind::Incremental)]);
+
+        let output = aggregate_all(vec![
+            gauge(2.0, MetricKind::Incremental),
+            gauge(4.0, MetricKind::Absolute),
+            gauge(1.0, MetricKind::Incremental),
+        ]);
+        assert_eq!(output, vec![gauge(5.0, MetricKind::Absolute)]);
+    }
+
+    #[test]
+    fn aggregate_sets_deduplicates_members() {
+        let set = |val: &str| {
+            Event::Metric(Metric::Set {
+                name: "users".into(),
+                val: val.into(),
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Incremental,
+            })
+        };
+
+        let output = aggregate_all(vec![set("alice"), set("bob"), set("alice")]);
+        assert_eq!(output, vec![set("alice"), set("bob")]);
+    }
+}
@@ -0,0 +1,202 @@
+use super::Transform;
+use crate::{
+    event::{self, Event, ValueKind},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use serde::{Deserialize, Serialize};
+use std::iter::Peekable;
+use std::str::Chars;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct AnsiStripperConfig {
+    pub field: Option<Atom>,
+    pub strip_control_characters: bool,
+}
+
+inventory::submit! {
+    TransformDescription::new::<AnsiStripperConfig>("ansi_stripper")
+}
+
+#[typetag::serde(name = "ansi_stripper")]
+impl TransformConfig for AnsiStripperConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        Ok(Box::new(AnsiStripper {
+            field: self.field.clone().unwrap_or_else(|| event::MESSAGE.clone()),
+            strip_control_characters: self.strip_control_characters,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "ansi_stripper"
+    }
+}
+
+pub struct AnsiStripper {
+    field: Atom,
+    strip_control_characters: bool,
+}
+
+impl Transform for AnsiStripper {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        match event.as_mut_log().get_mut(&self.field) {
+            Some(ValueKind::Bytes(bytes)) => {
+                let value = String::from_utf8_lossy(bytes).into_owned();
+                let stripped = strip(&value, self.strip_control_characters);
+                if stripped != value {
+                    *bytes = stripped.into();
+                }
+            }
+            Some(_) => {}
+            None => debug!(
+                message = "Field does not exist.",
+                field = self.field.as_ref(),
+            ),
+        }
+
+        Some(event)
+    }
+}
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+const CSI: char = '\u{9b}';
+
+/// Remove ANSI escape sequences from `input`. With `control_characters`
+/// set, other control characters except tabs and newlines are removed as
+/// well.
+fn strip(input: &str, control_characters: bool) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ESC => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                Some(']') => skip_string(&mut chars, true),
+                Some('P') | Some('X') | Some('^') | Some('_') => skip_string(&mut chars, false),
+                // Character set selection and the like, e.g. `ESC ( B`.
+                Some(' '..='/') => {
+                    while let Some(&c) = chars.peek() {
+                        if !(' '..='/').contains(&c) {
+                            break;
+                        }
+                        chars.next();
+                    }
+                    chars.next();
+                }
+                // Any other escape is a single character.
+                Some(_) | None => {}
+            },
+            CSI => skip_csi(&mut chars),
+            '\t' | '\n' => output.push(c),
+            c if control_characters && c.is_control() => {}
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Skip the parameters, intermediates and final byte of a control sequence.
+fn skip_csi(chars: &mut Peekable<Chars>) {
+    for c in chars {
+        if ('\u{40}'..='\u{7e}').contains(&c) {
+            break;
+        }
+    }
+}
+
+/// Skip a control string, which is terminated by `ESC \`, or for operating
+/// system commands also by `BEL`.
+fn skip_string(chars: &mut Peekable<Chars>, bel_terminates: bool) {
+    while let Some(c) = chars.next() {
+        match c {
+            BEL if bel_terminates => break,
+            ESC if chars.peek() == Some(&'\\') => {
+                chars.next();
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strip, AnsiStripperConfig};
+    use crate::{event::Event, topology::config::TransformConfig};
+
+    #[test]
+    fn ansi_stripper_strips_escape_sequences() {
+        let cases = vec![
+            ("\x1b[1;31merror\x1b[0m: failed", "error: failed"),
+            ("\x1b[38;5;208morange\x1b[39m", "orange"),
+            ("\x1b[2K\x1b[1Gprogress", "progress"),
+            ("\x1b]0;window title\x07shell", "shell"),
+            ("\x1b]8;;http://example.com\x1b\\link\x1b]8;;\x1b\\", "link"),
+            ("\x1b(Bplain\x1b=", "plain"),
+            ("\u{9b}32mgreen", "green"),
+            ("no escapes here", "no escapes here"),
+            ("unterminated \x1b[", "unterminated "),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(strip(input, false), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn ansi_stripper_control_characters() {
+        let input = "a\rb\x08c\td\ne\x7f";
+        assert_eq!(strip(input, false), input);
+        assert_eq!(strip(input, true), "abc\td\ne");
+    }
+
+    #[test]
+    fn ansi_stripper_transforms_field() {
+        let mut event = Event::from("\x1b[32mINFO\x1b[0m started");
+        event
+            .as_mut_log()
+            .insert_explicit("raw".into(), "\x1b[1mbold\x1b[0m".into());
+
+        let mut stripper = toml::from_str::<AnsiStripperConfig>("")
+            .unwrap()
+            .build()
+            .unwrap();
+        let event = stripper.transform(event).unwrap();
+        assert_eq!(event.as_log()[&"message".into()], "INFO started".into());
+        assert_eq!(event.as_log()[&"raw".into()], "\x1b[1mbold\x1b[0m".into());
+
+        let mut stripper = toml::from_str::<AnsiStripperConfig>("field = \"raw\"")
+            .unwrap()
+            .build()
+            .unwrap();
+        let event = stripper.transform(event).unwrap();
+        assert_eq!(event.as_log()[&"raw".into()], "bold".into());
+    }
+    #[test]
+    fn ansi_stripper_keeps_implicit_fields_implicit() {
+        let event = Event::from("\x1b[32mINFO\x1b[0m started");
+
+        let mut stripper = toml::from_str::<AnsiStripperConfig>("")
+            .unwrap()
+            .build()
+            .unwrap();
+        let event = stripper.transform(event).unwrap();
+        assert_eq!(event.as_log()[&"message".into()], "INFO started".into());
+        assert!(event
+            .as_log()
+            .explicit_fields()
+            .all(|(key, _)| key != &"message".into()));
+    }
+}
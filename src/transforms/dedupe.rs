@@ -0,0 +1,250 @@
+use super::Transform;
+use crate::{
+    event::{self, Event, ValueKind},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use bytes::Bytes;
+use linked_hash_map::LinkedHashMap;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Only one of `fields.match` and `fields.ignore` may be specified"))]
+    ConflictingFields,
+    #[snafu(display("The cache size must be greater than zero"))]
+    ZeroCacheSize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct DedupeConfig {
+    #[serde(default)]
+    pub fields: FieldMatchConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FieldMatchConfig {
+    #[serde(rename = "match")]
+    pub match_fields: Option<Vec<Atom>>,
+    #[serde(rename = "ignore")]
+    pub ignore_fields: Option<Vec<Atom>>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    pub num_events: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { num_events: 5000 }
+    }
+}
+
+inventory::submit! {
+    TransformDescription::new::<DedupeConfig>("dedupe")
+}
+
+#[typetag::serde(name = "dedupe")]
+impl TransformConfig for DedupeConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        if self.cache.num_events == 0 {
+            return Err(Box::new(BuildError::ZeroCacheSize));
+        }
+
+        let fields = match (&self.fields.match_fields, &self.fields.ignore_fields) {
+            (Some(_), Some(_)) => return Err(Box::new(BuildError::ConflictingFields)),
+            (Some(fields), None) => FieldMatch::Match(fields.clone()),
+            (None, Some(fields)) => FieldMatch::Ignore(fields.clone()),
+            (None, None) => FieldMatch::Match(vec![
+                event::TIMESTAMP.clone(),
+                event::HOST.clone(),
+                event::MESSAGE.clone(),
+            ]),
+        };
+
+        Ok(Box::new(Dedupe::new(fields, self.cache.num_events)))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "dedupe"
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum FieldMatch {
+    /// Only the listed fields make up the fingerprint of an event.
+    Match(Vec<Atom>),
+    /// Every field except the listed ones makes up the fingerprint.
+    Ignore(Vec<Atom>),
+}
+
+/// A field value as stored in the cache. The type is kept alongside the
+/// raw bytes so that, say, the integer `1` and the string `"1"` do not
+/// compare as equal.
+type TypedValue = (u8, Bytes);
+
+/// The fingerprint of a single event. For `Match` the values are in the
+/// order of the configured fields, with `None` for a missing field. For
+/// `Ignore` the entries are sorted by field name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Fingerprint {
+    Match(Vec<Option<TypedValue>>),
+    Ignore(Vec<(Atom, TypedValue)>),
+}
+
+pub struct Dedupe {
+    fields: FieldMatch,
+    cache: LinkedHashMap<Fingerprint, ()>,
+    capacity: usize,
+}
+
+impl Dedupe {
+    pub fn new(fields: FieldMatch, capacity: usize) -> Self {
+        Self {
+            fields,
+            cache: LinkedHashMap::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn fingerprint(&self, event: &Event) -> Fingerprint {
+        let log = event.as_log();
+        match &self.fields {
+            FieldMatch::Match(fields) => Fingerprint::Match(
+                fields
+                    .iter()
+                    .map(|field| log.get(field).map(typed_value))
+                    .collect(),
+            ),
+            FieldMatch::Ignore(ignore) => {
+                let mut entries = log
+                    .all_fields()
+                    .filter(|(field, _)| !ignore.contains(field))
+                    .map(|(field, value)| (field.clone(), typed_value(value)))
+                    .collect::<Vec<_>>();
+                entries.sort_by(|a, b| a.0[..].cmp(&b.0[..]));
+                Fingerprint::Ignore(entries)
+            }
+        }
+    }
+}
+
+fn typed_value(value: &ValueKind) -> TypedValue {
+    let kind = match value {
+        ValueKind::Bytes(_) => 0,
+        ValueKind::Integer(_) => 1,
+        ValueKind::Float(_) => 2,
+        ValueKind::Boolean(_) => 3,
+        ValueKind::Timestamp(_) => 4,
+    };
+    (kind, value.as_bytes())
+}
+
+impl Transform for Dedupe {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        let fingerprint = self.fingerprint(&event);
+
+        if self.cache.get_refresh(&fingerprint).is_some() {
+            trace!(message = "Dropping duplicate event.");
+            return None;
+        }
+
+        if self.cache.len() >= self.capacity {
+            self.cache.pop_front();
+        }
+        self.cache.insert(fingerprint, ());
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dedupe, FieldMatch};
+    use crate::{event::Event, transforms::Transform};
+
+    fn make_event(message: &str, matched: &str, other: &str) -> Event {
+        let mut event = Event::from(message);
+        event
+            .as_mut_log()
+            .insert_explicit("matched".into(), matched.into());
+        event
+            .as_mut_log()
+            .insert_explicit("other".into(), other.into());
+        event
+    }
+
+    #[test]
+    fn dedupe_match_drops_duplicates() {
+        let mut dedupe = Dedupe::new(FieldMatch::Match(vec!["matched".into()]), 5);
+
+        assert!(dedupe.transform(make_event("one", "a", "x")).is_some());
+        assert!(dedupe.transform(make_event("two", "b", "x")).is_some());
+        assert!(dedupe.transform(make_event("three", "a", "y")).is_none());
+    }
+
+    #[test]
+    fn dedupe_match_treats_missing_fields_as_values() {
+        let mut dedupe = Dedupe::new(FieldMatch::Match(vec!["missing".into()]), 5);
+
+        assert!(dedupe.transform(Event::from("one")).is_some());
+        assert!(dedupe.transform(Event::from("two")).is_none());
+    }
+
+    #[test]
+    fn dedupe_ignore_compares_remaining_fields() {
+        let mut dedupe = Dedupe::new(
+            FieldMatch::Ignore(vec!["timestamp".into(), "other".into()]),
+            5,
+        );
+
+        assert!(dedupe.transform(make_event("one", "a", "x")).is_some());
+        assert!(dedupe.transform(make_event("one", "a", "y")).is_none());
+        assert!(dedupe.transform(make_event("one", "b", "x")).is_some());
+    }
+
+    #[test]
+    fn dedupe_distinguishes_value_types() {
+        let mut dedupe = Dedupe::new(FieldMatch::Match(vec!["matched".into()]), 5);
+
+        let mut event = Event::from("one");
+        event
+            .as_mut_log()
+            .insert_explicit("matched".into(), "1".into());
+        assert!(dedupe.transform(event).is_some());
+
+        let mut event = Event::from("two");
+        event
+            .as_mut_log()
+            .insert_explicit("matched".into(), 1.into());
+        assert!(dedupe.transform(event).is_some());
+    }
+
+    #[test]
+    fn dedupe_evicts_least_recently_seen() {
+        let mut dedupe = Dedupe::new(FieldMatch::Match(vec!["matched".into()]), 2);
+
+        assert!(dedupe.transform(make_event("1", "a", "")).is_some());
+        assert!(dedupe.transform(make_event("2", "b", "")).is_some());
+        // Refreshes "a", so "b" is now the oldest entry.
+        assert!(dedupe.transform(make_event("3", "a", "")).is_none());
+        assert!(dedupe.transform(make_event("4", "c", "")).is_some());
+        assert!(dedupe.transform(make_event("5", "a", "")).is_none());
+        assert!(dedupe.transform(make_event("6", "b", "")).is_some());
+    }
+}
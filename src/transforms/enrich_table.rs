@@ -0,0 +1,259 @@
+use super::{util::WatchedFile, Transform};
+use crate::{
+    event::Event,
+    template::Template,
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Unable to read table {:?}: {}", path, source))]
+    ReadTable { path: PathBuf, source: csv::Error },
+    #[snafu(display("Table {:?} has no column {:?}", path, column))]
+    MissingColumn { path: PathBuf, column: String },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct EnrichTableConfig {
+    pub path: PathBuf,
+    pub key_column: String,
+    pub key: Template,
+    #[serde(default)]
+    pub columns: Vec<String>,
+    pub target: Option<String>,
+    #[serde(default = "default_reload_interval_secs")]
+    pub reload_interval_secs: u64,
+}
+
+fn default_reload_interval_secs() -> u64 {
+    60
+}
+
+inventory::submit! {
+    TransformDescription::new_without_default::<EnrichTableConfig>("enrich_table")
+}
+
+#[typetag::serde(name = "enrich_table")]
+impl TransformConfig for EnrichTableConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        let table = WatchedFile::load(
+            &self.path,
+            Duration::from_secs(self.reload_interval_secs),
+            |path| self.load(path),
+        )?;
+
+        Ok(Box::new(EnrichTable {
+            config: self.clone(),
+            table,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "enrich_table"
+    }
+}
+
+impl EnrichTableConfig {
+    fn load(&self, path: &Path) -> Result<Table, BuildError> {
+        Table::load(path, &self.key_column, &self.columns, &self.target)
+    }
+}
+
+/// The rows of the table by key, each holding the field names and values
+/// to add to matching events.
+#[derive(Debug, PartialEq)]
+struct Table {
+    rows: HashMap<String, Vec<(Atom, String)>>,
+}
+
+impl Table {
+    fn load(
+        path: &Path,
+        key_column: &str,
+        columns: &[String],
+        target: &Option<String>,
+    ) -> Result<Self, BuildError> {
+        let mut reader = csv::Reader::from_path(path).context(ReadTable { path })?;
+        let headers = reader.headers().context(ReadTable { path })?.clone();
+
+        let position = |column: &str| {
+            headers
+                .iter()
+                .position(|header| header == column)
+                .ok_or_else(|| BuildError::MissingColumn {
+                    path: path.into(),
+                    column: column.into(),
+                })
+        };
+
+        let key_index = position(key_column)?;
+        let selected = if columns.is_empty() {
+            (0..headers.len()).filter(|&i| i != key_index).collect()
+        } else {
+            columns
+                .iter()
+                .map(|column| position(column))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let names = selected
+            .iter()
+            .map(|&i| match target {
+                Some(target) => Atom::from(format!("{}.{}", target, &headers[i])),
+                None => Atom::from(&headers[i]),
+            })
+            .collect::<Vec<_>>();
+
+        let mut rows = HashMap::new();
+        for record in reader.records() {
+            let record = record.context(ReadTable { path })?;
+            let key = match record.get(key_index) {
+                Some(key) => key.to_string(),
+                None => continue,
+            };
+            let values = names
+                .iter()
+                .zip(&selected)
+                .filter_map(|(name, &i)| record.get(i).map(|value| (name.clone(), value.into())))
+                .collect();
+            rows.insert(key, values);
+        }
+
+        Ok(Self { rows })
+    }
+}
+
+pub struct EnrichTable {
+    config: EnrichTableConfig,
+    table: WatchedFile<Table>,
+}
+
+impl Transform for EnrichTable {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let config = &self.config;
+        let table = self.table.get(|path| config.load(path));
+
+        let key = match self.config.key.render_string(&event) {
+            Ok(key) => key,
+            Err(missing_keys) => {
+                debug!(
+                    message = "Keys do not exist on the event; not enriching.",
+                    ?missing_keys,
+                    rate_limit_secs = 30
+                );
+                return Some(event);
+            }
+        };
+
+        if let Some(values) = table.rows.get(&key) {
+            let log = event.as_mut_log();
+            for (name, value) in values {
+                log.insert_explicit(name.clone(), value.clone().into());
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnrichTableConfig;
+    use crate::{event::Event, topology::config::TransformConfig, transforms::Transform};
+    use std::{fs, path::Path};
+
+    const TABLE: &str = "\
+service,owner,team,cost_center
+api,alice,platform,100
+web,bob,frontend,200
+";
+
+    fn build(path: &Path, extra: &str) -> crate::Result<Box<dyn Transform>> {
+        let config = format!(
+            "path = {:?}\nkey_column = \"service\"\nkey = \"{{{{ service }}}}\"\n{}",
+            path, extra
+        );
+        toml::from_str::<EnrichTableConfig>(&config)
+            .unwrap()
+            .build()
+    }
+
+    fn request(service: &str) -> Event {
+        let mut event = Event::from("request");
+        event
+            .as_mut_log()
+            .insert_explicit("service".into(), service.into());
+        event
+    }
+
+    #[test]
+    fn enrich_table_copies_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("services.csv");
+        fs::write(&path, TABLE).unwrap();
+
+        let mut transform = build(&path, "").unwrap();
+        let event = transform.transform(request("api")).unwrap();
+        assert_eq!(event.as_log()[&"owner".into()], "alice".into());
+        assert_eq!(event.as_log()[&"team".into()], "platform".into());
+        assert_eq!(event.as_log()[&"cost_center".into()], "100".into());
+
+        let event = transform.transform(request("unknown")).unwrap();
+        assert!(event.as_log().get(&"owner".into()).is_none());
+    }
+
+    #[test]
+    fn enrich_table_selected_columns_into_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("services.csv");
+        fs::write(&path, TABLE).unwrap();
+
+        let mut transform = build(&path, "columns = [\"team\"]\ntarget = \"svc\"").unwrap();
+        let event = transform.transform(request("web")).unwrap();
+        assert_eq!(event.as_log()[&"svc.team".into()], "frontend".into());
+        assert!(event.as_log().get(&"svc.owner".into()).is_none());
+        assert!(event.as_log().get(&"team".into()).is_none());
+    }
+
+    #[test]
+    fn enrich_table_reloads_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("services.csv");
+        fs::write(&path, TABLE).unwrap();
+
+        let mut transform = build(&path, "reload_interval_secs = 0").unwrap();
+        let event = transform.transform(request("api")).unwrap();
+        assert_eq!(event.as_log()[&"owner".into()], "alice".into());
+
+        fs::write(&path, "service,owner\napi,carol-and-dave\n").unwrap();
+        let event = transform.transform(request("api")).unwrap();
+        assert_eq!(event.as_log()[&"owner".into()], "carol-and-dave".into());
+    }
+
+    #[test]
+    fn enrich_table_rejects_missing_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("services.csv");
+        fs::write(&path, TABLE).unwrap();
+
+        assert!(build(&path, "columns = [\"region\"]").is_err());
+        assert!(build(&dir.path().join("missing.csv"), "").is_err());
+    }
+}
@@ -0,0 +1,390 @@
+//! A minimal reader for the MaxMind DB format used by the GeoIP2 and
+//! GeoLite2 databases.
+//!
+//! https://maxmind.github.io/MaxMind-DB/
+
+use snafu::{ResultExt, Snafu};
+use std::{collections::BTreeMap, net::IpAddr, path::Path};
+
+const METADATA_START_MARKER: &[u8] = b"\xab\xcd\xefMaxMind.com";
+const DATA_SECTION_SEPARATOR_SIZE: usize = 16;
+/// How deeply pointers and containers may nest in the data section, the
+/// same limit as libmaxminddb. This also ends pointer cycles.
+const MAX_DATA_DEPTH: usize = 512;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unable to read database: {}", source))]
+    ReadDatabase { source: std::io::Error },
+    #[snafu(display("Invalid database: {}", reason))]
+    InvalidDatabase { reason: &'static str },
+}
+
+fn invalid<T>(reason: &'static str) -> Result<T, Error> {
+    Err(Error::InvalidDatabase { reason })
+}
+
+/// A decoded data section value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Double(f64),
+    Float(f32),
+    Bytes(Vec<u8>),
+    Uint(u64),
+    Uint128(u128),
+    Int(i32),
+    Bool(bool),
+    Map(BTreeMap<String, Value>),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Follows `path` through nested maps.
+    pub fn get(&self, path: &[&str]) -> Option<&Value> {
+        path.iter().try_fold(self, |value, key| match value {
+            Value::Map(map) => map.get(*key),
+            _ => None,
+        })
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Double(f) => Some(*f),
+            Value::Float(f) => Some(f64::from(*f)),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Uint(u) => Some(*u),
+            Value::Int(i) if *i >= 0 => Some(*i as u64),
+            _ => None,
+        }
+    }
+}
+
+pub struct Reader {
+    buf: Vec<u8>,
+    node_count: usize,
+    record_size: usize,
+    ip_version: u64,
+    search_tree_size: usize,
+    data_end: usize,
+    ipv4_start: usize,
+    database_type: String,
+}
+
+impl Reader {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let buf = std::fs::read(path).context(ReadDatabase)?;
+        Self::from_bytes(buf)
+    }
+
+    pub fn from_bytes(buf: Vec<u8>) -> Result<Self, Error> {
+        let data_end = match find_last(&buf, METADATA_START_MARKER) {
+            Some(marker) => marker,
+            None => return invalid("metadata marker not found"),
+        };
+        let metadata = Decoder {
+            section: &buf[data_end + METADATA_START_MARKER.len()..],
+        }
+        .decode(0)?;
+
+        let field = |name| metadata.get(&[name]).and_then(Value::as_u64);
+        let node_count = match field("node_count") {
+            Some(count) => count as usize,
+            None => return invalid("metadata is missing node_count"),
+        };
+        let record_size = match field("record_size") {
+            Some(size @ 24) | Some(size @ 28) | Some(size @ 32) => size as usize,
+            _ => return invalid("unsupported record_size"),
+        };
+        let ip_version = match field("ip_version") {
+            Some(version @ 4) | Some(version @ 6) => version,
+            _ => return invalid("unsupported ip_version"),
+        };
+        let database_type = metadata
+            .get(&["database_type"])
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned();
+
+        let search_tree_size = match node_count.checked_mul(record_size / 4) {
+            Some(size) if size + DATA_SECTION_SEPARATOR_SIZE <= data_end => size,
+            _ => return invalid("search tree is larger than the database"),
+        };
+
+        let mut reader = Self {
+            buf,
+            node_count,
+            record_size,
+            ip_version,
+            search_tree_size,
+            data_end,
+            ipv4_start: 0,
+            database_type,
+        };
+
+        // IPv4 addresses live under ::/96 in IPv6 databases
+        if ip_version == 6 {
+            let mut node = 0;
+            for _ in 0..96 {
+                if node >= node_count {
+                    break;
+                }
+                node = reader.read_record(node, 0)?;
+            }
+            reader.ipv4_start = node;
+        }
+
+        Ok(reader)
+    }
+
+    pub fn database_type(&self) -> &str {
+        &self.database_type
+    }
+
+    /// Looks up the record for `ip`, or `None` if its network isn't in the
+    /// database.
+    pub fn lookup(&self, ip: IpAddr) -> Result<Option<Value>, Error> {
+        let (bytes, mut node) = match ip {
+            IpAddr::V4(ip) => (ip.octets().to_vec(), self.ipv4_start),
+            IpAddr::V6(_) if self.ip_version == 4 => return Ok(None),
+            IpAddr::V6(ip) => (ip.octets().to_vec(), 0),
+        };
+
+        for i in 0..bytes.len() * 8 {
+            if node >= self.node_count {
+                break;
+            }
+            let bit = (bytes[i / 8] >> (7 - i % 8)) & 1;
+            node = self.read_record(node, bit)?;
+        }
+
+        if node == self.node_count {
+            return Ok(None);
+        }
+        if node < self.node_count {
+            return invalid("search tree is deeper than the address");
+        }
+
+        let offset = match (node - self.node_count).checked_sub(DATA_SECTION_SEPARATOR_SIZE) {
+            Some(offset) => offset,
+            None => return invalid("search tree record points into the data section separator"),
+        };
+        let data_start = self.search_tree_size + DATA_SECTION_SEPARATOR_SIZE;
+        let decoder = Decoder {
+            section: &self.buf[data_start..self.data_end],
+        };
+        Ok(Some(decoder.decode(offset)?))
+    }
+
+    fn read_record(&self, node: usize, bit: u8) -> Result<usize, Error> {
+        let node_size = self.record_size / 4;
+        let offset = node * node_size;
+        let bytes = match self.buf[..self.search_tree_size].get(offset..offset + node_size) {
+            Some(bytes) => bytes,
+            None => return invalid("search tree node out of bounds"),
+        };
+
+        let record = match (self.record_size, bit) {
+            (24, 0) => be_uint(&bytes[0..3]),
+            (24, _) => be_uint(&bytes[3..6]),
+            (28, 0) => (u64::from(bytes[3] >> 4) << 24) | be_uint(&bytes[0..3]),
+            (28, _) => (u64::from(bytes[3] & 0x0f) << 24) | be_uint(&bytes[4..7]),
+            (_, 0) => be_uint(&bytes[0..4]),
+            (_, _) => be_uint(&bytes[4..8]),
+        };
+        Ok(record as usize)
+    }
+}
+
+fn find_last(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
+
+fn be_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |value, byte| (value << 8) | u64::from(*byte))
+}
+
+/// Decodes values from a section of the database. Offsets, including
+/// pointers, are relative to the start of the section and may not leave it.
+struct Decoder<'a> {
+    section: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], Error> {
+        match offset
+            .checked_add(len)
+            .and_then(|end| self.section.get(offset..end))
+        {
+            Some(bytes) => Ok(bytes),
+            None => invalid("data section value out of bounds"),
+        }
+    }
+
+    /// Decodes the value at `offset`.
+    fn decode(&self, offset: usize) -> Result<Value, Error> {
+        Ok(self.decode_nested(offset, 0)?.0)
+    }
+
+    /// Decodes the value at `offset` found `depth` pointers and containers
+    /// deep, returning it and the offset just past it.
+    fn decode_nested(&self, offset: usize, depth: usize) -> Result<(Value, usize), Error> {
+        if depth > MAX_DATA_DEPTH {
+            return invalid("data section is nested too deeply");
+        }
+
+        let ctrl = self.bytes(offset, 1)?[0];
+        let mut offset = offset + 1;
+
+        let mut kind = ctrl >> 5;
+        if kind == 1 {
+            let (pointer, next) = self.pointer(ctrl, offset)?;
+            let (value, _) = self.decode_nested(pointer, depth + 1)?;
+            return Ok((value, next));
+        }
+        if kind == 0 {
+            kind = 7 + self.bytes(offset, 1)?[0];
+            offset += 1;
+        }
+
+        let (size, offset) = self.size(ctrl, offset)?;
+        let end = offset + size;
+        let value = match kind {
+            2 => match String::from_utf8(self.bytes(offset, size)?.to_vec()) {
+                Ok(s) => Value::String(s),
+                Err(_) => return invalid("string is not valid utf-8"),
+            },
+            3 if size == 8 => Value::Double(f64::from_bits(be_uint(self.bytes(offset, 8)?))),
+            4 => Value::Bytes(self.bytes(offset, size)?.to_vec()),
+            5 | 6 | 9 if size <= 8 => Value::Uint(be_uint(self.bytes(offset, size)?)),
+            7 => {
+                let mut map = BTreeMap::new();
+                let mut next = offset;
+                for _ in 0..size {
+                    let (key, after_key) = self.decode_nested(next, depth + 1)?;
+                    let key = match key {
+                        Value::String(key) => key,
+                        _ => return invalid("map key is not a string"),
+                    };
+                    let (value, after_value) = self.decode_nested(after_key, depth + 1)?;
+                    map.insert(key, value);
+                    next = after_value;
+                }
+                return Ok((Value::Map(map), next));
+            }
+            8 if size <= 4 => {
+                let unsigned = be_uint(self.bytes(offset, size)?) as u32;
+                Value::Int(unsigned as i32)
+            }
+            10 if size <= 16 => Value::Uint128(
+                self.bytes(offset, size)?
+                    .iter()
+                    .fold(0, |value, byte| (value << 8) | u128::from(*byte)),
+            ),
+            11 => {
+                // Every element takes at least a byte, so a corrupt size
+                // can't make this allocate more than the section
+                let mut array = Vec::with_capacity(size.min(self.section.len()));
+                let mut next = offset;
+                for _ in 0..size {
+                    let (value, after) = self.decode_nested(next, depth + 1)?;
+                    array.push(value);
+                    next = after;
+                }
+                return Ok((Value::Array(array), next));
+            }
+            // Booleans keep their value in the size bits
+            14 => return Ok((Value::Bool(size != 0), offset)),
+            15 if size == 4 => Value::Float(f32::from_bits(be_uint(self.bytes(offset, 4)?) as u32)),
+            _ => return invalid("unsupported data type"),
+        };
+        Ok((value, end))
+    }
+
+    fn pointer(&self, ctrl: u8, offset: usize) -> Result<(usize, usize), Error> {
+        let size = usize::from((ctrl >> 3) & 0x3) + 1;
+        let bytes = self.bytes(offset, size)?;
+        let high = u64::from(ctrl & 0x7);
+        let pointer = match size {
+            1 => (high << 8) | be_uint(bytes),
+            2 => ((high << 16) | be_uint(bytes)) + 2048,
+            3 => ((high << 24) | be_uint(bytes)) + 526_336,
+            _ => be_uint(bytes),
+        };
+        Ok((pointer as usize, offset + size))
+    }
+
+    fn size(&self, ctrl: u8, offset: usize) -> Result<(usize, usize), Error> {
+        let size = usize::from(ctrl & 0x1f);
+        Ok(match size {
+            29 => (29 + be_uint(self.bytes(offset, 1)?) as usize, offset + 1),
+            30 => (285 + be_uint(self.bytes(offset, 2)?) as usize, offset + 2),
+            31 => (
+                65_821 + be_uint(self.bytes(offset, 3)?) as usize,
+                offset + 3,
+            ),
+            _ => (size, offset),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decoder, Error, Reader, Value};
+
+    fn decode(section: &[u8], offset: usize) -> Result<Value, Error> {
+        Decoder { section }.decode(offset)
+    }
+
+    #[test]
+    fn mmdb_follows_pointers() {
+        // "hi" followed by a pointer to it
+        let section = [0x42, b'h', b'i', 0x20, 0x00];
+        assert_eq!(decode(&section, 3).unwrap(), Value::String("hi".into()));
+    }
+
+    #[test]
+    fn mmdb_rejects_pointer_cycles() {
+        // A pointer to itself, and a map whose value points back to the map
+        assert!(decode(&[0x20, 0x00], 0).is_err());
+        assert!(decode(&[0xe1, 0x41, b'a', 0x20, 0x00], 0).is_err());
+    }
+
+    #[test]
+    fn mmdb_rejects_deeply_nested_arrays() {
+        // Arrays of one array, nested deeper than the limit
+        let section = std::iter::repeat(&[0x01, 0x04][..])
+            .take(1000)
+            .flatten()
+            .cloned()
+            .collect::<Vec<u8>>();
+        assert!(decode(&section, 0).is_err());
+    }
+
+    #[test]
+    fn mmdb_rejects_out_of_bounds_offsets() {
+        // A pointer past the end of the section
+        assert!(decode(&[0x20, 0x10], 0).is_err());
+        // A string longer than the section
+        assert!(decode(&[0x45, b'h', b'i'], 0).is_err());
+        // An array claiming more elements than fit
+        assert!(decode(&[0x1f, 0x04, 0xff, 0xff, 0xff], 0).is_err());
+        assert!(Reader::from_bytes(b"not a database".to_vec()).is_err());
+    }
+}
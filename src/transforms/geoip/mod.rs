@@ -0,0 +1,307 @@
+use super::{util::WatchedFile, Transform};
+use crate::{
+    event::{Event, LogEvent, ValueKind},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use mmdb::{Reader, Value};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use string_cache::DefaultAtom as Atom;
+
+mod mmdb;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Unable to open GeoIP database {:?}: {}", path, source))]
+    OpenDatabase { path: PathBuf, source: mmdb::Error },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct GeoipConfig {
+    pub source: Atom,
+    pub database: PathBuf,
+    pub asn_database: Option<PathBuf>,
+    #[serde(default = "default_target")]
+    pub target: String,
+    #[serde(default = "default_reload_interval_secs")]
+    pub reload_interval_secs: u64,
+}
+
+fn default_target() -> String {
+    "geoip".into()
+}
+
+fn default_reload_interval_secs() -> u64 {
+    60
+}
+
+inventory::submit! {
+    TransformDescription::new_without_default::<GeoipConfig>("geoip")
+}
+
+#[typetag::serde(name = "geoip")]
+impl TransformConfig for GeoipConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        let reload_interval = Duration::from_secs(self.reload_interval_secs);
+        let database = open(&self.database, reload_interval)?;
+        let asn_database = match &self.asn_database {
+            Some(path) => Some(open(path, reload_interval)?),
+            None => None,
+        };
+
+        Ok(Box::new(Geoip {
+            source: self.source.clone(),
+            target: self.target.clone(),
+            database,
+            asn_database,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "geoip"
+    }
+}
+
+/// Opens a MaxMind database, which is reopened when the file on disk
+/// changes.
+fn open(path: &Path, reload_interval: Duration) -> Result<WatchedFile<Reader>, BuildError> {
+    WatchedFile::load(path, reload_interval, Reader::open).context(OpenDatabase { path })
+}
+
+pub struct Geoip {
+    source: Atom,
+    target: String,
+    database: WatchedFile<Reader>,
+    asn_database: Option<WatchedFile<Reader>>,
+}
+
+impl Geoip {
+    fn insert(&self, log: &mut LogEvent, name: &str, value: impl Into<ValueKind>) {
+        log.insert_explicit(format!("{}.{}", self.target, name).into(), value.into());
+    }
+
+    fn enrich(&mut self, log: &mut LogEvent, ip: IpAddr) {
+        let city = self.database.get(Reader::open).lookup(ip);
+        if let Some(city) = self.record(city) {
+            let string = |path: &[&str]| city.get(path).and_then(Value::as_str).map(str::to_owned);
+            let float = |path: &[&str]| city.get(path).and_then(Value::as_f64);
+
+            let fields = [
+                ("country_code", string(&["country", "iso_code"])),
+                ("country_name", string(&["country", "names", "en"])),
+                ("city_name", string(&["city", "names", "en"])),
+                ("continent_code", string(&["continent", "code"])),
+                ("postal_code", string(&["postal", "code"])),
+                ("timezone", string(&["location", "time_zone"])),
+            ];
+            for (name, value) in fields.iter().cloned() {
+                if let Some(value) = value {
+                    self.insert(log, name, value);
+                }
+            }
+            if let Some(latitude) = float(&["location", "latitude"]) {
+                self.insert(log, "latitude", latitude);
+            }
+            if let Some(longitude) = float(&["location", "longitude"]) {
+                self.insert(log, "longitude", longitude);
+            }
+        }
+
+        let asn = match &mut self.asn_database {
+            Some(database) => database.get(Reader::open).lookup(ip),
+            None => Ok(None),
+        };
+        if let Some(asn) = self.record(asn) {
+            if let Some(number) = asn
+                .get(&["autonomous_system_number"])
+                .and_then(Value::as_u64)
+            {
+                self.insert(log, "asn", number as i64);
+            }
+            if let Some(organization) = asn
+                .get(&["autonomous_system_organization"])
+                .and_then(Value::as_str)
+            {
+                self.insert(log, "organization", organization);
+            }
+        }
+    }
+
+    fn record(&self, lookup: Result<Option<Value>, mmdb::Error>) -> Option<Value> {
+        lookup
+            .map_err(|error| {
+                error!(
+                    message = "Unable to look up IP address in GeoIP database.",
+                    %error,
+                    rate_limit_secs = 30
+                )
+            })
+            .ok()
+            .and_then(|record| record)
+    }
+}
+
+impl Transform for Geoip {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let ip = event
+            .as_log()
+            .get(&self.source)
+            .map(|value| value.to_string_lossy());
+
+        match ip.as_ref().map(|ip| ip.trim().parse::<IpAddr>()) {
+            Some(Ok(ip)) => self.enrich(event.as_mut_log(), ip),
+            Some(Err(error)) => debug!(
+                message = "Field is not a valid IP address.",
+                field = self.source.as_ref(),
+                %error,
+                rate_limit_secs = 30
+            ),
+            None => debug!(
+                message = "Field does not exist.",
+                field = self.source.as_ref(),
+            ),
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        mmdb::{Reader, Value},
+        GeoipConfig,
+    };
+    use crate::{
+        event::Event, test_util::temp_dir, topology::config::TransformConfig,
+        transforms::util::WatchedFile,
+    };
+    use std::{fs, time::Duration};
+
+    // MaxMind's published test databases, see `make -C tests/data mmdb`
+    const DATABASE: &str = "tests/data/GeoIP2-City-Test.mmdb";
+    const ASN_DATABASE: &str = "tests/data/GeoLite2-ASN-Test.mmdb";
+
+    fn do_transform(ip: &str, config: &str) -> Event {
+        let mut event = Event::from("GET /");
+        event.as_mut_log().insert_explicit("ip".into(), ip.into());
+        let mut geoip = toml::from_str::<GeoipConfig>(config)
+            .unwrap()
+            .build()
+            .unwrap();
+        geoip.transform(event).unwrap()
+    }
+
+    #[test]
+    fn geoip_lookup_city() {
+        let config = format!("source = \"ip\"\ndatabase = {:?}", DATABASE);
+        let event = do_transform("2.125.160.216", &config);
+        let log = event.as_log();
+
+        assert_eq!(log[&"geoip.country_code".into()], "GB".into());
+        assert_eq!(log[&"geoip.country_name".into()], "United Kingdom".into());
+        assert_eq!(log[&"geoip.city_name".into()], "Boxford".into());
+        assert_eq!(log[&"geoip.continent_code".into()], "EU".into());
+        assert_eq!(log[&"geoip.postal_code".into()], "OX1".into());
+        assert_eq!(log[&"geoip.latitude".into()], 51.75.into());
+        assert_eq!(log[&"geoip.longitude".into()], (-1.25).into());
+        assert_eq!(log[&"geoip.timezone".into()], "Europe/London".into());
+        assert!(log.get(&"geoip.asn".into()).is_none());
+    }
+
+    #[test]
+    fn geoip_lookup_asn_into_target() {
+        let config = format!(
+            "source = \"ip\"\ndatabase = {:?}\nasn_database = {:?}\ntarget = \"geo\"",
+            DATABASE, ASN_DATABASE
+        );
+        let event = do_transform("1.128.0.0", &config);
+        let log = event.as_log();
+
+        assert_eq!(log[&"geo.asn".into()], 1221.into());
+        assert_eq!(log[&"geo.organization".into()], "Telstra Pty Ltd".into());
+    }
+
+    #[test]
+    fn geoip_lookup_without_optional_fields() {
+        let config = format!("source = \"ip\"\ndatabase = {:?}", DATABASE);
+        let event = do_transform("67.43.156.9", &config);
+        let log = event.as_log();
+
+        assert_eq!(log[&"geoip.country_code".into()], "BT".into());
+        assert_eq!(log[&"geoip.continent_code".into()], "AS".into());
+        assert_eq!(log[&"geoip.timezone".into()], "Asia/Thimphu".into());
+        assert_eq!(log[&"geoip.latitude".into()], 27.5.into());
+        assert_eq!(log[&"geoip.longitude".into()], 90.5.into());
+        assert!(log.get(&"geoip.city_name".into()).is_none());
+        assert!(log.get(&"geoip.postal_code".into()).is_none());
+    }
+
+    #[test]
+    fn geoip_database_reload() {
+        let path = temp_dir().with_extension("mmdb");
+        fs::copy(DATABASE, &path).unwrap();
+        let mut database = WatchedFile::load(&path, Duration::from_secs(0), Reader::open).unwrap();
+
+        let city = |database: &mut WatchedFile<Reader>| {
+            database
+                .get(Reader::open)
+                .lookup("2.125.160.216".parse().unwrap())
+                .unwrap()
+                .and_then(|record| record.get(&["city", "names", "en"]).cloned())
+        };
+        assert_eq!(city(&mut database), Some(Value::String("Boxford".into())));
+
+        // An unreadable file keeps the current database
+        fs::write(&path, b"not a database").unwrap();
+        assert_eq!(city(&mut database), Some(Value::String("Boxford".into())));
+
+        fs::copy(ASN_DATABASE, &path).unwrap();
+        let asn = database
+            .get(Reader::open)
+            .lookup("1.128.0.0".parse().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            asn.get(&["autonomous_system_number"])
+                .and_then(Value::as_u64),
+            Some(1221)
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn geoip_passes_through_unknown_and_invalid() {
+        let config = format!("source = \"ip\"\ndatabase = {:?}", DATABASE);
+
+        for ip in &["10.0.0.1", "not an ip"] {
+            let event = do_transform(ip, &config);
+            assert_eq!(event.as_log()[&"ip".into()], (*ip).into());
+            assert!(event.as_log().keys().all(|key| !key.starts_with("geoip.")));
+        }
+    }
+
+    #[test]
+    fn geoip_missing_database() {
+        let config = toml::from_str::<GeoipConfig>(
+            "source = \"ip\"\ndatabase = \"tests/data/does-not-exist.mmdb\"",
+        )
+        .unwrap();
+        assert!(config.build().is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use super::Transform;
+use crate::{
+    event::{self, Event, ValueKind},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+    types::{parse_conversion_map, Conversion},
+};
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::collections::HashMap;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("`value_split` must not be empty"))]
+    EmptyValueSplit,
+    #[snafu(display("`field_split` must not be empty"))]
+    EmptyFieldSplit,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyValueParserConfig {
+    pub field: Option<Atom>,
+    pub drop_field: bool,
+    pub field_split: Option<String>,
+    pub value_split: String,
+    pub prefix: Option<String>,
+    pub types: HashMap<Atom, String>,
+}
+
+impl Default for KeyValueParserConfig {
+    fn default() -> Self {
+        Self {
+            field: None,
+            drop_field: true,
+            field_split: None,
+            value_split: "=".into(),
+            prefix: None,
+            types: HashMap::new(),
+        }
+    }
+}
+
+inventory::submit! {
+    TransformDescription::new::<KeyValueParserConfig>("key_value_parser")
+}
+
+#[typetag::serde(name = "key_value_parser")]
+impl TransformConfig for KeyValueParserConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        let field = self.field.as_ref().unwrap_or(&event::MESSAGE);
+
+        if self.value_split.is_empty() {
+            return Err(Box::new(BuildError::EmptyValueSplit));
+        }
+        let field_split = match &self.field_split {
+            None => Separator::Whitespace,
+            Some(s) if s.is_empty() => return Err(Box::new(BuildError::EmptyFieldSplit)),
+            Some(s) => Separator::Literal(s.clone()),
+        };
+
+        let types = parse_conversion_map(&self.types)?;
+
+        Ok(Box::new(KeyValueParser {
+            field: field.clone(),
+            drop_field: self.drop_field,
+            field_split,
+            value_split: self.value_split.clone(),
+            prefix: self.prefix.clone().unwrap_or_default(),
+            types,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "key_value_parser"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Separator {
+    /// Any run of whitespace, as in logfmt.
+    Whitespace,
+    Literal(String),
+}
+
+impl Separator {
+    /// Returns the length of the separator at the start of `s`, if any.
+    fn match_len(&self, s: &str) -> Option<usize> {
+        match self {
+            Separator::Whitespace => {
+                let len = s.len() - s.trim_start().len();
+                if len > 0 {
+                    Some(len)
+                } else {
+                    None
+                }
+            }
+            Separator::Literal(sep) => {
+                if s.starts_with(sep.as_str()) {
+                    Some(sep.len())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+pub struct KeyValueParser {
+    field: Atom,
+    drop_field: bool,
+    field_split: Separator,
+    value_split: String,
+    prefix: String,
+    types: HashMap<Atom, Conversion>,
+}
+
+impl Transform for KeyValueParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let value = if self.drop_field {
+            event.as_mut_log().remove(&self.field)
+        } else {
+            event.as_log().get(&self.field).cloned()
+        };
+
+        let value = match value {
+            Some(value) => value.to_string_lossy(),
+            None => {
+                debug!(
+                    message = "Field does not exist.",
+                    field = self.field.as_ref(),
+                );
+                return Some(event);
+            }
+        };
+
+        for (key, value) in parse(&value, &self.field_split, &self.value_split) {
+            let name = Atom::from(format!("{}{}", self.prefix, key));
+            let value = match value {
+                // A key without a value is a flag, as in logfmt.
+                None => ValueKind::Boolean(true),
+                Some(value) => {
+                    let conversion = self.types.get(&name).unwrap_or(&Conversion::Bytes);
+                    match conversion.convert(value.into()) {
+                        Ok(value) => value,
+                        Err(error) => {
+                            debug!(
+                                message = "Could not convert types.",
+                                name = &name[..],
+                                %error,
+                                rate_limit_secs = 30
+                            );
+                            continue;
+                        }
+                    }
+                }
+            };
+            event.as_mut_log().insert_explicit(name, value);
+        }
+
+        Some(event)
+    }
+}
+
+/// Split `input` into key/value pairs. Values may be wrapped in double
+/// quotes, in which case they can contain separators and `\"` or `\\`
+/// escapes. Keys without a `value_split` are returned with no value.
+fn parse(input: &str, field_split: &Separator, value_split: &str) -> Vec<(String, Option<String>)> {
+    let mut pairs = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        if let Some(len) = field_split.match_len(rest) {
+            rest = &rest[len..];
+            continue;
+        }
+
+        // Scan the key, up to either the value separator or the next pair.
+        let mut key_end = rest.len();
+        let mut has_value = false;
+        for (idx, _) in rest.char_indices() {
+            if rest[idx..].starts_with(value_split) {
+                key_end = idx;
+                has_value = true;
+                break;
+            }
+            if field_split.match_len(&rest[idx..]).is_some() {
+                key_end = idx;
+                break;
+            }
+        }
+
+        let key = rest[..key_end].trim().to_string();
+        rest = &rest[key_end..];
+
+        let value = if has_value {
+            rest = &rest[value_split.len()..];
+            let (value, remainder) = parse_value(rest, field_split);
+            rest = remainder;
+            Some(value)
+        } else {
+            None
+        };
+
+        if !key.is_empty() {
+            pairs.push((key, value));
+        }
+    }
+
+    pairs
+}
+
+fn parse_value<'a>(input: &'a str, field_split: &Separator) -> (String, &'a str) {
+    // Allow whitespace around the value separator unless whitespace is what
+    // separates pairs, e.g. `a: "b", c: "d"`.
+    let input = match field_split {
+        Separator::Literal(sep) if !sep.trim().is_empty() => input.trim_start(),
+        _ => input,
+    };
+
+    if input.starts_with('"') {
+        let mut value = String::new();
+        let mut chars = input.char_indices().skip(1);
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => return (value, &input[idx + 1..]),
+                '\\' => match chars.next() {
+                    Some((_, c @ '"')) | Some((_, c @ '\\')) => value.push(c),
+                    Some((_, c)) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => value.push('\\'),
+                },
+                c => value.push(c),
+            }
+        }
+        // Unterminated quote; take everything that is left.
+        (value, "")
+    } else {
+        let end = input
+            .char_indices()
+            .find(|(idx, _)| field_split.match_len(&input[*idx..]).is_some())
+            .map(|(idx, _)| idx)
+            .unwrap_or_else(|| input.len());
+        (input[..end].trim().to_string(), &input[end..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, KeyValueParserConfig, Separator};
+    use crate::event::{LogEvent, ValueKind};
+    use crate::{topology::config::TransformConfig, Event};
+
+    fn pairs(
+        input: &str,
+        field_split: Separator,
+        value_split: &str,
+    ) -> Vec<(String, Option<String>)> {
+        parse(input, &field_split, value_split)
+    }
+
+    fn kv(key: &str, value: Option<&str>) -> (String, Option<String>) {
+        (key.into(), value.map(Into::into))
+    }
+
+    fn do_transform(event: &str, config: &str) -> LogEvent {
+        let event = Event::from(event);
+        let mut parser = toml::from_str::<KeyValueParserConfig>(config)
+            .unwrap()
+            .build()
+            .unwrap();
+        parser.transform(event).unwrap().into_log()
+    }
+
+    #[test]
+    fn key_value_parse_logfmt() {
+        assert_eq!(
+            pairs(
+                r#"level=info msg="hello \"world\"" ok  path=/a\b"#,
+                Separator::Whitespace,
+                "="
+            ),
+            vec![
+                kv("level", Some("info")),
+                kv("msg", Some(r#"hello "world""#)),
+                kv("ok", None),
+                kv("path", Some(r"/a\b")),
+            ]
+        );
+    }
+
+    #[test]
+    fn key_value_parse_custom_separators() {
+        assert_eq!(
+            pairs(
+                r#"a: 1, b: "x, y", c:"#,
+                Separator::Literal(",".into()),
+                ":"
+            ),
+            vec![kv("a", Some("1")), kv("b", Some("x, y")), kv("c", Some(""))]
+        );
+    }
+
+    #[test]
+    fn key_value_parse_handles_empty_and_unterminated() {
+        assert_eq!(pairs("", Separator::Whitespace, "="), vec![]);
+        assert_eq!(pairs("  =x  ", Separator::Whitespace, "="), vec![]);
+        assert_eq!(
+            pairs(r#"a="unterminated value"#, Separator::Whitespace, "="),
+            vec![kv("a", Some("unterminated value"))]
+        );
+    }
+
+    #[test]
+    fn key_value_parser_adds_fields_and_drops_source() {
+        let log = do_transform("status=200 duration=1.5", "");
+
+        assert_eq!(log[&"status".into()], "200".into());
+        assert_eq!(log[&"duration".into()], "1.5".into());
+        assert!(log.get(&"message".into()).is_none());
+    }
+
+    #[test]
+    fn key_value_parser_keeps_overwritten_source() {
+        let log = do_transform("level=info message=hi", "");
+
+        assert_eq!(log[&"level".into()], "info".into());
+        assert_eq!(log[&"message".into()], "hi".into());
+    }
+
+    #[test]
+    fn key_value_parser_prefix_and_types() {
+        let log = do_transform(
+            "status=200 duration=1.5 cached",
+            r#"
+            drop_field = false
+            prefix = "kv."
+            types."kv.status" = "int"
+            types."kv.duration" = "float"
+            "#,
+        );
+
+        assert_eq!(log[&"kv.status".into()], ValueKind::Integer(200));
+        assert_eq!(log[&"kv.duration".into()], ValueKind::Float(1.5));
+        assert_eq!(log[&"kv.cached".into()], ValueKind::Boolean(true));
+        assert!(log.get(&"message".into()).is_some());
+    }
+}
@@ -1,6 +1,6 @@
 use super::Transform;
 use crate::{
-    event::metric::Metric,
+    event::metric::{Metric, MetricKind},
     event::{self, ValueKind},
     template::Template,
     topology::config::{DataType, TransformConfig, TransformDescription},
@@ -167,6 +167,7 @@ fn to_metric(config: &MetricConfig, event: &Event) -> Result<Metric, TransformEr
                 val,
                 timestamp,
                 tags,
+                kind: MetricKind::Incremental,
             })
         }
         MetricConfig::Histogram(hist) => {
@@ -187,6 +188,7 @@ fn to_metric(config: &MetricConfig, event: &Event) -> Result<Metric, TransformEr
                 sample_rate: 1,
                 timestamp,
                 tags,
+                kind: MetricKind::Incremental,
             })
         }
         MetricConfig::Gauge(gauge) => {
@@ -204,9 +206,9 @@ fn to_metric(config: &MetricConfig, event: &Event) -> Result<Metric, TransformEr
             Ok(Metric::Gauge {
                 name,
                 val,
-                direction: None,
                 timestamp,
                 tags,
+                kind: MetricKind::Absolute,
             })
         }
         MetricConfig::Set(set) => {
@@ -223,6 +225,7 @@ fn to_metric(config: &MetricConfig, event: &Event) -> Result<Metric, TransformEr
                 val,
                 timestamp,
                 tags,
+                kind: MetricKind::Incremental,
             })
         }
     }
@@ -258,7 +261,7 @@ impl Transform for LogToMetric {
 mod tests {
     use super::{LogToMetric, LogToMetricConfig};
     use crate::{
-        event::{self, Metric},
+        event::{self, Metric, MetricKind},
         transforms::Transform,
         Event,
     };
@@ -301,6 +304,7 @@ mod tests {
                 val: 1.0,
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
     }
@@ -343,6 +347,7 @@ mod tests {
                     .into_iter()
                     .collect(),
                 ),
+                kind: MetricKind::Incremental,
             }
         );
     }
@@ -369,6 +374,7 @@ mod tests {
                 val: 1.0,
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
     }
@@ -414,6 +420,7 @@ mod tests {
                 val: 33.99,
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
     }
@@ -438,9 +445,9 @@ mod tests {
             Metric::Gauge {
                 name: "memory_rss_bytes".into(),
                 val: 123.0,
-                direction: None,
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Absolute,
             }
         );
     }
@@ -518,6 +525,7 @@ mod tests {
                 val: 1.0,
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
         assert_eq!(
@@ -527,6 +535,7 @@ mod tests {
                 val: 1.0,
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
     }
@@ -579,6 +588,7 @@ mod tests {
                 val: 1.0,
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
         assert_eq!(
@@ -588,6 +598,7 @@ mod tests {
                 val: "42".into(),
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
     }
@@ -614,6 +625,7 @@ mod tests {
                 val: "1.2.3.4".into(),
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
     }
@@ -640,6 +652,7 @@ mod tests {
                 sample_rate: 1,
                 timestamp: Some(ts()),
                 tags: None,
+                kind: MetricKind::Incremental,
             }
         );
     }
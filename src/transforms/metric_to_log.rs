@@ -0,0 +1,317 @@
+use super::Transform;
+use crate::{
+    event::{self, Event, LogEvent, Metric, MetricKind},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MetricToLogConfig {
+    pub host_tag: Option<String>,
+}
+
+inventory::submit! {
+    TransformDescription::new::<MetricToLogConfig>("metric_to_log")
+}
+
+#[typetag::serde(name = "metric_to_log")]
+impl TransformConfig for MetricToLogConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        Ok(Box::new(MetricToLog::new(self.host_tag.clone())))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "metric_to_log"
+    }
+}
+
+pub struct MetricToLog {
+    host_tag: Option<String>,
+}
+
+impl MetricToLog {
+    pub fn new(host_tag: Option<String>) -> Self {
+        Self { host_tag }
+    }
+}
+
+impl Transform for MetricToLog {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        let metric = event.into_metric();
+        let mut log = Event::new_empty_log().into_log();
+
+        let name = |log: &mut LogEvent, name: &str, kind: &str| {
+            log.insert_explicit(Atom::from("name"), name.into());
+            log.insert_explicit(Atom::from("type"), kind.into());
+        };
+
+        let (timestamp, tags, kind) = match metric {
+            Metric::Counter {
+                name: n,
+                val,
+                timestamp,
+                tags,
+                kind,
+            } => {
+                name(&mut log, &n, "counter");
+                log.insert_explicit(Atom::from("val"), val.into());
+                (timestamp, tags, kind)
+            }
+            Metric::Histogram {
+                name: n,
+                val,
+                sample_rate,
+                timestamp,
+                tags,
+                kind,
+            } => {
+                name(&mut log, &n, "histogram");
+                log.insert_explicit(Atom::from("val"), val.into());
+                log.insert_explicit(Atom::from("sample_rate"), i64::from(sample_rate).into());
+                (timestamp, tags, kind)
+            }
+            Metric::Gauge {
+                name: n,
+                val,
+                timestamp,
+                tags,
+                kind,
+            } => {
+                name(&mut log, &n, "gauge");
+                log.insert_explicit(Atom::from("val"), val.into());
+                (timestamp, tags, kind)
+            }
+            Metric::Set {
+                name: n,
+                val,
+                timestamp,
+                tags,
+                kind,
+            } => {
+                name(&mut log, &n, "set");
+                log.insert_explicit(Atom::from("val"), val.into());
+                (timestamp, tags, kind)
+            }
+            Metric::AggregatedHistogram {
+                name: n,
+                buckets,
+                counts,
+                count,
+                sum,
+                timestamp,
+                tags,
+                kind,
+            } => {
+                name(&mut log, &n, "aggregated_histogram");
+                for (i, (bucket, bucket_count)) in buckets.into_iter().zip(counts).enumerate() {
+                    log.insert_explicit(format!("buckets[{}]", i).into(), bucket.into());
+                    log.insert_explicit(
+                        format!("counts[{}]", i).into(),
+                        (bucket_count as i64).into(),
+                    );
+                }
+                log.insert_explicit(Atom::from("count"), (count as i64).into());
+                log.insert_explicit(Atom::from("sum"), sum.into());
+                (timestamp, tags, kind)
+            }
+            Metric::AggregatedSummary {
+                name: n,
+                quantiles,
+                values,
+                count,
+                sum,
+                timestamp,
+                tags,
+                kind,
+            } => {
+                name(&mut log, &n, "aggregated_summary");
+                for (i, (quantile, value)) in quantiles.into_iter().zip(values).enumerate() {
+                    log.insert_explicit(format!("quantiles[{}]", i).into(), quantile.into());
+                    log.insert_explicit(format!("values[{}]", i).into(), value.into());
+                }
+                log.insert_explicit(Atom::from("count"), (count as i64).into());
+                log.insert_explicit(Atom::from("sum"), sum.into());
+                (timestamp, tags, kind)
+            }
+        };
+
+        let kind = match kind {
+            MetricKind::Incremental => "incremental",
+            MetricKind::Absolute => "absolute",
+        };
+        log.insert_explicit(Atom::from("kind"), kind.into());
+
+        log.insert_implicit(
+            event::TIMESTAMP.clone(),
+            timestamp.unwrap_or_else(Utc::now).into(),
+        );
+
+        if let Some(tags) = tags {
+            if let Some(host) = self.host_tag.as_ref().and_then(|tag| tags.get(tag)) {
+                log.insert_implicit(event::HOST.clone(), host.clone().into());
+            }
+
+            for (key, value) in tags {
+                log.insert_explicit(format!("tags.{}", key).into(), value.into());
+            }
+        }
+
+        Some(Event::Log(log))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MetricToLog;
+    use crate::{
+        event::{Event, Metric, MetricKind},
+        transforms::Transform,
+    };
+    use chrono::{offset::TimeZone, DateTime, Utc};
+    use std::collections::HashMap;
+    use string_cache::DefaultAtom as Atom;
+
+    fn ts() -> DateTime<Utc> {
+        Utc.ymd(2018, 11, 14).and_hms_nano(8, 9, 10, 11)
+    }
+
+    fn tags() -> Option<HashMap<String, String>> {
+        Some(
+            vec![
+                ("host".to_owned(), "localhost".to_owned()),
+                ("code".to_owned(), "200".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    fn transform(metric: Metric) -> Event {
+        MetricToLog::new(Some("host".into()))
+            .transform(Event::Metric(metric))
+            .unwrap()
+    }
+
+    #[test]
+    fn transform_counter() {
+        let event = transform(Metric::Counter {
+            name: "hits".into(),
+            val: 10.0,
+            timestamp: Some(ts()),
+            tags: tags(),
+            kind: MetricKind::Absolute,
+        });
+        let log = event.as_log();
+
+        assert_eq!(log[&Atom::from("name")], "hits".into());
+        assert_eq!(log[&Atom::from("type")], "counter".into());
+        assert_eq!(log[&Atom::from("val")], 10.0.into());
+        assert_eq!(log[&Atom::from("kind")], "absolute".into());
+        assert_eq!(log[&Atom::from("timestamp")], ts().into());
+        assert_eq!(log[&Atom::from("host")], "localhost".into());
+        assert_eq!(log[&Atom::from("tags.host")], "localhost".into());
+        assert_eq!(log[&Atom::from("tags.code")], "200".into());
+    }
+
+    #[test]
+    fn transform_gauge() {
+        let event = transform(Metric::Gauge {
+            name: "memory".into(),
+            val: -2.5,
+            timestamp: Some(ts()),
+            tags: None,
+            kind: MetricKind::Incremental,
+        });
+        let log = event.as_log();
+
+        assert_eq!(log[&Atom::from("type")], "gauge".into());
+        assert_eq!(log[&Atom::from("val")], (-2.5).into());
+        assert_eq!(log[&Atom::from("kind")], "incremental".into());
+        assert!(log.get(&Atom::from("host")).is_none());
+    }
+
+    #[test]
+    fn transform_histogram() {
+        let event = transform(Metric::Histogram {
+            name: "latency".into(),
+            val: 0.25,
+            sample_rate: 10,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+        });
+        let log = event.as_log();
+
+        assert_eq!(log[&Atom::from("type")], "histogram".into());
+        assert_eq!(log[&Atom::from("val")], 0.25.into());
+        assert_eq!(log[&Atom::from("sample_rate")], 10.into());
+        assert!(log.get(&Atom::from("timestamp")).is_some());
+    }
+
+    #[test]
+    fn transform_set() {
+        let event = transform(Metric::Set {
+            name: "users".into(),
+            val: "alice".into(),
+            timestamp: Some(ts()),
+            tags: None,
+            kind: MetricKind::Incremental,
+        });
+        let log = event.as_log();
+
+        assert_eq!(log[&Atom::from("type")], "set".into());
+        assert_eq!(log[&Atom::from("val")], "alice".into());
+    }
+
+    #[test]
+    fn transform_aggregated_histogram() {
+        let event = transform(Metric::AggregatedHistogram {
+            name: "latency".into(),
+            buckets: vec![0.5, 1.0],
+            counts: vec![4, 2],
+            count: 7,
+            sum: 4.5,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        });
+        let log = event.as_log();
+
+        assert_eq!(log[&Atom::from("type")], "aggregated_histogram".into());
+        assert_eq!(log[&Atom::from("buckets[1]")], 1.0.into());
+        assert_eq!(log[&Atom::from("counts[1]")], 2.into());
+        assert_eq!(log[&Atom::from("count")], 7.into());
+        assert_eq!(log[&Atom::from("sum")], 4.5.into());
+    }
+
+    #[test]
+    fn transform_aggregated_summary() {
+        let event = transform(Metric::AggregatedSummary {
+            name: "latency".into(),
+            quantiles: vec![0.5, 0.99],
+            values: vec![0.25, 1.5],
+            count: 7,
+            sum: 4.5,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+        });
+        let log = event.as_log();
+
+        assert_eq!(log[&Atom::from("type")], "aggregated_summary".into());
+        assert_eq!(log[&Atom::from("quantiles[0]")], 0.5.into());
+        assert_eq!(log[&Atom::from("values[0]")], 0.25.into());
+        assert_eq!(log[&Atom::from("count")], 7.into());
+    }
+}
@@ -1,20 +1,39 @@
 use crate::Event;
+use futures::{stream, Async, Poll, Stream};
 use snafu::Snafu;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::timer::Interval;
 
 pub mod add_fields;
 pub mod add_tags;
+pub mod aggregate;
+pub mod ansi_stripper;
 pub mod coercer;
+pub mod dedupe;
+pub mod enrich_table;
 pub mod field_filter;
+pub mod geoip;
 pub mod grok_parser;
 pub mod json_parser;
+pub mod key_value_parser;
 pub mod log_to_metric;
 pub mod lua;
+pub mod metric_to_log;
+pub mod redact;
+pub mod reduce;
 pub mod regex_parser;
 pub mod remove_fields;
 pub mod remove_tags;
+pub mod rename_fields;
 pub mod sampler;
 pub mod split;
+pub mod syslog_parser;
+pub mod tag_cardinality_limit;
+pub mod throttle;
+pub mod timestamp_parser;
 pub mod tokenizer;
+pub mod util;
 
 pub trait Transform: Send {
     fn transform(&mut self, event: Event) -> Option<Event>;
@@ -24,6 +43,81 @@ pub trait Transform: Send {
             output.push(transformed);
         }
     }
+
+    /// Wrap the transform around a stream of input events. Transforms that
+    /// need to emit events independently of their input, such as on a
+    /// timer, can override this; the default passes each event through
+    /// `transform_into`.
+    fn transform_stream(
+        self: Box<Self>,
+        input_rx: Box<dyn Stream<Item = Event, Error = ()> + Send>,
+    ) -> Box<dyn Stream<Item = Event, Error = ()> + Send>
+    where
+        Self: 'static,
+    {
+        let mut me = self;
+        Box::new(
+            input_rx
+                .map(move |event| {
+                    let mut output = Vec::with_capacity(1);
+                    me.transform_into(&mut output, event);
+                    futures::stream::iter_ok(output.into_iter())
+                })
+                .flatten(),
+        )
+    }
+}
+
+/// Runs `transform` over the input stream like the default
+/// `Transform::transform_stream`, while also calling `on_tick` every `period`
+/// and `on_done` once the input ends, so buffered events can be emitted.
+pub fn stream_with_timer<T>(
+    transform: Box<T>,
+    period: Duration,
+    input_rx: Box<dyn Stream<Item = Event, Error = ()> + Send>,
+    on_tick: fn(&mut T, &mut Vec<Event>),
+    on_done: fn(&mut T, &mut Vec<Event>),
+) -> Box<dyn Stream<Item = Event, Error = ()> + Send>
+where
+    T: Transform + 'static,
+{
+    let mut me = transform;
+    let mut input_rx = input_rx;
+    let mut timer = Interval::new_interval(period);
+    let mut output = Vec::new();
+    let mut pending = VecDeque::new();
+    let mut input_done = false;
+
+    Box::new(stream::poll_fn(move || -> Poll<Option<Event>, ()> {
+        loop {
+            if let Some(event) = pending.pop_front() {
+                return Ok(Async::Ready(Some(event)));
+            }
+
+            if input_done {
+                return Ok(Async::Ready(None));
+            }
+
+            match timer.poll() {
+                Ok(Async::Ready(_)) => on_tick(&mut me, &mut output),
+                Ok(Async::NotReady) => {}
+                Err(error) => error!(message = "Timer error.", %error),
+            }
+
+            if output.is_empty() {
+                match input_rx.poll()? {
+                    Async::Ready(Some(event)) => me.transform_into(&mut output, event),
+                    Async::Ready(None) => {
+                        on_done(&mut me, &mut output);
+                        input_done = true;
+                    }
+                    Async::NotReady => return Ok(Async::NotReady),
+                }
+            }
+
+            pending.extend(output.drain(..));
+        }
+    }))
 }
 
 #[derive(Debug, Snafu)]
@@ -0,0 +1,388 @@
+use super::Transform;
+use crate::{
+    event::{Event, ValueKind},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+use std::{borrow::Cow, ops::Range};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid regular expression {:?}: {}", pattern, source))]
+    InvalidRegex {
+        pattern: String,
+        source: regex::Error,
+    },
+    #[snafu(display("At least one of `patterns` or `regexes` must be given"))]
+    NoPatterns,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct RedactConfig {
+    pub fields: Vec<Atom>,
+    pub patterns: Vec<Pattern>,
+    pub regexes: Vec<String>,
+    pub mode: Mode,
+    #[derivative(Default(value = "default_replacement()"))]
+    pub replacement: String,
+}
+
+fn default_replacement() -> String {
+    "[REDACTED]".into()
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    CreditCard,
+    Email,
+    BearerToken,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Derivative)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum Mode {
+    /// Replace every character with `*`, keeping the length.
+    #[derivative(Default)]
+    Mask,
+    /// Replace with the configured `replacement`.
+    Fixed,
+    /// Replace with the SHA-256 hash of the sensitive value, so equal
+    /// values can still be correlated.
+    Hash,
+}
+
+inventory::submit! {
+    TransformDescription::new::<RedactConfig>("redact")
+}
+
+#[typetag::serde(name = "redact")]
+impl TransformConfig for RedactConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        if self.patterns.is_empty() && self.regexes.is_empty() {
+            return Err(Box::new(BuildError::NoPatterns));
+        }
+
+        let mut matchers = self
+            .patterns
+            .iter()
+            .map(|pattern| Matcher::builtin(*pattern))
+            .collect::<Vec<_>>();
+        for pattern in &self.regexes {
+            let regex = Regex::new(pattern).context(InvalidRegex { pattern })?;
+            matchers.push(Matcher { regex, find: None });
+        }
+
+        Ok(Box::new(Redact {
+            fields: self.fields.clone(),
+            matchers,
+            mode: self.mode.clone(),
+            replacement: self.replacement.clone(),
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "redact"
+    }
+}
+
+/// A regex finding sensitive values. If it has a capture group named
+/// `redact`, only that group is replaced. If it has a `find` function, only
+/// the parts of the match it returns are replaced.
+struct Matcher {
+    regex: Regex,
+    find: Option<fn(&str) -> Vec<Range<usize>>>,
+}
+
+impl Matcher {
+    fn builtin(pattern: Pattern) -> Self {
+        let (regex, find) = match pattern {
+            Pattern::CreditCard => (
+                r"\b\d(?:[ -]?\d){12,}\b",
+                Some(find_cards as fn(&str) -> Vec<Range<usize>>),
+            ),
+            Pattern::Email => (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", None),
+            Pattern::BearerToken => (r"(?i)\bbearer\s+(?P<redact>[A-Za-z0-9\-._~+/]+=*)", None),
+        };
+
+        Self {
+            regex: Regex::new(regex).unwrap(),
+            find,
+        }
+    }
+}
+
+/// Finds the card numbers in a run of digit groups, which may include other
+/// numbers before or after them. Scanning from the left, each card is the
+/// longest span of 13 to 19 digits that begins and ends at a group boundary
+/// and passes the Luhn check.
+fn find_cards(run: &str) -> Vec<Range<usize>> {
+    // Byte offsets of the digits; the rest are single byte separators
+    let digits = run
+        .char_indices()
+        .filter(|(_, c)| c.is_ascii_digit())
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    let starts_group = |i: usize| i == 0 || digits[i] != digits[i - 1] + 1;
+    let ends_group = |i: usize| i + 1 == digits.len() || digits[i + 1] != digits[i] + 1;
+
+    let mut cards = Vec::new();
+    let mut start = 0;
+    while start < digits.len() {
+        let end = if starts_group(start) {
+            (13..=19)
+                .rev()
+                .map(|len| start + len - 1)
+                .filter(|&end| end < digits.len() && ends_group(end))
+                .find(|&end| luhn_valid(&run[digits[start]..=digits[end]]))
+        } else {
+            None
+        };
+
+        match end {
+            Some(end) => {
+                cards.push(digits[start]..digits[end] + 1);
+                start = end + 1;
+            }
+            None => start += 1,
+        }
+    }
+    cards
+}
+
+/// Checks the Luhn checksum of the digits in `s`, which all payment card
+/// numbers carry.
+fn luhn_valid(s: &str) -> bool {
+    let sum = s
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, digit)| match (i % 2, digit * 2) {
+            (0, _) => digit,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum::<u32>();
+    sum % 10 == 0
+}
+
+pub struct Redact {
+    fields: Vec<Atom>,
+    matchers: Vec<Matcher>,
+    mode: Mode,
+    replacement: String,
+}
+
+impl Redact {
+    fn replace(&self, secret: &str) -> String {
+        match self.mode {
+            Mode::Mask => "*".repeat(secret.chars().count()),
+            Mode::Fixed => self.replacement.clone(),
+            Mode::Hash => format!("sha256:{}", hex::encode(Sha256::digest(secret.as_bytes()))),
+        }
+    }
+
+    fn redact<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let mut value = Cow::Borrowed(value);
+        for matcher in &self.matchers {
+            let replaced = matcher.regex.replace_all(&value, |caps: &Captures| {
+                let whole = caps.get(0).unwrap();
+                if let Some(find) = matcher.find {
+                    let mut redacted = String::new();
+                    let mut last = 0;
+                    for secret in find(whole.as_str()) {
+                        redacted.push_str(&whole.as_str()[last..secret.start]);
+                        redacted.push_str(&self.replace(&whole.as_str()[secret.clone()]));
+                        last = secret.end;
+                    }
+                    redacted.push_str(&whole.as_str()[last..]);
+                    return redacted;
+                }
+
+                match caps.name("redact") {
+                    Some(secret) => {
+                        let start = secret.start() - whole.start();
+                        let end = secret.end() - whole.start();
+                        format!(
+                            "{}{}{}",
+                            &whole.as_str()[..start],
+                            self.replace(secret.as_str()),
+                            &whole.as_str()[end..]
+                        )
+                    }
+                    None => self.replace(whole.as_str()),
+                }
+            });
+            if let Cow::Owned(replaced) = replaced {
+                value = Cow::Owned(replaced);
+            }
+        }
+        value
+    }
+
+    fn redact_value(&self, value: &ValueKind) -> Option<ValueKind> {
+        match value {
+            ValueKind::Bytes(bytes) => match self.redact(&String::from_utf8_lossy(bytes)) {
+                Cow::Owned(redacted) => Some(redacted.into()),
+                Cow::Borrowed(_) => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Transform for Redact {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let fields = if self.fields.is_empty() {
+            event.as_log().keys().cloned().collect()
+        } else {
+            self.fields.clone()
+        };
+
+        for field in fields {
+            if let Some(value) = event.as_mut_log().get_mut(&field) {
+                if let Some(redacted) = self.redact_value(value) {
+                    *value = redacted;
+                }
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_cards, luhn_valid, RedactConfig};
+    use crate::{event::Event, topology::config::TransformConfig};
+
+    fn do_transform(message: &str, config: &str) -> Event {
+        let mut redact = toml::from_str::<RedactConfig>(config)
+            .unwrap()
+            .build()
+            .unwrap();
+        redact.transform(Event::from(message)).unwrap()
+    }
+
+    fn message(event: &Event) -> String {
+        event.as_log()[&"message".into()].to_string_lossy()
+    }
+
+    #[test]
+    fn redact_luhn() {
+        assert!(luhn_valid("4111 1111 1111 1111"));
+        assert!(luhn_valid("5500-0000-0000-0004"));
+        assert!(!luhn_valid("4111 1111 1111 1112"));
+    }
+
+    #[test]
+    fn redact_finds_cards_next_to_other_numbers() {
+        assert_eq!(find_cards("4111 1111 1111 1111 4"), vec![0..19]);
+        assert_eq!(find_cards("12 4111111111111111"), vec![3..19]);
+        assert_eq!(find_cards("41111111111111114"), vec![]);
+
+        let event = do_transform(
+            "cards 4111 1111 1111 1111 4 and 7 5500000000000004 7",
+            r#"patterns = ["credit_card"]"#,
+        );
+        assert_eq!(
+            message(&event),
+            "cards ******************* 4 and 7 **************** 7"
+        );
+    }
+
+    #[test]
+    fn redact_keeps_implicit_fields_implicit() {
+        let event = do_transform("from a@example.com", r#"patterns = ["email"]"#);
+        assert_eq!(message(&event), "from *************");
+        assert!(event
+            .as_log()
+            .explicit_fields()
+            .all(|(key, _)| key != &"message".into()));
+    }
+
+    #[test]
+    fn redact_builtin_patterns_masked() {
+        let event = do_transform(
+            "card 4111 1111 1111 1111 order 1234567890123 from jane.doe@example.com \
+             Authorization: Bearer abc.DEF-123=",
+            r#"patterns = ["credit_card", "email", "bearer_token"]"#,
+        );
+
+        assert_eq!(
+            message(&event),
+            "card ******************* order 1234567890123 from ******************** \
+             Authorization: Bearer ************"
+        );
+    }
+
+    #[test]
+    fn redact_regex_fixed_replacement_in_selected_fields() {
+        let mut event = Event::from("password=hunter2");
+        event
+            .as_mut_log()
+            .insert_explicit("query".into(), "user=bob&password=hunter2".into());
+        event
+            .as_mut_log()
+            .insert_explicit("status".into(), 200.into());
+
+        let mut redact = toml::from_str::<RedactConfig>(
+            r#"
+            fields = ["query", "status"]
+            regexes = ["password=(?P<redact>[^&]*)"]
+            mode = "fixed"
+            "#,
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let event = redact.transform(event).unwrap();
+
+        assert_eq!(
+            event.as_log()[&"query".into()],
+            "user=bob&password=[REDACTED]".into()
+        );
+        assert_eq!(event.as_log()[&"status".into()], 200.into());
+        assert_eq!(message(&event), "password=hunter2");
+    }
+
+    #[test]
+    fn redact_hash_is_stable() {
+        let config = r#"
+            patterns = ["email"]
+            mode = "hash"
+        "#;
+        let first = do_transform("from a@example.com", config);
+        let second = do_transform("to a@example.com", config);
+
+        let hash = |event: &Event| message(event).split(' ').nth(1).unwrap().to_string();
+        assert!(hash(&first).starts_with("sha256:"));
+        assert_eq!(hash(&first).len(), "sha256:".len() + 64);
+        assert_eq!(hash(&first), hash(&second));
+    }
+
+    #[test]
+    fn redact_requires_patterns() {
+        assert!(toml::from_str::<RedactConfig>("").unwrap().build().is_err());
+        assert!(toml::from_str::<RedactConfig>(r#"regexes = ["("]"#)
+            .unwrap()
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,636 @@
+use super::Transform;
+use crate::{
+    event::{Event, LogEvent, ValueKind},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use bytes::{Bytes, BytesMut};
+use futures::Stream;
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Only one of `starts_when` and `ends_when` may be specified"))]
+    ConflictingConditions,
+    #[snafu(display("Invalid regular expression in condition: {}", source))]
+    InvalidConditionRegex { source: regex::Error },
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReduceConfig {
+    pub expire_after_ms: u64,
+    pub flush_period_ms: u64,
+    pub identifier_fields: Vec<Atom>,
+    pub merge_strategies: HashMap<Atom, MergeStrategy>,
+    pub starts_when: Option<FieldCondition>,
+    pub ends_when: Option<FieldCondition>,
+}
+
+impl Default for ReduceConfig {
+    fn default() -> Self {
+        Self {
+            expire_after_ms: 30000,
+            flush_period_ms: 1000,
+            identifier_fields: Vec::new(),
+            merge_strategies: HashMap::new(),
+            starts_when: None,
+            ends_when: None,
+        }
+    }
+}
+
+/// A condition that holds when the given field exists and its value
+/// matches the regular expression.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FieldCondition {
+    pub field: Atom,
+    pub regex: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Join string values with a space.
+    Concat,
+    /// Join string values with a newline.
+    ConcatNewline,
+    /// Add numeric values together.
+    Sum,
+    /// Keep the largest numeric or timestamp value.
+    Max,
+    /// Keep the smallest numeric or timestamp value.
+    Min,
+    /// Keep the first value seen.
+    First,
+    /// Keep the last value seen.
+    Last,
+    /// Keep every value, written out as `field[0]`, `field[1]`, ...
+    Array,
+}
+
+inventory::submit! {
+    TransformDescription::new::<ReduceConfig>("reduce")
+}
+
+#[typetag::serde(name = "reduce")]
+impl TransformConfig for ReduceConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        let condition = match (&self.starts_when, &self.ends_when) {
+            (Some(_), Some(_)) => return Err(Box::new(BuildError::ConflictingConditions)),
+            (Some(cond), None) => Some(GroupCondition::StartsWhen(Condition::new(cond)?)),
+            (None, Some(cond)) => Some(GroupCondition::EndsWhen(Condition::new(cond)?)),
+            (None, None) => None,
+        };
+
+        Ok(Box::new(Reduce::new(
+            Duration::from_millis(self.expire_after_ms),
+            Duration::from_millis(self.flush_period_ms),
+            self.identifier_fields.clone(),
+            self.merge_strategies.clone(),
+            condition,
+        )))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "reduce"
+    }
+}
+
+pub struct Condition {
+    field: Atom,
+    regex: Regex,
+}
+
+impl Condition {
+    fn new(config: &FieldCondition) -> crate::Result<Self> {
+        let regex = Regex::new(&config.regex).context(InvalidConditionRegex)?;
+        Ok(Self {
+            field: config.field.clone(),
+            regex,
+        })
+    }
+
+    fn check(&self, log: &LogEvent) -> bool {
+        log.get(&self.field)
+            .map_or(false, |value| self.regex.is_match(&value.as_bytes()))
+    }
+}
+
+pub enum GroupCondition {
+    /// A matching event closes the current group and begins a new one.
+    StartsWhen(Condition),
+    /// A matching event is the last event of the current group.
+    EndsWhen(Condition),
+}
+
+/// The accumulated value of a single field within a group.
+#[derive(Debug)]
+enum Merger {
+    Concat(BytesMut, u8),
+    Sum(ValueKind),
+    Max(ValueKind),
+    Min(ValueKind),
+    First(ValueKind),
+    Last(ValueKind),
+    Array(Vec<ValueKind>),
+}
+
+impl Merger {
+    fn new(strategy: Option<MergeStrategy>, value: ValueKind) -> Self {
+        let strategy = strategy.unwrap_or_else(|| match value {
+            ValueKind::Integer(_) | ValueKind::Float(_) => MergeStrategy::Sum,
+            _ => MergeStrategy::First,
+        });
+
+        match strategy {
+            MergeStrategy::Concat => Merger::Concat(BytesMut::from(&value.as_bytes()[..]), b' '),
+            MergeStrategy::ConcatNewline => {
+                Merger::Concat(BytesMut::from(&value.as_bytes()[..]), b'\n')
+            }
+            MergeStrategy::Sum => Merger::Sum(value),
+            MergeStrategy::Max => Merger::Max(value),
+            MergeStrategy::Min => Merger::Min(value),
+            MergeStrategy::First => Merger::First(value),
+            MergeStrategy::Last => Merger::Last(value),
+            MergeStrategy::Array => Merger::Array(vec![value]),
+        }
+    }
+
+    fn add(&mut self, value: ValueKind) -> Result<(), String> {
+        match self {
+            Merger::Concat(buffer, separator) => {
+                buffer.extend_from_slice(&[*separator]);
+                buffer.extend_from_slice(&value.as_bytes());
+            }
+            Merger::Sum(total) => {
+                *total = match (&*total, &value) {
+                    (ValueKind::Integer(a), ValueKind::Integer(b)) => match a.checked_add(*b) {
+                        Some(sum) => ValueKind::Integer(sum),
+                        None => ValueKind::Float(*a as f64 + *b as f64),
+                    },
+                    (a, b) => ValueKind::Float(as_f64(a)? + as_f64(b)?),
+                }
+            }
+            Merger::Max(current) => {
+                if compare(&value, current)? == std::cmp::Ordering::Greater {
+                    *current = value;
+                }
+            }
+            Merger::Min(current) => {
+                if compare(&value, current)? == std::cmp::Ordering::Less {
+                    *current = value;
+                }
+            }
+            Merger::First(_) => {}
+            Merger::Last(current) => *current = value,
+            Merger::Array(values) => values.push(value),
+        }
+        Ok(())
+    }
+
+    fn insert_into(self, key: Atom, explicit: bool, log: &mut LogEvent) {
+        let insert = |log: &mut LogEvent, key: Atom, value: ValueKind| {
+            if explicit {
+                log.insert_explicit(key, value);
+            } else {
+                log.insert_implicit(key, value);
+            }
+        };
+
+        match self {
+            Merger::Concat(buffer, _) => insert(log, key, buffer.freeze().into()),
+            Merger::Sum(value)
+            | Merger::Max(value)
+            | Merger::Min(value)
+            | Merger::First(value)
+            | Merger::Last(value) => insert(log, key, value),
+            Merger::Array(values) => {
+                for (i, value) in values.into_iter().enumerate() {
+                    insert(log, format!("{}[{}]", key, i).into(), value);
+                }
+            }
+        }
+    }
+}
+
+fn as_f64(value: &ValueKind) -> Result<f64, String> {
+    match value {
+        ValueKind::Integer(i) => Ok(*i as f64),
+        ValueKind::Float(f) => Ok(*f),
+        other => Err(format!("{:?} is not a number", other)),
+    }
+}
+
+fn compare(a: &ValueKind, b: &ValueKind) -> Result<std::cmp::Ordering, String> {
+    match (a, b) {
+        (ValueKind::Timestamp(a), ValueKind::Timestamp(b)) => Ok(a.cmp(b)),
+        (ValueKind::Integer(a), ValueKind::Integer(b)) => Ok(a.cmp(b)),
+        (a, b) => as_f64(a)?
+            .partial_cmp(&as_f64(b)?)
+            .ok_or_else(|| "values are not comparable".to_string()),
+    }
+}
+
+struct ReduceState {
+    fields: HashMap<Atom, (Merger, bool)>,
+    stale_since: Instant,
+}
+
+impl ReduceState {
+    fn new(log: LogEvent, strategies: &HashMap<Atom, MergeStrategy>) -> Self {
+        let mut state = Self {
+            fields: HashMap::new(),
+            stale_since: Instant::now(),
+        };
+        state.add(log, strategies);
+        state
+    }
+
+    fn add(&mut self, mut log: LogEvent, strategies: &HashMap<Atom, MergeStrategy>) {
+        let explicit = log
+            .explicit_fields()
+            .map(|(k, _)| k.clone())
+            .collect::<HashSet<_>>();
+        let keys = log.keys().cloned().collect::<Vec<_>>();
+
+        for key in keys {
+            let value = match log.remove(&key) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            if let Some((merger, _)) = self.fields.get_mut(&key) {
+                if let Err(error) = merger.add(value) {
+                    debug!(
+                        message = "Failed to merge field value.",
+                        field = &key[..],
+                        %error,
+                        rate_limit_secs = 30
+                    );
+                }
+            } else {
+                let merger = Merger::new(strategies.get(&key).cloned(), value);
+                let is_explicit = explicit.contains(&key);
+                self.fields.insert(key, (merger, is_explicit));
+            }
+        }
+
+        self.stale_since = Instant::now();
+    }
+
+    fn flush(self) -> Event {
+        let mut event = Event::new_empty_log();
+        let log = event.as_mut_log();
+        for (key, (merger, explicit)) in self.fields {
+            merger.insert_into(key, explicit, log);
+        }
+        event
+    }
+}
+
+pub struct Reduce {
+    expire_after: Duration,
+    flush_period: Duration,
+    identifier_fields: Vec<Atom>,
+    merge_strategies: HashMap<Atom, MergeStrategy>,
+    condition: Option<GroupCondition>,
+    groups: HashMap<Vec<Option<Bytes>>, ReduceState>,
+    /// Groups completed by `transform`, which can return only one event.
+    completed: Vec<Event>,
+}
+
+impl Reduce {
+    pub fn new(
+        expire_after: Duration,
+        flush_period: Duration,
+        identifier_fields: Vec<Atom>,
+        merge_strategies: HashMap<Atom, MergeStrategy>,
+        condition: Option<GroupCondition>,
+    ) -> Self {
+        Self {
+            expire_after,
+            flush_period,
+            identifier_fields,
+            merge_strategies,
+            condition,
+            groups: HashMap::new(),
+            completed: Vec::new(),
+        }
+    }
+
+    fn flush_expired_into(&mut self, output: &mut Vec<Event>) {
+        output.append(&mut self.completed);
+
+        let expire_after = self.expire_after;
+        let expired = self
+            .groups
+            .iter()
+            .filter(|(_, state)| state.stale_since.elapsed() >= expire_after)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in expired {
+            if let Some(state) = self.groups.remove(&key) {
+                output.push(state.flush());
+            }
+        }
+    }
+
+    fn flush_all_into(&mut self, output: &mut Vec<Event>) {
+        output.append(&mut self.completed);
+        output.extend(self.groups.drain().map(|(_, state)| state.flush()));
+    }
+}
+
+impl Transform for Reduce {
+    // Events always begin or join a group, completed groups are emitted with
+    // the output of the next call to `transform_into` or a flush.
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        let mut completed = Vec::new();
+        self.transform_into(&mut completed, event);
+        self.completed = completed;
+        None
+    }
+
+    fn transform_into(&mut self, output: &mut Vec<Event>, event: Event) {
+        output.append(&mut self.completed);
+
+        let log = event.into_log();
+        let key = self
+            .identifier_fields
+            .iter()
+            .map(|field| log.get(field).map(ValueKind::as_bytes))
+            .collect::<Vec<_>>();
+
+        match &self.condition {
+            Some(GroupCondition::StartsWhen(condition)) if condition.check(&log) => {
+                if let Some(state) = self.groups.remove(&key) {
+                    output.push(state.flush());
+                }
+                self.groups
+                    .insert(key, ReduceState::new(log, &self.merge_strategies));
+            }
+            Some(GroupCondition::EndsWhen(condition)) if condition.check(&log) => {
+                let state = match self.groups.remove(&key) {
+                    Some(mut state) => {
+                        state.add(log, &self.merge_strategies);
+                        state
+                    }
+                    None => ReduceState::new(log, &self.merge_strategies),
+                };
+                output.push(state.flush());
+            }
+            _ => {
+                if let Some(state) = self.groups.get_mut(&key) {
+                    state.add(log, &self.merge_strategies);
+                } else {
+                    self.groups
+                        .insert(key, ReduceState::new(log, &self.merge_strategies));
+                }
+            }
+        }
+    }
+
+    fn transform_stream(
+        self: Box<Self>,
+        input_rx: Box<dyn Stream<Item = Event, Error = ()> + Send>,
+    ) -> Box<dyn Stream<Item = Event, Error = ()> + Send>
+    where
+        Self: 'static,
+    {
+        let flush_period = self.flush_period;
+        super::stream_with_timer(
+            self,
+            flush_period,
+            input_rx,
+            Self::flush_expired_into,
+            Self::flush_all_into,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReduceConfig, Transform};
+    use crate::{event::Event, topology::config::TransformConfig};
+    use futures::{stream, Stream};
+    use string_cache::DefaultAtom as Atom;
+
+    fn reduce(config: &str) -> Box<dyn Transform> {
+        toml::from_str::<ReduceConfig>(config)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn collect(reduce: Box<dyn Transform>, events: Vec<Event>) -> Vec<Event> {
+        let mut rt = crate::runtime::Runtime::new().unwrap();
+        rt.block_on(
+            reduce
+                .transform_stream(Box::new(stream::iter_ok(events)))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    fn event(fields: &[(&str, &str)]) -> Event {
+        let mut event = Event::new_empty_log();
+        for (key, value) in fields {
+            event
+                .as_mut_log()
+                .insert_explicit((*key).into(), (*value).into());
+        }
+        event
+    }
+
+    #[test]
+    fn reduce_groups_until_ends_when() {
+        let reduce = reduce(
+            r#"
+            identifier_fields = ["request_id"]
+            merge_strategies.message = "concat"
+            ends_when.field = "end"
+            ends_when.regex = "true"
+            "#,
+        );
+
+        let output = collect(
+            reduce,
+            vec![
+                event(&[("message", "a"), ("request_id", "1")]),
+                event(&[("message", "b"), ("request_id", "2")]),
+                event(&[("message", "c"), ("request_id", "1"), ("end", "true")]),
+            ],
+        );
+
+        // The ended group is emitted right away, the open one at end of stream
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].as_log()[&Atom::from("message")], "a c".into());
+        assert_eq!(output[0].as_log()[&Atom::from("request_id")], "1".into());
+        assert_eq!(output[1].as_log()[&Atom::from("message")], "b".into());
+        assert_eq!(output[1].as_log()[&Atom::from("request_id")], "2".into());
+    }
+
+    #[test]
+    fn reduce_starts_when_flushes_previous_group() {
+        let reduce = reduce(
+            r#"
+            merge_strategies.message = "concat_newline"
+            starts_when.field = "message"
+            starts_when.regex = "^\\S"
+            "#,
+        );
+
+        let output = collect(
+            reduce,
+            vec![
+                event(&[("message", "Exception")]),
+                event(&[("message", "  at foo")]),
+                event(&[("message", "  at bar")]),
+                event(&[("message", "Next")]),
+            ],
+        );
+
+        assert_eq!(output.len(), 2);
+        assert_eq!(
+            output[0].as_log()[&Atom::from("message")],
+            "Exception\n  at foo\n  at bar".into()
+        );
+        assert_eq!(output[1].as_log()[&Atom::from("message")], "Next".into());
+    }
+
+    #[test]
+    fn reduce_merge_strategies() {
+        let reduce = reduce(
+            r#"
+            merge_strategies.first = "first"
+            merge_strategies.last = "last"
+            merge_strategies.max = "max"
+            merge_strategies.min = "min"
+            merge_strategies.list = "array"
+            ends_when.field = "end"
+            ends_when.regex = "."
+            "#,
+        );
+
+        let mut values = Vec::new();
+        for (i, n) in [3, 7, 1].iter().enumerate() {
+            let mut e = Event::new_empty_log();
+            let log = e.as_mut_log();
+            log.insert_explicit("first".into(), (i as i64).to_string().into());
+            log.insert_explicit("last".into(), (i as i64).to_string().into());
+            log.insert_explicit("max".into(), (*n as i64).into());
+            log.insert_explicit("min".into(), (*n as i64).into());
+            log.insert_explicit("list".into(), (*n as i64).into());
+            log.insert_explicit("count".into(), 2.into());
+            log.insert_explicit("ratio".into(), 0.5.into());
+            if i == 2 {
+                log.insert_explicit("end".into(), "yes".into());
+            }
+            values.push(e);
+        }
+
+        let output = collect(reduce, values);
+        assert_eq!(output.len(), 1);
+        let log = output[0].as_log();
+
+        assert_eq!(log[&Atom::from("first")], "0".into());
+        assert_eq!(log[&Atom::from("last")], "2".into());
+        assert_eq!(log[&Atom::from("max")], 7.into());
+        assert_eq!(log[&Atom::from("min")], 1.into());
+        assert_eq!(log[&Atom::from("list[0]")], 3.into());
+        assert_eq!(log[&Atom::from("list[1]")], 7.into());
+        assert_eq!(log[&Atom::from("list[2]")], 1.into());
+        assert_eq!(log[&Atom::from("count")], 6.into());
+        assert_eq!(log[&Atom::from("ratio")], 1.5.into());
+    }
+
+    #[test]
+    fn reduce_sum_overflows_into_float() {
+        let reduce = reduce("");
+
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            let mut e = Event::new_empty_log();
+            e.as_mut_log()
+                .insert_explicit("bytes".into(), std::i64::MAX.into());
+            events.push(e);
+        }
+
+        let output = collect(reduce, events);
+        assert_eq!(output.len(), 1);
+        assert_eq!(
+            output[0].as_log()[&Atom::from("bytes")],
+            (std::i64::MAX as f64 * 2.0).into()
+        );
+    }
+
+    #[test]
+    fn reduce_transform_holds_completed_groups() {
+        let mut reduce = reduce(
+            r#"
+            identifier_fields = ["request_id"]
+            merge_strategies.message = "concat"
+            ends_when.field = "end"
+            ends_when.regex = "true"
+            "#,
+        );
+
+        assert!(reduce
+            .transform(event(&[("message", "a"), ("request_id", "1")]))
+            .is_none());
+        assert!(reduce
+            .transform(event(&[
+                ("message", "b"),
+                ("request_id", "1"),
+                ("end", "true")
+            ]))
+            .is_none());
+        assert!(reduce
+            .transform(event(&[("message", "c"), ("request_id", "2")]))
+            .is_none());
+
+        let output = collect(reduce, Vec::new());
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].as_log()[&Atom::from("message")], "a b".into());
+        assert_eq!(output[1].as_log()[&Atom::from("message")], "c".into());
+    }
+
+    #[test]
+    fn reduce_flushes_remaining_groups_at_end_of_stream() {
+        let reduce = reduce(
+            r#"
+            identifier_fields = ["request_id"]
+            merge_strategies.message = "concat"
+            "#,
+        );
+
+        let input = vec![
+            event(&[("message", "a"), ("request_id", "1")]),
+            event(&[("message", "b"), ("request_id", "2")]),
+            event(&[("message", "c"), ("request_id", "1")]),
+        ];
+
+        let mut output = collect(reduce, input)
+            .into_iter()
+            .map(|e| e.as_log()[&Atom::from("message")].to_string_lossy())
+            .collect::<Vec<_>>();
+        output.sort();
+
+        assert_eq!(output, vec!["a c".to_string(), "b".to_string()]);
+    }
+}
@@ -67,7 +67,10 @@ impl Transform for RemoveTags {
 #[cfg(test)]
 mod tests {
     use super::RemoveTags;
-    use crate::{event::Event, event::Metric, transforms::Transform};
+    use crate::{
+        event::{Event, Metric, MetricKind},
+        transforms::Transform,
+    };
 
     #[test]
     fn remove_tags() {
@@ -84,6 +87,7 @@ mod tests {
                 .into_iter()
                 .collect(),
             ),
+            kind: MetricKind::Incremental,
         });
 
         let mut transform = RemoveTags::new(vec!["region".into(), "host".into()]);
@@ -107,6 +111,7 @@ mod tests {
                     .into_iter()
                     .collect(),
             ),
+            kind: MetricKind::Incremental,
         });
 
         let mut transform = RemoveTags::new(vec!["env".into()]);
@@ -122,6 +127,7 @@ mod tests {
             val: "bar".into(),
             timestamp: None,
             tags: None,
+            kind: MetricKind::Incremental,
         });
 
         let mut transform = RemoveTags::new(vec!["env".into()]);
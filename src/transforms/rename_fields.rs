@@ -0,0 +1,239 @@
+use super::Transform;
+use crate::{
+    event::LogEvent,
+    topology::config::{DataType, TransformConfig, TransformDescription},
+    Event,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display(
+        "Field `{}` can't be renamed to `{}`, which is nested below it",
+        from,
+        to
+    ))]
+    NestedDestination { from: Atom, to: Atom },
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RenameFieldsConfig {
+    pub fields: IndexMap<Atom, Atom>,
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+inventory::submit! {
+    TransformDescription::new_without_default::<RenameFieldsConfig>("rename_fields")
+}
+
+#[typetag::serde(name = "rename_fields")]
+impl TransformConfig for RenameFieldsConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        for (from, to) in &self.fields {
+            if from != to && is_path_or_child(to, from) {
+                return Err(Box::new(BuildError::NestedDestination {
+                    from: from.clone(),
+                    to: to.clone(),
+                }));
+            }
+        }
+
+        Ok(Box::new(RenameFields::new(
+            self.fields.clone(),
+            self.overwrite,
+        )))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "rename_fields"
+    }
+}
+
+pub struct RenameFields {
+    fields: IndexMap<Atom, Atom>,
+    overwrite: bool,
+}
+
+impl RenameFields {
+    pub fn new(fields: IndexMap<Atom, Atom>, overwrite: bool) -> Self {
+        RenameFields { fields, overwrite }
+    }
+
+    fn rename(&self, log: &mut LogEvent, from: &Atom, to: &Atom) {
+        // Renaming a path also moves everything nested below it, e.g.
+        // renaming `a` moves `a.b` and `a[0]` as well.
+        let keys = log
+            .keys()
+            .filter(|key| is_path_or_child(key, from))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            let new_key = Atom::from(format!("{}{}", to, &key[from.len()..]));
+            if !self.overwrite && log.contains(&new_key) {
+                warn!(
+                    message = "Field already exists, not renaming.",
+                    field = &key[..],
+                    destination = &new_key[..],
+                    rate_limit_secs = 30
+                );
+                continue;
+            }
+            log.rename_key(&key, new_key);
+        }
+    }
+}
+
+fn is_path_or_child(key: &str, path: &str) -> bool {
+    key.starts_with(path)
+        && match key[path.len()..].chars().next() {
+            None | Some('.') | Some('[') => true,
+            Some(_) => false,
+        }
+}
+
+impl Transform for RenameFields {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        for (from, to) in &self.fields {
+            if from != to {
+                self.rename(event.as_mut_log(), from, to);
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenameFields, RenameFieldsConfig};
+    use crate::{event::Event, topology::config::TransformConfig, transforms::Transform};
+    use indexmap::IndexMap;
+    use string_cache::DefaultAtom as Atom;
+
+    fn fields(pairs: &[(&str, &str)]) -> IndexMap<Atom, Atom> {
+        pairs
+            .iter()
+            .map(|(from, to)| (Atom::from(*from), Atom::from(*to)))
+            .collect()
+    }
+
+    #[test]
+    fn rename_fields_keeps_value_and_explicit_flag() {
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert_explicit("lvl".into(), 3.into());
+        event
+            .as_mut_log()
+            .insert_implicit("msg".into(), "hello".into());
+
+        let mut transform = RenameFields::new(
+            fields(&[("lvl", "level"), ("msg", "message"), ("unknown", "x")]),
+            true,
+        );
+        let event = transform.transform(event).unwrap();
+        let log = event.as_log();
+
+        assert_eq!(log[&"level".into()], 3.into());
+        assert_eq!(log[&"message".into()], "hello".into());
+        assert!(log.get(&"lvl".into()).is_none());
+        assert!(log.get(&"msg".into()).is_none());
+        assert!(log.get(&"x".into()).is_none());
+
+        let explicit = log.explicit_fields().map(|(k, _)| k).collect::<Vec<_>>();
+        assert_eq!(explicit, vec![&Atom::from("level")]);
+    }
+
+    #[test]
+    fn rename_fields_moves_nested_paths() {
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert_explicit("a.b".into(), 1.into());
+        event.as_mut_log().insert_explicit("a[0]".into(), 2.into());
+        event.as_mut_log().insert_explicit("ab".into(), 3.into());
+
+        let mut transform = RenameFields::new(fields(&[("a", "x.y")]), true);
+        let event = transform.transform(event).unwrap();
+        let log = event.as_log();
+
+        assert_eq!(log[&"x.y.b".into()], 1.into());
+        assert_eq!(log[&"x.y[0]".into()], 2.into());
+        assert_eq!(log[&"ab".into()], 3.into());
+    }
+
+    #[test]
+    fn rename_fields_rejects_destination_below_source() {
+        let config = toml::from_str::<RenameFieldsConfig>(
+            r#"
+            fields.a = "a.b"
+            "#,
+        )
+        .unwrap();
+        assert!(config.build().is_err());
+
+        let config = toml::from_str::<RenameFieldsConfig>(
+            r#"
+            fields.a = "ab"
+            fields."a.b" = "a"
+            "#,
+        )
+        .unwrap();
+        assert!(config.build().is_ok());
+    }
+
+    #[test]
+    fn rename_fields_skips_existing_destinations_by_default() {
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert_explicit("a.c".into(), 1.into());
+        event.as_mut_log().insert_explicit("a.d".into(), 2.into());
+        event.as_mut_log().insert_explicit("b.c".into(), 3.into());
+
+        let mut transform = toml::from_str::<RenameFieldsConfig>(
+            r#"
+            fields.a = "b"
+            "#,
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let event = transform.transform(event).unwrap();
+        let log = event.as_log();
+
+        assert_eq!(log[&"a.c".into()], 1.into());
+        assert_eq!(log[&"b.c".into()], 3.into());
+        assert_eq!(log[&"b.d".into()], 2.into());
+        assert!(log.get(&"a.d".into()).is_none());
+    }
+
+    #[test]
+    fn rename_fields_overwrite_or_skip() {
+        let mut event = Event::new_empty_log();
+        event
+            .as_mut_log()
+            .insert_explicit("msg".into(), "new".into());
+        event
+            .as_mut_log()
+            .insert_explicit("message".into(), "old".into());
+
+        let mut transform = RenameFields::new(fields(&[("msg", "message")]), false);
+        let skipped = transform.transform(event.clone()).unwrap();
+        assert_eq!(skipped.as_log()[&"message".into()], "old".into());
+        assert_eq!(skipped.as_log()[&"msg".into()], "new".into());
+
+        let mut transform = RenameFields::new(fields(&[("msg", "message")]), true);
+        let overwritten = transform.transform(event).unwrap();
+        assert_eq!(overwritten.as_log()[&"message".into()], "new".into());
+        assert!(overwritten.as_log().get(&"msg".into()).is_none());
+    }
+}
@@ -12,6 +12,7 @@ use string_cache::DefaultAtom as Atom;
 #[serde(deny_unknown_fields)]
 pub struct SamplerConfig {
     pub rate: u64,
+    pub key_field: Option<Atom>,
     pub pass_list: Vec<String>,
 }
 
@@ -23,7 +24,9 @@ inventory::submit! {
 impl TransformConfig for SamplerConfig {
     fn build(&self) -> crate::Result<Box<dyn Transform>> {
         Ok(RegexSet::new(&self.pass_list)
-            .map::<Box<dyn Transform>, _>(|regex_set| Box::new(Sampler::new(self.rate, regex_set)))
+            .map::<Box<dyn Transform>, _>(|regex_set| {
+                Box::new(Sampler::new(self.rate, self.key_field.clone(), regex_set))
+            })
             .context(super::InvalidRegex)?)
     }
 
@@ -42,12 +45,17 @@ impl TransformConfig for SamplerConfig {
 
 pub struct Sampler {
     rate: u64,
+    key_field: Option<Atom>,
     pass_list: RegexSet,
 }
 
 impl Sampler {
-    pub fn new(rate: u64, pass_list: RegexSet) -> Self {
-        Self { rate, pass_list }
+    pub fn new(rate: u64, key_field: Option<Atom>, pass_list: RegexSet) -> Self {
+        Self {
+            rate,
+            key_field,
+            pass_list,
+        }
     }
 }
 
@@ -63,7 +71,16 @@ impl Transform for Sampler {
             return Some(event);
         }
 
-        if seahash::hash(message.as_bytes()) % self.rate == 0 {
+        // The hash is stable across processes, so every instance keeps or
+        // drops all events sharing the same key.
+        let key = self
+            .key_field
+            .as_ref()
+            .and_then(|field| event.as_log().get(field))
+            .map(|v| v.as_bytes())
+            .unwrap_or_else(|| message.into());
+
+        if seahash::hash(&key) % self.rate == 0 {
             event
                 .as_mut_log()
                 .insert_implicit(Atom::from("sample_rate"), self.rate.to_string().into());
@@ -89,7 +106,7 @@ mod tests {
         let num_events = 10000;
 
         let events = random_events(num_events);
-        let mut sampler = Sampler::new(2, RegexSet::new(&["na"]).unwrap());
+        let mut sampler = Sampler::new(2, None, RegexSet::new(&["na"]).unwrap());
         let total_passed = events
             .into_iter()
             .filter_map(|event| sampler.transform(event))
@@ -99,7 +116,7 @@ mod tests {
         assert_relative_eq!(ideal, actual, epsilon = ideal * 0.5);
 
         let events = random_events(num_events);
-        let mut sampler = Sampler::new(25, RegexSet::new(&["na"]).unwrap());
+        let mut sampler = Sampler::new(25, None, RegexSet::new(&["na"]).unwrap());
         let total_passed = events
             .into_iter()
             .filter_map(|event| sampler.transform(event))
@@ -112,7 +129,7 @@ mod tests {
     #[test]
     fn consistely_samples_the_same_events() {
         let events = random_events(1000);
-        let mut sampler = Sampler::new(2, RegexSet::new(&["na"]).unwrap());
+        let mut sampler = Sampler::new(2, None, RegexSet::new(&["na"]).unwrap());
 
         let first_run = events
             .clone()
@@ -130,7 +147,7 @@ mod tests {
     #[test]
     fn always_passes_events_matching_pass_list() {
         let event = Event::from("i am important");
-        let mut sampler = Sampler::new(0, RegexSet::new(&["important"]).unwrap());
+        let mut sampler = Sampler::new(0, None, RegexSet::new(&["important"]).unwrap());
         let iterations = 0..1000;
         let total_passed = iterations
             .filter_map(|_| sampler.transform(event.clone()))
@@ -138,10 +155,29 @@ mod tests {
         assert_eq!(total_passed, 1000);
     }
 
+    #[test]
+    fn always_passes_pass_list_matches_that_would_be_sampled_out() {
+        let mut sampler = Sampler::new(
+            1000,
+            Some("trace_id".into()),
+            RegexSet::new(&["important"]).unwrap(),
+        );
+
+        for trace_id in 0..100 {
+            let mut event = Event::from(format!("important event {}", trace_id));
+            event
+                .as_mut_log()
+                .insert_explicit("trace_id".into(), trace_id.to_string().into());
+
+            let passed = sampler.transform(event).unwrap();
+            assert!(passed.as_log().get(&Atom::from("sample_rate")).is_none());
+        }
+    }
+
     #[test]
     fn sampler_adds_sampling_rate_to_event() {
         let events = random_events(10000);
-        let mut sampler = Sampler::new(10, RegexSet::new(&["na"]).unwrap());
+        let mut sampler = Sampler::new(10, None, RegexSet::new(&["na"]).unwrap());
         let passing = events
             .into_iter()
             .filter(|s| !s.as_log()[&event::MESSAGE].to_string_lossy().contains("na"))
@@ -150,7 +186,7 @@ mod tests {
         assert_eq!(passing.as_log()[&Atom::from("sample_rate")], "10".into());
 
         let events = random_events(10000);
-        let mut sampler = Sampler::new(25, RegexSet::new(&["na"]).unwrap());
+        let mut sampler = Sampler::new(25, None, RegexSet::new(&["na"]).unwrap());
         let passing = events
             .into_iter()
             .filter(|s| !s.as_log()[&event::MESSAGE].to_string_lossy().contains("na"))
@@ -159,12 +195,39 @@ mod tests {
         assert_eq!(passing.as_log()[&Atom::from("sample_rate")], "25".into());
 
         // If the event passed the regex check, don't include the sampling rate
-        let mut sampler = Sampler::new(25, RegexSet::new(&["na"]).unwrap());
+        let mut sampler = Sampler::new(25, None, RegexSet::new(&["na"]).unwrap());
         let event = Event::from("nananana");
         let passing = sampler.transform(event).unwrap();
         assert!(passing.as_log().get(&Atom::from("sample_rate")).is_none());
     }
 
+    #[test]
+    fn samples_events_with_the_same_key_together() {
+        let mut sampler = Sampler::new(
+            4,
+            Some("trace_id".into()),
+            RegexSet::new(&["important"]).unwrap(),
+        );
+
+        for trace_id in 0..100 {
+            let passed = random_events(20)
+                .into_iter()
+                .map(|mut event| {
+                    event
+                        .as_mut_log()
+                        .insert_explicit("trace_id".into(), trace_id.to_string().into());
+                    sampler.transform(event)
+                })
+                .collect::<Vec<_>>();
+
+            let kept = passed.iter().filter(|event| event.is_some()).count();
+            assert!(kept == 0 || kept == passed.len());
+            for event in passed.into_iter().flatten() {
+                assert_eq!(event.as_log()[&Atom::from("sample_rate")], "4".into());
+            }
+        }
+    }
+
     fn random_events(n: usize) -> Vec<Event> {
         use rand::distributions::Alphanumeric;
         use rand::{thread_rng, Rng};
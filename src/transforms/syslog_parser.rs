@@ -0,0 +1,159 @@
+use super::Transform;
+use crate::{
+    event::{self, Event},
+    syslog,
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use serde::{Deserialize, Serialize};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct SyslogParserConfig {
+    pub field: Option<Atom>,
+    pub drop_invalid: bool,
+    #[derivative(Default(value = "true"))]
+    pub drop_field: bool,
+}
+
+inventory::submit! {
+    TransformDescription::new::<SyslogParserConfig>("syslog_parser")
+}
+
+#[typetag::serde(name = "syslog_parser")]
+impl TransformConfig for SyslogParserConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        Ok(Box::new(SyslogParser::from(self.clone())))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "syslog_parser"
+    }
+}
+
+pub struct SyslogParser {
+    field: Atom,
+    drop_invalid: bool,
+    drop_field: bool,
+}
+
+impl From<SyslogParserConfig> for SyslogParser {
+    fn from(config: SyslogParserConfig) -> SyslogParser {
+        SyslogParser {
+            field: config.field.unwrap_or_else(|| event::MESSAGE.clone()),
+            drop_invalid: config.drop_invalid,
+            drop_field: config.drop_field,
+        }
+    }
+}
+
+impl Transform for SyslogParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let parsed = event.as_log().get(&self.field).and_then(|value| {
+            syslog::parse(&value.to_string_lossy())
+                .map_err(|error| {
+                    debug!(
+                        message = "Event failed to parse as syslog.",
+                        field = self.field.as_ref(),
+                        %error,
+                        rate_limit_secs = 30
+                    )
+                })
+                .ok()
+        });
+
+        let parsed = match parsed {
+            Some(parsed) => parsed,
+            None if self.drop_invalid => return None,
+            None => return Some(event),
+        };
+
+        let log = event.as_mut_log();
+        if self.drop_field {
+            log.remove(&self.field);
+        }
+
+        log.insert_explicit(event::MESSAGE.clone(), parsed.msg.clone().into());
+        if let Some(host) = &parsed.hostname {
+            log.insert_implicit(event::HOST.clone(), host.clone().into());
+        }
+        if let Some(timestamp) = parsed.timestamp {
+            log.insert_implicit(event::TIMESTAMP.clone(), timestamp.into());
+        }
+        parsed.insert_fields(log);
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyslogParserConfig;
+    use crate::{event::LogEvent, topology::config::TransformConfig, Event};
+    use chrono::{TimeZone, Utc};
+
+    fn do_transform(event: Event, config: &str) -> Option<LogEvent> {
+        let mut parser = toml::from_str::<SyslogParserConfig>(config)
+            .unwrap()
+            .build()
+            .unwrap();
+        parser.transform(event).map(Event::into_log)
+    }
+
+    #[test]
+    fn syslog_parser_parses_rfc5424() {
+        let event = Event::from(
+            r#"<34>1 2003-10-11T22:14:15.003Z mymachine.example.com su 1234 ID47 [exampleSDID@32473 iut="3"] 'su root' failed"#,
+        );
+        let log = do_transform(event, "").unwrap();
+
+        assert_eq!(log[&"message".into()], "'su root' failed".into());
+        assert_eq!(log[&"host".into()], "mymachine.example.com".into());
+        assert_eq!(
+            log[&"timestamp".into()],
+            Utc.ymd(2003, 10, 11).and_hms_milli(22, 14, 15, 3).into()
+        );
+        assert_eq!(log[&"appname".into()], "su".into());
+        assert_eq!(log[&"procid".into()], 1234.into());
+        assert_eq!(log[&"msgid".into()], "ID47".into());
+        assert_eq!(log[&"facility".into()], "auth".into());
+        assert_eq!(log[&"severity".into()], "crit".into());
+        assert_eq!(log[&"exampleSDID@32473.iut".into()], "3".into());
+    }
+
+    #[test]
+    fn syslog_parser_parses_other_field() {
+        let mut event = Event::from("original");
+        event.as_mut_log().insert_explicit(
+            "raw".into(),
+            "<13>Feb  5 17:32:18 10.0.0.99 myapp[42]: hello world".into(),
+        );
+
+        let log = do_transform(event.clone(), r#"field = "raw""#).unwrap();
+        assert_eq!(log[&"message".into()], "hello world".into());
+        assert_eq!(log[&"host".into()], "10.0.0.99".into());
+        assert_eq!(log[&"appname".into()], "myapp".into());
+        assert_eq!(log[&"procid".into()], 42.into());
+        assert!(log.get(&"raw".into()).is_none());
+
+        let log = do_transform(event, "field = \"raw\"\ndrop_field = false").unwrap();
+        assert!(log.get(&"raw".into()).is_some());
+    }
+
+    #[test]
+    fn syslog_parser_handles_invalid() {
+        let log = do_transform(Event::from("not syslog"), "").unwrap();
+        assert_eq!(log[&"message".into()], "not syslog".into());
+
+        assert!(do_transform(Event::from("not syslog"), "drop_invalid = true").is_none());
+    }
+}
@@ -0,0 +1,356 @@
+use super::Transform;
+use crate::{
+    event::Event,
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::collections::{hash_map::DefaultHasher, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("`value_limit` must be greater than zero"))]
+    ZeroValueLimit,
+    #[snafu(display("`cache_size_per_key` must be greater than zero"))]
+    ZeroCacheSize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagCardinalityLimitConfig {
+    pub value_limit: usize,
+    pub limit_exceeded_action: LimitExceededAction,
+    pub mode: Mode,
+    pub cache_size_per_key: usize,
+}
+
+impl Default for TagCardinalityLimitConfig {
+    fn default() -> Self {
+        Self {
+            value_limit: 500,
+            limit_exceeded_action: LimitExceededAction::DropTag,
+            mode: Mode::Exact,
+            cache_size_per_key: 5 * 1024,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitExceededAction {
+    DropTag,
+    DropEvent,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Remember every value, using memory proportional to `value_limit`.
+    Exact,
+    /// Remember values in a bloom filter of `cache_size_per_key` bytes. New
+    /// values are occasionally mistaken for already seen ones, letting a
+    /// few more than `value_limit` values through.
+    Probabilistic,
+}
+
+inventory::submit! {
+    TransformDescription::new::<TagCardinalityLimitConfig>("tag_cardinality_limit")
+}
+
+#[typetag::serde(name = "tag_cardinality_limit")]
+impl TransformConfig for TagCardinalityLimitConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        if self.value_limit == 0 {
+            return Err(Box::new(BuildError::ZeroValueLimit));
+        }
+        if self.mode == Mode::Probabilistic && self.cache_size_per_key == 0 {
+            return Err(Box::new(BuildError::ZeroCacheSize));
+        }
+
+        Ok(Box::new(TagCardinalityLimit::new(self.clone())))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "tag_cardinality_limit"
+    }
+}
+
+/// The values accepted so far for a single tag key.
+enum AcceptedValues {
+    Exact(HashSet<String>),
+    Probabilistic { filter: BloomFilter, count: usize },
+}
+
+impl AcceptedValues {
+    fn new(config: &TagCardinalityLimitConfig) -> Self {
+        match config.mode {
+            Mode::Exact => AcceptedValues::Exact(HashSet::new()),
+            Mode::Probabilistic => AcceptedValues::Probabilistic {
+                filter: BloomFilter::new(config.cache_size_per_key * 8, config.value_limit),
+                count: 0,
+            },
+        }
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        match self {
+            AcceptedValues::Exact(values) => values.contains(value),
+            AcceptedValues::Probabilistic { filter, .. } => filter.contains(value),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            AcceptedValues::Exact(values) => values.len(),
+            AcceptedValues::Probabilistic { count, .. } => *count,
+        }
+    }
+
+    fn insert(&mut self, value: &str) {
+        match self {
+            AcceptedValues::Exact(values) => {
+                values.insert(value.to_string());
+            }
+            AcceptedValues::Probabilistic { filter, count } => {
+                filter.insert(value);
+                *count += 1;
+            }
+        }
+    }
+}
+
+/// A plain bloom filter over strings, using double hashing to derive the
+/// bit positions.
+struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u64,
+}
+
+impl BloomFilter {
+    fn new(num_bits: usize, expected_items: usize) -> Self {
+        let num_bits = num_bits.max(64) as u64;
+        // The optimal number of hashes is (m / n) * ln(2).
+        let num_hashes = (num_bits as f64 / expected_items as f64 * std::f64::consts::LN_2)
+            .round()
+            .max(1.0)
+            .min(16.0) as u64;
+
+        Self {
+            bits: vec![0; ((num_bits + 63) / 64) as usize],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    fn positions(&self, value: &str) -> impl Iterator<Item = u64> {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let h1 = hasher.finish();
+        0xdead_beef_u64.hash(&mut hasher);
+        let h2 = hasher.finish() | 1;
+
+        let num_bits = self.num_bits;
+        (0..self.num_hashes).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        self.positions(value)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, value: &str) {
+        let positions = self.positions(value).collect::<Vec<_>>();
+        for bit in positions {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+}
+
+pub struct TagCardinalityLimit {
+    config: TagCardinalityLimitConfig,
+    accepted: HashMap<String, AcceptedValues>,
+}
+
+impl TagCardinalityLimit {
+    pub fn new(config: TagCardinalityLimitConfig) -> Self {
+        Self {
+            config,
+            accepted: HashMap::new(),
+        }
+    }
+
+    /// Whether `value` may be used for `key`, either because it was seen
+    /// before or because the limit has not been reached yet.
+    fn is_allowed(&self, key: &str, value: &str) -> bool {
+        match self.accepted.get(key) {
+            Some(accepted) => accepted.contains(value) || accepted.len() < self.config.value_limit,
+            None => true,
+        }
+    }
+
+    fn record(&mut self, key: &str, value: &str) {
+        let config = &self.config;
+        let accepted = self
+            .accepted
+            .entry(key.to_string())
+            .or_insert_with(|| AcceptedValues::new(config));
+        if !accepted.contains(value) {
+            accepted.insert(value);
+        }
+    }
+}
+
+impl Transform for TagCardinalityLimit {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let tags = match event.as_mut_metric().tags_mut() {
+            Some(tags) => tags,
+            None => return Some(event),
+        };
+
+        match self.config.limit_exceeded_action {
+            LimitExceededAction::DropEvent => {
+                if let Some((key, _)) = tags.iter().find(|(k, v)| !self.is_allowed(k, v)) {
+                    debug!(
+                        message = "Tag value limit exceeded; dropping event.",
+                        tag = &key[..],
+                        rate_limit_secs = 30
+                    );
+                    return None;
+                }
+                for (key, value) in tags.iter() {
+                    self.record(key, value);
+                }
+            }
+            LimitExceededAction::DropTag => {
+                let mut exceeded = Vec::new();
+                for (key, value) in tags.iter() {
+                    if self.is_allowed(key, value) {
+                        self.record(key, value);
+                    } else {
+                        exceeded.push(key.clone());
+                    }
+                }
+                for key in exceeded {
+                    debug!(
+                        message = "Tag value limit exceeded; dropping tag.",
+                        tag = &key[..],
+                        rate_limit_secs = 30
+                    );
+                    tags.remove(&key);
+                }
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BloomFilter, TagCardinalityLimitConfig};
+    use crate::{
+        event::{Event, Metric, MetricKind},
+        topology::config::TransformConfig,
+        transforms::Transform,
+    };
+
+    fn metric(tags: &[(&str, &str)]) -> Event {
+        Event::Metric(Metric::Counter {
+            name: "requests".into(),
+            val: 1.0,
+            timestamp: None,
+            tags: Some(
+                tags.iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            kind: MetricKind::Incremental,
+        })
+    }
+
+    fn build(config: &str) -> Box<dyn Transform> {
+        toml::from_str::<TagCardinalityLimitConfig>(config)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn limit_drops_tag(config: &str) {
+        let mut transform = build(config);
+
+        for user in &["a", "b", "a"] {
+            let event = transform.transform(metric(&[("user", *user), ("env", "prod")]));
+            let tags = event.unwrap().into_metric().tags().clone().unwrap();
+            assert_eq!(tags["user"], *user);
+        }
+
+        let event = transform
+            .transform(metric(&[("user", "c"), ("env", "prod")]))
+            .unwrap();
+        let tags = event.into_metric().tags().clone().unwrap();
+        assert!(!tags.contains_key("user"));
+        assert_eq!(tags["env"], "prod");
+    }
+
+    #[test]
+    fn tag_cardinality_limit_drop_tag_exact() {
+        limit_drops_tag("value_limit = 2");
+    }
+
+    #[test]
+    fn tag_cardinality_limit_drop_tag_probabilistic() {
+        limit_drops_tag("value_limit = 2\nmode = \"probabilistic\"");
+    }
+
+    #[test]
+    fn tag_cardinality_limit_drop_event() {
+        let mut transform = build("value_limit = 1\nlimit_exceeded_action = \"drop_event\"");
+
+        assert!(transform
+            .transform(metric(&[("user", "a"), ("env", "prod")]))
+            .is_some());
+        assert!(transform
+            .transform(metric(&[("user", "b"), ("env", "staging")]))
+            .is_none());
+        // Values of a dropped event are not remembered.
+        assert!(transform
+            .transform(metric(&[("user", "a"), ("env", "staging")]))
+            .is_none());
+        assert!(transform
+            .transform(metric(&[("user", "a"), ("env", "prod")]))
+            .is_some());
+    }
+
+    #[test]
+    fn tag_cardinality_limit_bloom_filter() {
+        let mut filter = BloomFilter::new(8 * 1024, 100);
+        for i in 0..100 {
+            filter.insert(&i.to_string());
+        }
+        assert!((0..100).all(|i| filter.contains(&i.to_string())));
+        let false_positives = (100..1100)
+            .filter(|i| filter.contains(&i.to_string()))
+            .count();
+        assert!(false_positives < 10);
+    }
+
+    #[test]
+    fn tag_cardinality_limit_rejects_zero_limit() {
+        assert!(
+            toml::from_str::<TagCardinalityLimitConfig>("value_limit = 0")
+                .unwrap()
+                .build()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use super::Transform;
+use crate::{
+    event::Event,
+    template::Template,
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("`window_secs` must be greater than zero"))]
+    ZeroWindow,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ThrottleConfig {
+    pub threshold: u64,
+    pub window_secs: u64,
+    pub key_field: Option<Template>,
+    #[serde(default)]
+    pub exceeded_action: ExceededAction,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExceededAction {
+    /// Discard events over the threshold.
+    Drop,
+    /// Pass events over the threshold through with a `throttled` field set,
+    /// so they can be routed elsewhere downstream.
+    Tag,
+}
+
+impl Default for ExceededAction {
+    fn default() -> Self {
+        ExceededAction::Drop
+    }
+}
+
+inventory::submit! {
+    TransformDescription::new_without_default::<ThrottleConfig>("throttle")
+}
+
+#[typetag::serde(name = "throttle")]
+impl TransformConfig for ThrottleConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        if self.window_secs == 0 {
+            return Err(Box::new(BuildError::ZeroWindow));
+        }
+
+        Ok(Box::new(Throttle::new(
+            self.threshold,
+            Duration::from_secs(self.window_secs),
+            self.key_field.clone(),
+            self.exceeded_action,
+        )))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "throttle"
+    }
+}
+
+struct Bucket {
+    window_start: Instant,
+    count: u64,
+}
+
+pub struct Throttle {
+    threshold: u64,
+    window: Duration,
+    key_field: Option<Template>,
+    exceeded_action: ExceededAction,
+    buckets: HashMap<Bytes, Bucket>,
+    last_sweep: Instant,
+}
+
+impl Throttle {
+    pub fn new(
+        threshold: u64,
+        window: Duration,
+        key_field: Option<Template>,
+        exceeded_action: ExceededAction,
+    ) -> Self {
+        Self {
+            threshold,
+            window,
+            key_field,
+            exceeded_action,
+            buckets: HashMap::new(),
+            last_sweep: Instant::now(),
+        }
+    }
+
+    fn key(&self, event: &Event) -> Bytes {
+        match &self.key_field {
+            None => Bytes::new(),
+            Some(template) => template.render(event).unwrap_or_else(|missing_keys| {
+                debug!(
+                    message = "Keys do not exist on the event; using the default bucket.",
+                    ?missing_keys,
+                    rate_limit_secs = 30
+                );
+                Bytes::new()
+            }),
+        }
+    }
+
+    fn transform_at(&mut self, mut event: Event, now: Instant) -> Option<Event> {
+        // Forget about keys that have not been seen for a whole window so
+        // the map does not grow without bound with high cardinality keys.
+        if now.duration_since(self.last_sweep) >= self.window {
+            let window = self.window;
+            self.buckets
+                .retain(|_, bucket| now.duration_since(bucket.window_start) < window);
+            self.last_sweep = now;
+        }
+
+        let key = self.key(&event);
+        let bucket = self.buckets.entry(key).or_insert(Bucket {
+            window_start: now,
+            count: 0,
+        });
+
+        if now.duration_since(bucket.window_start) >= self.window {
+            bucket.window_start = now;
+            bucket.count = 0;
+        }
+
+        bucket.count += 1;
+        if bucket.count <= self.threshold {
+            return Some(event);
+        }
+
+        match self.exceeded_action {
+            ExceededAction::Drop => {
+                trace!(message = "Rate limit exceeded; dropping event.");
+                None
+            }
+            ExceededAction::Tag => {
+                event
+                    .as_mut_log()
+                    .insert_implicit(Atom::from("throttled"), true.into());
+                Some(event)
+            }
+        }
+    }
+}
+
+impl Transform for Throttle {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        self.transform_at(event, Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExceededAction, Throttle};
+    use crate::event::Event;
+    use std::time::{Duration, Instant};
+    use string_cache::DefaultAtom as Atom;
+
+    fn event_for(service: &str) -> Event {
+        let mut event = Event::from("message");
+        event
+            .as_mut_log()
+            .insert_explicit("service".into(), service.into());
+        event
+    }
+
+    #[test]
+    fn throttle_drops_events_over_threshold() {
+        let mut throttle = Throttle::new(2, Duration::from_secs(10), None, ExceededAction::Drop);
+        let now = Instant::now();
+
+        assert!(throttle.transform_at(event_for("a"), now).is_some());
+        assert!(throttle.transform_at(event_for("a"), now).is_some());
+        assert!(throttle.transform_at(event_for("a"), now).is_none());
+        assert!(throttle.transform_at(event_for("b"), now).is_none());
+    }
+
+    #[test]
+    fn throttle_resets_after_window() {
+        let mut throttle = Throttle::new(1, Duration::from_secs(10), None, ExceededAction::Drop);
+        let now = Instant::now();
+
+        assert!(throttle.transform_at(event_for("a"), now).is_some());
+        assert!(throttle
+            .transform_at(event_for("a"), now + Duration::from_secs(5))
+            .is_none());
+        assert!(throttle
+            .transform_at(event_for("a"), now + Duration::from_secs(10))
+            .is_some());
+    }
+
+    #[test]
+    fn throttle_limits_per_key() {
+        let mut throttle = Throttle::new(
+            1,
+            Duration::from_secs(10),
+            Some("{{ service }}".into()),
+            ExceededAction::Drop,
+        );
+        let now = Instant::now();
+
+        assert!(throttle.transform_at(event_for("a"), now).is_some());
+        assert!(throttle.transform_at(event_for("b"), now).is_some());
+        assert!(throttle.transform_at(event_for("a"), now).is_none());
+        assert!(throttle.transform_at(event_for("b"), now).is_none());
+    }
+
+    #[test]
+    fn throttle_tags_events_over_threshold() {
+        let mut throttle = Throttle::new(1, Duration::from_secs(10), None, ExceededAction::Tag);
+        let now = Instant::now();
+
+        let first = throttle.transform_at(event_for("a"), now).unwrap();
+        assert!(first.as_log().get(&Atom::from("throttled")).is_none());
+
+        let second = throttle.transform_at(event_for("a"), now).unwrap();
+        assert_eq!(second.as_log()[&Atom::from("throttled")], true.into());
+    }
+}
@@ -0,0 +1,342 @@
+use super::Transform;
+use crate::{
+    event::{self, Event, ValueKind},
+    topology::config::{DataType, TransformConfig, TransformDescription},
+};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use string_cache::DefaultAtom as Atom;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("At least one timestamp format must be given"))]
+    NoFormats,
+    #[snafu(display("Unknown timezone {:?}: {}", name, message))]
+    UnknownTimezone { name: String, message: String },
+    #[snafu(display(
+        "Timezone names (%Z) can not be parsed, use an offset (%z) instead: {:?}",
+        format
+    ))]
+    ZoneName { format: String },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Derivative)]
+#[serde(deny_unknown_fields, default)]
+#[derivative(Default)]
+pub struct TimestampParserConfig {
+    pub field: Option<Atom>,
+    #[derivative(Default(value = "true"))]
+    pub drop_field: bool,
+    pub formats: Vec<String>,
+    pub timezone: Option<String>,
+}
+
+inventory::submit! {
+    TransformDescription::new::<TimestampParserConfig>("timestamp_parser")
+}
+
+#[typetag::serde(name = "timestamp_parser")]
+impl TransformConfig for TimestampParserConfig {
+    fn build(&self) -> crate::Result<Box<dyn Transform>> {
+        if self.formats.is_empty() {
+            return Err(Box::new(BuildError::NoFormats));
+        }
+        if let Some(format) = self.formats.iter().find(|f| f.contains("%Z")) {
+            return Err(Box::new(BuildError::ZoneName {
+                format: format.clone(),
+            }));
+        }
+
+        let timezone = match &self.timezone {
+            Some(name) => name
+                .parse::<Tz>()
+                .map_err(|message| BuildError::UnknownTimezone {
+                    name: name.clone(),
+                    message,
+                })?,
+            None => Tz::UTC,
+        };
+
+        Ok(Box::new(TimestampParser {
+            field: self
+                .field
+                .clone()
+                .unwrap_or_else(|| event::TIMESTAMP.clone()),
+            drop_field: self.drop_field,
+            formats: self
+                .formats
+                .iter()
+                .map(|f| Format::from(f.as_str()))
+                .collect(),
+            timezone,
+        }))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn transform_type(&self) -> &'static str {
+        "timestamp_parser"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Format {
+    /// Seconds (or fractions thereof) since the UNIX epoch. The value is
+    /// the number of units per second.
+    Epoch(i64),
+    /// A `strftime` format without a zone, interpreted in the configured
+    /// timezone.
+    Local(String),
+    /// A `strftime` format carrying its own offset.
+    Zoned(String),
+}
+
+impl From<&str> for Format {
+    fn from(format: &str) -> Self {
+        match format {
+            "epoch_s" => Format::Epoch(1),
+            "epoch_ms" => Format::Epoch(1_000),
+            "epoch_us" => Format::Epoch(1_000_000),
+            "epoch_ns" => Format::Epoch(1_000_000_000),
+            _ if has_zone(format) => Format::Zoned(format.into()),
+            _ => Format::Local(format.into()),
+        }
+    }
+}
+
+impl Format {
+    fn parse(&self, s: &str, timezone: &Tz) -> Option<DateTime<Utc>> {
+        match self {
+            Format::Epoch(per_second) => parse_epoch(s, *per_second),
+            Format::Local(format) => NaiveDateTime::parse_from_str(s, format)
+                .ok()
+                .and_then(|naive| timezone.from_local_datetime(&naive).earliest())
+                .map(|ts| ts.with_timezone(&Utc)),
+            Format::Zoned(format) => DateTime::parse_from_str(s, format)
+                .ok()
+                .map(|ts| ts.with_timezone(&Utc)),
+        }
+    }
+}
+
+fn has_zone(format: &str) -> bool {
+    ["%z", "%:z", "%#z", "%+"]
+        .iter()
+        .any(|spec| format.contains(spec))
+}
+
+fn parse_epoch(s: &str, per_second: i64) -> Option<DateTime<Utc>> {
+    let nanos_per_unit = 1_000_000_000 / per_second;
+    if let Ok(value) = s.parse::<i64>() {
+        let secs = value.div_euclid(per_second);
+        let nanos = value.rem_euclid(per_second) * nanos_per_unit;
+        Utc.timestamp_opt(secs, nanos as u32).single()
+    } else if let Ok(value) = s.parse::<f64>() {
+        let value = value / per_second as f64;
+        // Casting a float outside of the i64 range is undefined, and NaN
+        // fails both comparisons
+        let in_range = value >= i64::min_value() as f64 && value < i64::max_value() as f64;
+        if !in_range {
+            return None;
+        }
+        let secs = value.floor();
+        let nanos = ((value - secs) * 1e9).round() as u32;
+        Utc.timestamp_opt(secs as i64, nanos.min(999_999_999))
+            .single()
+    } else {
+        None
+    }
+}
+
+pub struct TimestampParser {
+    field: Atom,
+    drop_field: bool,
+    formats: Vec<Format>,
+    timezone: Tz,
+}
+
+impl TimestampParser {
+    fn parse(&self, value: &ValueKind) -> Option<DateTime<Utc>> {
+        if let ValueKind::Timestamp(ts) = value {
+            return Some(*ts);
+        }
+
+        let s = value.to_string_lossy();
+        let s = s.trim();
+        self.formats
+            .iter()
+            .filter_map(|format| format.parse(s, &self.timezone))
+            .next()
+    }
+}
+
+impl Transform for TimestampParser {
+    fn transform(&mut self, mut event: Event) -> Option<Event> {
+        let value = match event.as_log().get(&self.field) {
+            Some(value) => value,
+            None => {
+                debug!(
+                    message = "Field does not exist.",
+                    field = self.field.as_ref(),
+                );
+                return Some(event);
+            }
+        };
+
+        match self.parse(value) {
+            Some(timestamp) => {
+                if self.drop_field {
+                    event.as_mut_log().remove(&self.field);
+                }
+                event
+                    .as_mut_log()
+                    .insert_implicit(event::TIMESTAMP.clone(), timestamp.into());
+            }
+            None => {
+                debug!(
+                    message = "No matching timestamp format found.",
+                    field = self.field.as_ref(),
+                    value = &value.to_string_lossy()[..],
+                    rate_limit_secs = 30
+                );
+            }
+        }
+
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimestampParserConfig;
+    use crate::{
+        event::{LogEvent, ValueKind},
+        topology::config::TransformConfig,
+        Event,
+    };
+    use chrono::{TimeZone, Utc};
+
+    fn do_transform(value: ValueKind, config: &str) -> LogEvent {
+        let mut event = Event::new_empty_log();
+        event.as_mut_log().insert_explicit("time".into(), value);
+        let mut parser = toml::from_str::<TimestampParserConfig>(config)
+            .unwrap()
+            .build()
+            .unwrap();
+        parser.transform(event).unwrap().into_log()
+    }
+
+    #[test]
+    fn timestamp_parser_tries_formats_in_order() {
+        let config = r#"
+            field = "time"
+            formats = ["%d/%b/%Y:%H:%M:%S %z", "%Y-%m-%d %H:%M:%S"]
+        "#;
+
+        let log = do_transform("10/Oct/2000:13:55:36 -0700".into(), config);
+        assert_eq!(
+            log[&"timestamp".into()],
+            Utc.ymd(2000, 10, 10).and_hms(20, 55, 36).into()
+        );
+        assert!(log.get(&"time".into()).is_none());
+
+        let log = do_transform("2019-03-04 05:06:07".into(), config);
+        assert_eq!(
+            log[&"timestamp".into()],
+            Utc.ymd(2019, 3, 4).and_hms(5, 6, 7).into()
+        );
+    }
+
+    #[test]
+    fn timestamp_parser_epoch_units() {
+        let log = do_transform(
+            "1553184000123".into(),
+            "field = \"time\"\nformats = [\"epoch_ms\"]",
+        );
+        assert_eq!(
+            log[&"timestamp".into()],
+            Utc.ymd(2019, 3, 21).and_hms_milli(16, 0, 0, 123).into()
+        );
+
+        let log = do_transform(
+            ValueKind::Integer(1553184000),
+            "field = \"time\"\nformats = [\"epoch_s\"]",
+        );
+        assert_eq!(
+            log[&"timestamp".into()],
+            Utc.ymd(2019, 3, 21).and_hms(16, 0, 0).into()
+        );
+
+        let log = do_transform(
+            "1553184000.5".into(),
+            "field = \"time\"\nformats = [\"epoch_s\"]",
+        );
+        assert_eq!(
+            log[&"timestamp".into()],
+            Utc.ymd(2019, 3, 21).and_hms_milli(16, 0, 0, 500).into()
+        );
+    }
+
+    #[test]
+    fn timestamp_parser_named_timezone() {
+        let log = do_transform(
+            "2019-07-01 12:00:00".into(),
+            r#"
+            field = "time"
+            formats = ["%Y-%m-%d %H:%M:%S"]
+            timezone = "Europe/Berlin"
+            "#,
+        );
+        assert_eq!(
+            log[&"timestamp".into()],
+            Utc.ymd(2019, 7, 1).and_hms(10, 0, 0).into()
+        );
+    }
+
+    #[test]
+    fn timestamp_parser_keeps_unparsable_events() {
+        let log = do_transform(
+            "yesterday".into(),
+            "field = \"time\"\nformats = [\"epoch_s\"]",
+        );
+        assert_eq!(log[&"time".into()], "yesterday".into());
+        assert!(log.get(&"timestamp".into()).is_none());
+    }
+
+    #[test]
+    fn timestamp_parser_rejects_invalid_epochs() {
+        for value in &["inf", "-inf", "NaN", "1e300", "-1e300"] {
+            let log = do_transform((*value).into(), "field = \"time\"\nformats = [\"epoch_s\"]");
+            assert_eq!(log[&"time".into()], (*value).into());
+            assert!(log.get(&"timestamp".into()).is_none());
+        }
+    }
+
+    #[test]
+    fn timestamp_parser_rejects_bad_config() {
+        assert!(toml::from_str::<TimestampParserConfig>("")
+            .unwrap()
+            .build()
+            .is_err());
+        assert!(toml::from_str::<TimestampParserConfig>(
+            "formats = [\"epoch_s\"]\ntimezone = \"Mars/Olympus\""
+        )
+        .unwrap()
+        .build()
+        .is_err());
+        assert!(
+            toml::from_str::<TimestampParserConfig>("formats = [\"%Y-%m-%d %H:%M:%S %Z\"]")
+                .unwrap()
+                .build()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// The contents of a file, loaded again whenever the file changes. The file
+/// is checked at most once per `check_interval`.
+pub struct WatchedFile<T> {
+    path: PathBuf,
+    contents: T,
+    version: Option<(SystemTime, u64)>,
+    check_interval: Duration,
+    last_checked: Instant,
+}
+
+impl<T> WatchedFile<T> {
+    pub fn load<E>(
+        path: &Path,
+        check_interval: Duration,
+        load: impl FnOnce(&Path) -> Result<T, E>,
+    ) -> Result<Self, E> {
+        // Taken before loading, so changes made meanwhile are picked up later
+        let version = version(path);
+        let contents = load(path)?;
+
+        Ok(Self {
+            path: path.into(),
+            contents,
+            version,
+            check_interval,
+            last_checked: Instant::now(),
+        })
+    }
+
+    /// Returns the contents, first loading them again with `load` if the
+    /// file changed since they were last loaded.
+    pub fn get<E: Display>(&mut self, load: impl FnOnce(&Path) -> Result<T, E>) -> &T {
+        let now = Instant::now();
+        if now.duration_since(self.last_checked) >= self.check_interval {
+            self.last_checked = now;
+            self.reload(load);
+        }
+        &self.contents
+    }
+
+    fn reload<E: Display>(&mut self, load: impl FnOnce(&Path) -> Result<T, E>) {
+        let version = version(&self.path);
+        if version == self.version {
+            return;
+        }
+
+        match load(&self.path) {
+            Ok(contents) => {
+                info!(message = "Reloaded file.", path = ?self.path);
+                self.contents = contents;
+                self.version = version;
+            }
+            // Keep the old contents, the file may still be being written.
+            Err(error) => error!(
+                message = "Unable to reload file.",
+                path = ?self.path,
+                %error,
+                rate_limit_secs = 30
+            ),
+        }
+    }
+}
+
+/// Identifies the contents of the file on disk, to detect when it changes.
+fn version(path: &Path) -> Option<(SystemTime, u64)> {
+    std::fs::metadata(path)
+        .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+        .ok()
+}
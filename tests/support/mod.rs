@@ -100,6 +100,7 @@ impl Transform for MockTransform {
                 val,
                 timestamp: _,
                 tags: _,
+                kind: _,
             }) => {
                 *val += self.increase;
             }
@@ -109,15 +110,16 @@ impl Transform for MockTransform {
                 sample_rate: _,
                 timestamp: _,
                 tags: _,
+                kind: _,
             }) => {
                 *val += self.increase;
             }
             Event::Metric(Metric::Gauge {
                 name: _,
                 val,
-                direction: _,
                 timestamp: _,
                 tags: _,
+                kind: _,
             }) => {
                 *val += self.increase;
             }
@@ -126,9 +128,14 @@ impl Transform for MockTransform {
                 val,
                 timestamp: _,
                 tags: _,
+                kind: _,
             }) => {
                 val.push_str(&self.suffix);
             }
+            Event::Metric(Metric::AggregatedHistogram { sum, .. })
+            | Event::Metric(Metric::AggregatedSummary { sum, .. }) => {
+                *sum += self.increase;
+            }
         };
         Some(event)
     }